@@ -0,0 +1,30 @@
+//! Measures both engines on day 9 part 2, the program of the Performance section of the README.
+//! Run with `cargo bench`.
+
+use std::time::{Duration, Instant};
+
+use intcode::blocks::Engine;
+use intcode::loader;
+use intcode::src::{Interpreter, RunResult};
+
+const RUNS: u32 = 20;
+
+fn main() {
+    let code = loader::parse(include_str!("../data/day9.txt")).unwrap();
+
+    for engine in [Engine::Step, Engine::Blocks] {
+        let mut times = vec![];
+        let mut instructions = 0;
+        for _ in 0..RUNS {
+            let mut pc = Interpreter::new(code.to_owned(), vec![2].into()).with_engine(engine);
+            let start = Instant::now();
+            assert_eq!(pc.run(), Ok(RunResult::Halted));
+            times.push(start.elapsed());
+            instructions = pc.usage().instructions;
+        }
+
+        let mean = times.iter().sum::<Duration>() / RUNS;
+        println!("{:?}: {} runs of {} instructions, mean {:?}, fastest {:?}, {:.1} million instructions per second",
+                 engine, RUNS, instructions, mean, times.iter().min().unwrap(), instructions as f64 / mean.as_secs_f64() / 1e6);
+    }
+}
@@ -0,0 +1,115 @@
+//! Machines connected in a chain or a ring, each feeding its output into the input of the next.
+
+use std::thread;
+
+use itertools::Itertools;
+
+use crate::src::{ErrorKind, Interpreter, InterpreterError, RunResult, VALUE};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Topology {
+    /// The output of the last machine is the result.
+    Chain,
+    /// The output of the last machine is fed back into the first one.
+    Ring,
+}
+
+pub struct Amplifiers {
+    pub machines: Vec<Interpreter>,
+    topology: Topology,
+}
+
+impl Amplifiers {
+    /// Creates one machine per prefix, which is the first input of that machine.
+    pub fn new(code: &[VALUE], prefixes: Vec<Vec<VALUE>>, topology: Topology) -> Amplifiers {
+        Amplifiers {
+            machines: prefixes.into_iter().map(|prefix| Interpreter::new(code.to_owned(), prefix.into())).collect(),
+            topology,
+        }
+    }
+
+    /// One machine per phase setting, connected in a chain.
+    pub fn chain(code: &[VALUE], phases: &[VALUE]) -> Amplifiers {
+        Amplifiers::new(code, phases.iter().map(|&phase| vec![phase]).collect(), Topology::Chain)
+    }
+
+    /// One machine per phase setting, connected in a ring.
+    pub fn ring(code: &[VALUE], phases: &[VALUE]) -> Amplifiers {
+        Amplifiers::new(code, phases.iter().map(|&phase| vec![phase]).collect(), Topology::Ring)
+    }
+
+    /// Sends `signal` to the first machine and runs all machines until they halt. Returns the
+    /// last output of the last machine, if there was any.
+    ///
+    /// Fails with `ErrorKind::NoInput` if the machines wait for each other without halting.
+    pub fn run(&mut self, signal: VALUE) -> Result<Option<VALUE>, InterpreterError> {
+        let n = self.machines.len();
+        if n == 0 {
+            return Ok(Some(signal));
+        }
+        self.machines[0].input_buffer.push_back(signal);
+
+        let mut last = None;
+        loop {
+            let mut progress = false;
+            for k in 0..n {
+                // Runs the machine until it needs input or halts
+                loop {
+                    let val = match self.machines[k].step_loop()? {
+                        RunResult::Output(val) => val,
+                        RunResult::NeedsInput | RunResult::Halted => break,
+                        RunResult::BudgetExhausted(limit) => return Err(self.machines[k].fault(ErrorKind::BudgetExhausted(limit), None)),
+                    };
+                    progress = true;
+                    if k + 1 < n {
+                        self.machines[k + 1].input_buffer.push_back(val);
+                    } else {
+                        last = Some(val);
+                        if self.topology == Topology::Ring {
+                            self.machines[0].input_buffer.push_back(val);
+                        }
+                    }
+                }
+            }
+
+            if self.machines.iter().all(|pc| pc.finish) {
+                return Ok(last);
+            }
+            // Input only ever comes from output, so nothing will change anymore
+            if !progress {
+                let stuck = self.machines.iter().find(|pc| !pc.finish).unwrap();
+                return Err(stuck.fault(ErrorKind::NoInput, Some(0)));
+            }
+        }
+    }
+}
+
+/// Runs the amplifiers for every permutation of `phases`, spread over all cores, and returns
+/// the highest final signal together with the phase settings producing it.
+pub fn search(code: &[VALUE], phases: &[VALUE], topology: Topology) -> Result<Option<(VALUE, Vec<VALUE>)>, InterpreterError> {
+    let permutations: Vec<Vec<VALUE>> = phases.iter().copied().permutations(phases.len()).collect();
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = permutations.len().div_ceil(threads).max(1);
+
+    let best = thread::scope(|scope| {
+        let workers: Vec<_> = permutations
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || -> Result<Option<(VALUE, Vec<VALUE>)>, InterpreterError> {
+                let mut best: Option<(VALUE, Vec<VALUE>)> = None;
+                for phases in chunk {
+                    let prefixes = phases.iter().map(|&phase| vec![phase]).collect();
+                    if let Some(signal) = Amplifiers::new(code, prefixes, topology).run(0)? {
+                        if best.as_ref().is_none_or(|(highest, _)| signal > *highest) {
+                            best = Some((signal, phases.to_owned()));
+                        }
+                    }
+                }
+                Ok(best)
+            }))
+            .collect();
+
+        workers.into_iter().map(|worker| worker.join().unwrap()).collect::<Result<Vec<_>, _>>()
+    })?;
+
+    Ok(best.into_iter().flatten().max_by_key(|(signal, _)| *signal))
+}
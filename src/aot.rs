@@ -0,0 +1,389 @@
+//! Ahead-of-time translation of programs into Rust source.
+//!
+//! `translate` emits a module with a state machine over `ip`, with one arm per instruction
+//! found by following the control flow of the program. The module is compiled together with
+//! the crate, see `src/compiled`, and run with `Compiled`.
+//!
+//! The compiled code assumes that its instructions never change. Whenever it can not continue
+//! natively, it hands the machine over to an `Interpreter`, before the current instruction has
+//! any effect:
+//! 1. On a write to a cell of a compiled instruction, ie. self-modifying code.
+//! 2. On a jump to an address without a compiled instruction.
+//! 3. On any fault, eg. an overflow, which the interpreter then reports.
+
+use std::collections::{BTreeSet, VecDeque};
+use std::error::Error;
+use std::fmt::{Display, Write};
+
+use crate::disassembler::{self, Decoded, Mode, Operand};
+use crate::memory::Memory;
+use crate::snapshot::Snapshot;
+use crate::src::{Interpreter, InterpreterError, RunResult, VALUE};
+
+/// Values per line of the `CODE` array.
+const CODE_PER_LINE: usize = 16;
+
+/// What to do about instructions which write into compiled code.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Policy {
+    /// Refuse programs with writes to compiled code at addresses known at translation time.
+    Refuse,
+    /// Compile every program, and fall back to the interpreter once the code is modified.
+    Fallback,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AotError {
+    /// The instruction at `ip` writes to the compiled instruction at `address`.
+    SelfModifying { ip: usize, address: usize },
+}
+
+impl Display for AotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AotError::SelfModifying { ip, address } => write!(f, "ip {}: Self-modifying code, writes to the instruction at {}", ip, address),
+        }
+    }
+}
+
+impl Error for AotError {}
+
+/// Why the compiled code returned control.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Exit {
+    Output(VALUE),
+    NeedsInput,
+    Halted,
+    /// The current instruction can not be executed natively, see the module documentation.
+    Fallback,
+}
+
+/// The state of a machine running compiled code, with a dense memory.
+pub struct Native {
+    pub memory: Vec<VALUE>,
+    pub ip: usize,
+    pub relative_base: VALUE,
+    pub input_buffer: VecDeque<VALUE>,
+    /// One bit per cell of a compiled instruction.
+    compiled: &'static [u64],
+}
+
+impl Native {
+    #[inline]
+    pub fn load(&self, address: usize) -> VALUE {
+        self.memory.get(address).copied().unwrap_or(0)
+    }
+
+    #[inline]
+    pub fn store(&mut self, address: usize, val: VALUE) {
+        if address >= self.memory.len() {
+            self.memory.resize(address + 1, 0);
+        }
+        self.memory[address] = val;
+    }
+
+    /// The address of a parameter in relative mode, or `None` if it is negative.
+    #[inline]
+    pub fn relative(&self, offset: VALUE) -> Option<usize> {
+        offset.checked_add(self.relative_base).filter(|&address| address >= 0).map(|address| address as usize)
+    }
+
+    /// Whether `address` is not part of a compiled instruction.
+    #[inline]
+    pub fn writable(&self, address: usize) -> bool {
+        self.compiled.get(address / 64).is_none_or(|bits| bits & (1 << (address % 64)) == 0)
+    }
+}
+
+/// A program translated by `translate`, running natively until it has to fall back to the
+/// interpreter. Tracers and budgets are not supported.
+pub struct Compiled {
+    native: Native,
+    resume: fn(&mut Native) -> Exit,
+    finish: bool,
+    last_output: Option<VALUE>,
+    /// The interpreter which took over, if any.
+    interpreter: Option<Interpreter>,
+}
+
+impl Compiled {
+    /// Called by the generated `machine` function of a compiled module.
+    pub fn new(code: &[VALUE], compiled: &'static [u64], resume: fn(&mut Native) -> Exit, input_buffer: VecDeque<VALUE>) -> Compiled {
+        Compiled {
+            native: Native { memory: code.to_owned(), ip: 0, relative_base: 0, input_buffer, compiled },
+            resume,
+            finish: false,
+            last_output: None,
+            interpreter: None,
+        }
+    }
+
+    /// Runs the program until it produces output, needs input, or halts, like
+    /// `Interpreter::step_loop`.
+    pub fn step_loop(&mut self) -> Result<RunResult, InterpreterError> {
+        if let Some(pc) = self.interpreter.as_mut() {
+            return pc.step_loop();
+        }
+        if self.finish {
+            return Ok(RunResult::Halted);
+        }
+
+        match (self.resume)(&mut self.native) {
+            Exit::Output(val) => {
+                self.last_output = Some(val);
+                Ok(RunResult::Output(val))
+            },
+            Exit::NeedsInput => Ok(RunResult::NeedsInput),
+            Exit::Halted => {
+                self.finish = true;
+                Ok(RunResult::Halted)
+            },
+            Exit::Fallback => self.fall_back().step_loop(),
+        }
+    }
+
+    /// Hands the machine over to an interpreter, which executes the current instruction again.
+    fn fall_back(&mut self) -> &mut Interpreter {
+        let native = &mut self.native;
+        let last_output = self.last_output;
+        self.interpreter.get_or_insert_with(|| Interpreter::from_snapshot(Snapshot {
+            memory: Memory::Dense(std::mem::take(&mut native.memory)),
+            ip: native.ip,
+            relative_base: native.relative_base as isize,
+            finish: false,
+            input_buffer: std::mem::take(&mut native.input_buffer),
+            last_output,
+        }))
+    }
+
+    pub fn push_input(&mut self, val: VALUE) {
+        match self.interpreter.as_mut() {
+            Some(pc) => pc.input_buffer.push_back(val),
+            None => self.native.input_buffer.push_back(val),
+        }
+    }
+
+    /// The value at `address`.
+    pub fn read(&self, address: usize) -> VALUE {
+        match self.interpreter.as_ref() {
+            Some(pc) => pc.memory().read(address),
+            None => self.native.load(address),
+        }
+    }
+
+    /// Whether the program still runs natively.
+    pub fn is_native(&self) -> bool {
+        self.interpreter.is_none()
+    }
+}
+
+/// Addresses of the instructions to compile. Besides the control flow from address 0, which
+/// `disassembler::reachable` follows, immediate values and the initial targets of jumps in
+/// position mode are taken as further entry points, eg. return addresses pushed before a call.
+pub fn entry_points(code: &[VALUE]) -> BTreeSet<usize> {
+    let mut roots = BTreeSet::from([0]);
+    loop {
+        let starts = disassembler::reachable_from(code, roots.iter().copied());
+        let mut covered = vec![false; code.len()];
+        let decoded: Vec<Decoded> = starts.iter().filter_map(|&address| disassembler::decode(code, address)).collect();
+        for instruction in &decoded {
+            covered[instruction.address..instruction.address + instruction.size()].iter_mut().for_each(|x| *x = true);
+        }
+
+        let mut candidates = vec![];
+        for instruction in &decoded {
+            for operand in &instruction.operands {
+                if operand.mode == Mode::Immediate {
+                    candidates.push(operand.value);
+                }
+            }
+            if matches!(instruction.instruction.opcode, 5 | 6) && instruction.operands[1].mode == Mode::Position {
+                candidates.extend(usize::try_from(instruction.operands[1].value).ok().and_then(|k| code.get(k).copied()));
+            }
+        }
+
+        let before = roots.len();
+        roots.extend(candidates
+            .into_iter()
+            .filter_map(|val| usize::try_from(val).ok())
+            .filter(|&k| k < code.len() && !covered[k] && disassembler::decode(code, k).is_some()));
+        if roots.len() == before {
+            return starts;
+        }
+    }
+}
+
+/// The statements executing `instruction`.
+fn arm(instruction: &Decoded, compiled: &[bool]) -> Vec<String> {
+    let next = instruction.address + instruction.size();
+    let names = ["a", "b", "c"];
+    let operands = &instruction.operands;
+    let opcode = instruction.instruction.opcode;
+
+    // The result of arithmetic on immediate values only, computed now
+    let folded = match (opcode, &operands[..]) {
+        (1, [a, b, _]) if a.mode == Mode::Immediate && b.mode == Mode::Immediate => Some(a.value.checked_add(b.value)),
+        (2, [a, b, _]) if a.mode == Mode::Immediate && b.mode == Mode::Immediate => Some(a.value.checked_mul(b.value)),
+        (7, [a, b, _]) if a.mode == Mode::Immediate && b.mode == Mode::Immediate => Some(Some((a.value < b.value) as VALUE)),
+        (8, [a, b, _]) if a.mode == Mode::Immediate && b.mode == Mode::Immediate => Some(Some((a.value == b.value) as VALUE)),
+        _ => None,
+    };
+
+    // Faults on negative addresses and overflows, and writes to compiled instructions, known in advance
+    let negative = operands.iter().any(|operand| operand.mode == Mode::Position && operand.value < 0);
+    let modifies = instruction.instruction.writes.is_some_and(|k| {
+        operands[k].mode == Mode::Position && compiled.get(operands[k].value as usize) == Some(&true)
+    });
+    if negative || modifies || folded == Some(None) {
+        return vec!["return Exit::Fallback;".to_owned()];
+    }
+
+    // Relative addresses are resolved first, as the interpreter validates all parameters up front
+    let mut body = vec![];
+    for (operand, name) in operands.iter().zip(names) {
+        if operand.mode == Mode::Relative {
+            body.push(format!("let Some({}) = m.relative({}) else {{ return Exit::Fallback }};", name, operand.value));
+        }
+    }
+    if let Some(k) = instruction.instruction.writes.filter(|&k| operands[k].mode == Mode::Relative) {
+        body.push(format!("if !m.writable({}) {{ return Exit::Fallback }}", names[k]));
+    }
+
+    let read = |k: usize| match operands[k].mode {
+        Mode::Immediate => operands[k].value.to_string(),
+        Mode::Position => format!("m.load({})", operands[k].value),
+        Mode::Relative => format!("m.load({})", names[k]),
+    };
+    let target = |k: usize| match operands[k].mode {
+        Mode::Relative => names[k].to_owned(),
+        _ => operands[k].value.to_string(),
+    };
+
+    match opcode {
+        1 | 2 | 7 | 8 if folded.is_some() => {
+            body.push(format!("m.store({}, {});", target(2), folded.flatten().unwrap()));
+            body.push(format!("m.ip = {};", next));
+        },
+        1 | 2 => {
+            let op = if opcode == 1 { "checked_add" } else { "checked_mul" };
+            body.push(format!("let Some(val) = VALUE::{}({}, {}) else {{ return Exit::Fallback }};", op, read(0), read(1)));
+            body.push(format!("m.store({}, val);", target(2)));
+            body.push(format!("m.ip = {};", next));
+        },
+        3 => {
+            body.push("let Some(val) = m.input_buffer.pop_front() else { return Exit::NeedsInput };".to_owned());
+            body.push(format!("m.store({}, val);", target(0)));
+            body.push(format!("m.ip = {};", next));
+        },
+        4 => {
+            body.push(format!("let val = {};", read(0)));
+            body.push(format!("m.ip = {};", next));
+            body.push("return Exit::Output(val);".to_owned());
+        },
+        5 | 6 => {
+            let jump = match operands[1] {
+                Operand { mode: Mode::Immediate, value } if value < 0 => "return Exit::Fallback;".to_owned(),
+                Operand { mode: Mode::Immediate, value } => format!("m.ip = {};", value),
+                _ => format!("let target = {};\n    if target < 0 {{ return Exit::Fallback }}\n    m.ip = target as usize;", read(1)),
+            };
+            let condition = if opcode == 5 { "!=" } else { "==" };
+            match operands[0] {
+                // Conditions in immediate mode are decided at translation time
+                Operand { mode: Mode::Immediate, value } if (value != 0) == (opcode == 5) => body.push(jump.replace("\n    ", "\n")),
+                Operand { mode: Mode::Immediate, .. } => body.push(format!("m.ip = {};", next)),
+                _ => body.push(format!("if {} {} 0 {{\n    {}\n}} else {{\n    m.ip = {};\n}}", read(0), condition, jump, next)),
+            }
+        },
+        7 | 8 => {
+            let op = if opcode == 7 { "<" } else { "==" };
+            body.push(format!("let val = ({} {} {}) as VALUE;", read(0), op, read(1)));
+            body.push(format!("m.store({}, val);", target(2)));
+            body.push(format!("m.ip = {};", next));
+        },
+        9 => {
+            body.push(format!("let Some(base) = m.relative_base.checked_add({}) else {{ return Exit::Fallback }};", read(0)));
+            body.push("m.relative_base = base;".to_owned());
+            body.push(format!("m.ip = {};", next));
+        },
+        _ => body.push("return Exit::Halted;".to_owned()),
+    }
+
+    body.iter().flat_map(|statement| statement.lines()).map(str::to_owned).collect()
+}
+
+/// Translates `code` into the source of a Rust module, see the module documentation. `source`
+/// names the program in the header of the module.
+pub fn translate(code: &[VALUE], source: &str, policy: Policy) -> Result<String, AotError> {
+    let instructions: Vec<Decoded> = entry_points(code).into_iter().filter_map(|address| disassembler::decode(code, address)).collect();
+    let mut compiled = vec![false; code.len()];
+    for instruction in &instructions {
+        compiled[instruction.address..instruction.address + instruction.size()].iter_mut().for_each(|x| *x = true);
+    }
+
+    if policy == Policy::Refuse {
+        for instruction in &instructions {
+            if let Some(k) = instruction.instruction.writes {
+                let Operand { mode, value } = instruction.operands[k];
+                if mode == Mode::Position && compiled.get(value as usize) == Some(&true) {
+                    return Err(AotError::SelfModifying { ip: instruction.address, address: value as usize });
+                }
+            }
+        }
+    }
+
+    let mut out = String::new();
+    writeln!(out, "//! Generated by `intcode compile {}`, do not edit.", source).unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "use std::collections::VecDeque;").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "use crate::aot::{{Compiled, Exit, Native}};").unwrap();
+    writeln!(out, "use crate::src::VALUE;").unwrap();
+    writeln!(out).unwrap();
+
+    writeln!(out, "pub static CODE: [VALUE; {}] = [", code.len()).unwrap();
+    for values in code.chunks(CODE_PER_LINE) {
+        writeln!(out, "    {},", values.iter().map(|val| val.to_string()).collect::<Vec<_>>().join(", ")).unwrap();
+    }
+    writeln!(out, "];").unwrap();
+    writeln!(out).unwrap();
+
+    let bits: Vec<String> = compiled
+        .chunks(64)
+        .map(|cells| format!("{:#018x}", cells.iter().enumerate().filter(|(_, &x)| x).fold(0u64, |bits, (k, _)| bits | 1 << k)))
+        .collect();
+    writeln!(out, "/// One bit per cell of a compiled instruction.").unwrap();
+    writeln!(out, "const COMPILED: [u64; {}] = [", bits.len()).unwrap();
+    for bits in bits.chunks(4) {
+        writeln!(out, "    {},", bits.join(", ")).unwrap();
+    }
+    writeln!(out, "];").unwrap();
+    writeln!(out).unwrap();
+
+    writeln!(out, "pub fn machine(input_buffer: VecDeque<VALUE>) -> Compiled {{").unwrap();
+    writeln!(out, "    Compiled::new(&CODE, &COMPILED, resume, input_buffer)").unwrap();
+    writeln!(out, "}}").unwrap();
+    writeln!(out).unwrap();
+
+    let arms: Vec<Vec<String>> = instructions.iter().map(|instruction| arm(instruction, &compiled)).collect();
+    if arms.iter().all(|body| body.last().is_some_and(|line| line.starts_with("return"))) {
+        writeln!(out, "#[allow(clippy::never_loop)]").unwrap();
+    }
+    writeln!(out, "fn resume(m: &mut Native) -> Exit {{").unwrap();
+    writeln!(out, "    loop {{").unwrap();
+    writeln!(out, "        match m.ip {{").unwrap();
+    for (instruction, body) in instructions.iter().zip(arms) {
+        let operands: Vec<String> = instruction.operands.iter().map(|operand| operand.to_string()).collect();
+        writeln!(out, "            // {} {}", instruction.instruction.mnemonic(), operands.join(", ")).unwrap();
+        writeln!(out, "            {} => {{", instruction.address).unwrap();
+        for line in body {
+            writeln!(out, "                {}", line).unwrap();
+        }
+        writeln!(out, "            }},").unwrap();
+    }
+    writeln!(out, "            _ => return Exit::Fallback,").unwrap();
+    writeln!(out, "        }}").unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out, "}}").unwrap();
+
+    Ok(out)
+}
@@ -0,0 +1,123 @@
+//! Text based programs, which read and write ASCII characters one value at a time.
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt::Display;
+use std::io::{self, BufRead, Write};
+
+use crate::src::{ErrorKind, Interpreter, InterpreterError, RunResult, VALUE};
+
+/// What the program did next, see `Console::next_event`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// A line of text, without the newline. A partial line is flushed before any other event.
+    Line(String),
+    /// A value outside of the ASCII range, eg. a final result.
+    Number(VALUE),
+    NeedsInput,
+    Halted,
+}
+
+#[derive(Debug)]
+pub enum ConsoleError {
+    Io(io::Error),
+    Fault(InterpreterError),
+}
+
+impl Display for ConsoleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConsoleError::Io(e) => write!(f, "{}", e),
+            ConsoleError::Fault(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for ConsoleError {}
+
+impl From<io::Error> for ConsoleError {
+    fn from(e: io::Error) -> ConsoleError {
+        ConsoleError::Io(e)
+    }
+}
+
+impl From<InterpreterError> for ConsoleError {
+    fn from(e: InterpreterError) -> ConsoleError {
+        ConsoleError::Fault(e)
+    }
+}
+
+/// Line buffered ASCII input and output around an `Interpreter`.
+pub struct Console {
+    pub pc: Interpreter,
+    /// The characters of the current, unfinished output line.
+    line: String,
+    /// Events which are due after the current partial line has been flushed.
+    pending: VecDeque<Event>,
+}
+
+impl Console {
+    pub fn new(pc: Interpreter) -> Console {
+        Console { pc, line: String::new(), pending: VecDeque::new() }
+    }
+
+    /// Queues the characters of `line` followed by a newline as input.
+    pub fn send_line(&mut self, line: &str) {
+        self.pc.input_buffer.extend(line.chars().map(|c| c as VALUE));
+        self.pc.input_buffer.push_back('\n' as VALUE);
+    }
+
+    /// Runs the program until it completes a line, outputs a number, needs input or halts.
+    /// An exhausted budget is reported as `ErrorKind::BudgetExhausted`.
+    pub fn next_event(&mut self) -> Result<Event, InterpreterError> {
+        if let Some(event) = self.pending.pop_front() {
+            return Ok(event);
+        }
+
+        loop {
+            let event = match self.pc.step_loop()? {
+                RunResult::Output(10) => return Ok(Event::Line(std::mem::take(&mut self.line))),
+                RunResult::Output(val @ 0..=127) => { self.line.push(val as u8 as char); continue; },
+                RunResult::Output(val) => Event::Number(val),
+                RunResult::NeedsInput => Event::NeedsInput,
+                RunResult::Halted => Event::Halted,
+                // The partial line is kept, for when the machine is resumed
+                RunResult::BudgetExhausted(limit) => return Err(self.pc.fault(ErrorKind::BudgetExhausted(limit), None)),
+            };
+
+            if self.line.is_empty() {
+                return Ok(event);
+            }
+            self.pending.push_back(event);
+            return Ok(Event::Line(std::mem::take(&mut self.line)));
+        }
+    }
+
+    /// Runs the program until it halts, printing its output to `out`. Whenever the program needs
+    /// input, the next line of `input` is sent and echoed to `out`, if `echo` is set. Returns
+    /// `RunResult::NeedsInput` if `input` runs out first.
+    ///
+    /// A script can be followed by interactive input, eg. `script.chain(io::stdin().lock())`.
+    pub fn drive(&mut self, input: impl BufRead, mut out: impl Write, echo: bool) -> Result<RunResult, ConsoleError> {
+        let mut lines = input.lines();
+        loop {
+            match self.next_event()? {
+                Event::Line(line) => writeln!(out, "{}", line)?,
+                Event::Number(val) => writeln!(out, "{}", val)?,
+                Event::Halted => return Ok(RunResult::Halted),
+                Event::NeedsInput => {
+                    out.flush()?;
+                    let Some(line) = lines.next() else {
+                        return Ok(RunResult::NeedsInput);
+                    };
+                    let line = line?;
+                    let line = line.trim_end_matches('\r');
+                    if echo {
+                        writeln!(out, "{}", line)?;
+                    }
+                    self.send_line(line);
+                },
+            }
+        }
+    }
+}
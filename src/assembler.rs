@@ -0,0 +1,355 @@
+//! Assembles a textual Intcode assembly language into code for `Interpreter::new`.
+//!
+//! ```text
+//! ; Outputs the sum of two inputs.
+//! start:  input   a               ; position mode, the cell labelled `a`
+//!         input   b
+//!         add     a, b, @0        ; relative mode, relative base + 0
+//!         output  @0
+//!         jump-if-false #0, #end  ; immediate mode
+//! a:      .data   0
+//! b:      .data   0
+//! end:    halt
+//! ```
+//!
+//! Every line consists of optional labels (`name:`), an optional statement, and an optional
+//! comment starting with `;`. A statement is either an instruction or a directive.
+//!
+//! Instructions use the names of `OPCODES`, with spaces replaced by dashes (`less-than`,
+//! `relative-base-offset`), or one of the short aliases in `ALIASES`. Operands are separated
+//! by commas. A bare operand is in position mode, `#` selects immediate mode and `@` relative
+//! mode. An operand is a number, a label, or a sum of those, eg. `#table+2`.
+//!
+//! Directives:
+//! - `.data 1, -2, label` emits the given values.
+//! - `.ascii "text\n"` emits the characters of the string, supporting the escapes `\n`, `\"`
+//!   and `\\`.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Display;
+
+use crate::src::{Instruction, OPCODES, VALUE};
+
+/// Short mnemonics for the instructions in `OPCODES`.
+const ALIASES: [(&str, u8); 10] = [
+    ("hlt", 99),
+    ("add", 1),
+    ("mul", 2),
+    ("in", 3),
+    ("out", 4),
+    ("jt", 5),
+    ("jf", 6),
+    ("lt", 7),
+    ("eq", 8),
+    ("arb", 9),
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AsmErrorKind {
+    UnknownMnemonic(String),
+    UnknownDirective(String),
+    WrongOperandCount { mnemonic: String, expected: usize, found: usize },
+    InvalidOperand(String),
+    ImmediateWrite { mnemonic: String, parameter: usize },
+    InvalidLabel(String),
+    DuplicateLabel(String),
+    UndefinedLabel(String),
+    InvalidString(String),
+    Overflow,
+}
+
+/// An error while assembling, together with the (1-based) line it occurred in.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AsmError {
+    pub line: usize,
+    pub kind: AsmErrorKind,
+}
+
+impl Display for AsmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            AsmErrorKind::UnknownMnemonic(s) => write!(f, "Unknown mnemonic `{}`", s),
+            AsmErrorKind::UnknownDirective(s) => write!(f, "Unknown directive `{}`", s),
+            AsmErrorKind::WrongOperandCount { mnemonic, expected, found } =>
+                write!(f, "`{}` takes {} operands, found {}", mnemonic, expected, found),
+            AsmErrorKind::InvalidOperand(s) => write!(f, "Invalid operand `{}`", s),
+            AsmErrorKind::ImmediateWrite { mnemonic, parameter } =>
+                write!(f, "`{}` writes to operand {}, which can not be in immediate mode", mnemonic, parameter + 1),
+            AsmErrorKind::InvalidLabel(s) => write!(f, "Invalid label `{}`", s),
+            AsmErrorKind::DuplicateLabel(s) => write!(f, "Label `{}` is already defined", s),
+            AsmErrorKind::UndefinedLabel(s) => write!(f, "Undefined label `{}`", s),
+            AsmErrorKind::InvalidString(s) => write!(f, "Invalid string {}", s),
+            AsmErrorKind::Overflow => write!(f, "Value does not fit into an Intcode value"),
+        }
+    }
+}
+
+impl Error for AsmError {}
+
+/// A number or a label, possibly negated.
+#[derive(Debug)]
+enum Term {
+    Number(VALUE),
+    Label(String, bool),
+}
+
+/// A sum of terms, resolved once all labels are known.
+type Expr = Vec<Term>;
+
+#[derive(Debug)]
+struct Operand {
+    mode: VALUE,
+    expr: Expr,
+}
+
+#[derive(Debug)]
+enum Statement {
+    Instruction { opcode: u8, operands: Vec<Operand> },
+    Data(Vec<Expr>),
+}
+
+impl Statement {
+    fn len(&self) -> usize {
+        match self {
+            Statement::Instruction { operands, .. } => 1 + operands.len(),
+            Statement::Data(values) => values.len(),
+        }
+    }
+}
+
+pub fn assemble(source: &str) -> Result<Vec<VALUE>, AsmError> {
+    let mut labels: HashMap<&str, usize> = HashMap::new();
+    let mut statements: Vec<(usize, Statement)> = vec![];
+    let mut address = 0;
+
+    for (k, line) in source.lines().enumerate() {
+        let line_number = k + 1;
+        let error = |kind| AsmError { line: line_number, kind };
+
+        let mut rest = strip_comment(line).trim();
+
+        while let Some((label, after)) = split_label(rest) {
+            if !is_identifier(label) {
+                return Err(error(AsmErrorKind::InvalidLabel(label.to_owned())));
+            }
+            if labels.insert(label, address).is_some() {
+                return Err(error(AsmErrorKind::DuplicateLabel(label.to_owned())));
+            }
+            rest = after.trim_start();
+        }
+
+        if rest.is_empty() {
+            continue;
+        }
+
+        let statement = parse_statement(rest).map_err(error)?;
+        address += statement.len();
+        statements.push((line_number, statement));
+    }
+
+    let mut code = Vec::with_capacity(address);
+
+    for (line_number, statement) in statements {
+        let resolve = |expr: &Expr| resolve(expr, &labels).map_err(|kind| AsmError { line: line_number, kind });
+
+        match statement {
+            Statement::Instruction { opcode, operands } => {
+                let modes = operands
+                    .iter()
+                    .enumerate()
+                    .map(|(k, operand)| operand.mode * 10i64.pow(2 + k as u32))
+                    .sum::<VALUE>();
+                code.push(opcode as VALUE + modes);
+                for operand in operands.iter() {
+                    code.push(resolve(&operand.expr)?);
+                }
+            },
+            Statement::Data(values) => {
+                for value in values.iter() {
+                    code.push(resolve(value)?);
+                }
+            },
+        }
+    }
+
+    Ok(code)
+}
+
+/// Removes a trailing `;` comment, ignoring semicolons inside of string literals.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (k, c) in line.char_indices() {
+        match c {
+            _ if escaped => { escaped = false; },
+            '\\' if in_string => { escaped = true; },
+            '"' => { in_string = !in_string; },
+            ';' if !in_string => { return &line[..k]; },
+            _ => {},
+        }
+    }
+    line
+}
+
+/// Splits `label: rest` into its label and the rest of the line.
+fn split_label(line: &str) -> Option<(&str, &str)> {
+    let end = line.find(|c: char| c.is_whitespace() || c == ':' || c == '"')?;
+    if line[end..].starts_with(':') {
+        Some((&line[..end], &line[end+1..]))
+    } else {
+        None
+    }
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+fn find_instruction(mnemonic: &str) -> Option<&'static Instruction> {
+    let opcode = ALIASES.iter().find(|(alias, _)| *alias == mnemonic).map(|&(_, opcode)| opcode);
+
+    OPCODES.iter().find(|instruction| match opcode {
+        Some(opcode) => instruction.opcode == opcode,
+        None => instruction.mnemonic() == mnemonic,
+    })
+}
+
+fn parse_statement(statement: &str) -> Result<Statement, AsmErrorKind> {
+    let (head, rest) = match statement.find(char::is_whitespace) {
+        Some(k) => (&statement[..k], statement[k..].trim()),
+        None => (statement, ""),
+    };
+
+    if let Some(directive) = head.strip_prefix('.') {
+        return match directive {
+            "data" => Ok(Statement::Data(split_operands(rest)
+                .into_iter()
+                .map(parse_expr)
+                .collect::<Result<_, _>>()?)),
+            "ascii" => Ok(Statement::Data(parse_string(rest)?
+                .chars()
+                .map(|c| vec![Term::Number(c as VALUE)])
+                .collect())),
+            _ => Err(AsmErrorKind::UnknownDirective(head.to_owned())),
+        };
+    }
+
+    let instruction = find_instruction(head).ok_or_else(|| AsmErrorKind::UnknownMnemonic(head.to_owned()))?;
+
+    let operands = split_operands(rest);
+    if operands.len() != instruction.number_parameters {
+        return Err(AsmErrorKind::WrongOperandCount {
+            mnemonic: head.to_owned(),
+            expected: instruction.number_parameters,
+            found: operands.len(),
+        });
+    }
+
+    let operands = operands
+        .into_iter()
+        .map(|operand| {
+            if let Some(expr) = operand.strip_prefix('#') {
+                Ok(Operand { mode: 1, expr: parse_expr(expr)? })
+            } else if let Some(expr) = operand.strip_prefix('@') {
+                Ok(Operand { mode: 2, expr: parse_expr(expr)? })
+            } else {
+                Ok(Operand { mode: 0, expr: parse_expr(operand)? })
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if let Some(parameter) = instruction.writes {
+        if operands[parameter].mode == 1 {
+            return Err(AsmErrorKind::ImmediateWrite { mnemonic: head.to_owned(), parameter });
+        }
+    }
+
+    Ok(Statement::Instruction { opcode: instruction.opcode, operands })
+}
+
+fn split_operands(operands: &str) -> Vec<&str> {
+    if operands.is_empty() {
+        return vec![];
+    }
+    operands.split(',').map(str::trim).collect()
+}
+
+/// Parses sums like `label+2`, `-3` or `a-b+1`.
+fn parse_expr(expr: &str) -> Result<Expr, AsmErrorKind> {
+    let invalid = || AsmErrorKind::InvalidOperand(expr.to_owned());
+
+    let mut terms = vec![];
+    let mut negative = false;
+    let mut rest = expr.trim();
+
+    if let Some(after) = rest.strip_prefix('-') {
+        negative = true;
+        rest = after;
+    }
+
+    loop {
+        let end = rest.find(['+', '-']).unwrap_or(rest.len());
+        let term = rest[..end].trim();
+
+        if term.is_empty() {
+            return Err(invalid());
+        } else if term.starts_with(|c: char| c.is_ascii_digit()) {
+            let signed = if negative { format!("-{term}") } else { term.to_owned() };
+            terms.push(Term::Number(signed.parse::<VALUE>().map_err(|_| invalid())?));
+        } else if is_identifier(term) {
+            terms.push(Term::Label(term.to_owned(), negative));
+        } else {
+            return Err(invalid());
+        }
+
+        if end == rest.len() {
+            return Ok(terms);
+        }
+        negative = rest.as_bytes()[end] == b'-';
+        rest = &rest[end+1..];
+    }
+}
+
+fn parse_string(literal: &str) -> Result<String, AsmErrorKind> {
+    let invalid = || AsmErrorKind::InvalidString(literal.to_owned());
+
+    let inner = literal
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .ok_or_else(invalid)?;
+
+    let mut s = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => s.push('\n'),
+                Some('"') => s.push('"'),
+                Some('\\') => s.push('\\'),
+                _ => return Err(invalid()),
+            },
+            '"' => return Err(invalid()),
+            _ => s.push(c),
+        }
+    }
+    Ok(s)
+}
+
+fn resolve(expr: &Expr, labels: &HashMap<&str, usize>) -> Result<VALUE, AsmErrorKind> {
+    expr.iter().try_fold(0 as VALUE, |sum, term| {
+        let val = match term {
+            Term::Number(val) => *val,
+            Term::Label(label, negative) => {
+                let address = *labels
+                    .get(label.as_str())
+                    .ok_or_else(|| AsmErrorKind::UndefinedLabel(label.to_owned()))? as VALUE;
+                if *negative { -address } else { address }
+            },
+        };
+        sum.checked_add(val).ok_or(AsmErrorKind::Overflow)
+    })
+}
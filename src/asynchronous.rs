@@ -0,0 +1,329 @@
+//! Machines as tasks of a single threaded executor, exchanging values over async channels.
+//!
+//! `drive` runs a machine as a future, which suspends while the machine waits for input, or
+//! while its output channel is full. An `Executor` only polls tasks which have been woken, so
+//! any number of machines take turns on one thread without busy polling:
+//!
+//! ```ignore
+//! let mut executor = Executor::new();
+//! let (tx, rx) = asynchronous::channel();
+//! let (out_tx, mut out_rx) = asynchronous::channel();
+//! let machine = executor.spawn(asynchronous::drive(Interpreter::new(code, [].into()), rx, out_tx));
+//! executor.spawn(async move { tx.send(1).await });
+//! executor.run();
+//! ```
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::{poll_fn, Future};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+
+use crate::budget::{Budget, Limit};
+use crate::src::{ErrorKind, Interpreter, InterpreterError, RunResult, VALUE};
+
+/// The number of instructions a machine executes before it lets other tasks run.
+pub const SLICE: u64 = 10_000;
+
+struct Channel {
+    queue: VecDeque<VALUE>,
+    capacity: usize,
+    senders: usize,
+    receiver: bool,
+    /// The receiver waiting for a value.
+    receiving: Option<Waker>,
+    /// The senders waiting for space.
+    sending: Vec<Waker>,
+}
+
+/// Sends values to a `Receiver`. Once all senders are gone, the receiver gets `None`.
+pub struct Sender(Arc<Mutex<Channel>>);
+
+pub struct Receiver(Arc<Mutex<Channel>>);
+
+/// A channel holding any number of values.
+pub fn channel() -> (Sender, Receiver) {
+    bounded(usize::MAX)
+}
+
+/// A channel holding up to `capacity` values, on which `Sender::send` waits while it is full.
+///
+/// Panics if `capacity` is 0.
+pub fn bounded(capacity: usize) -> (Sender, Receiver) {
+    assert!(capacity > 0, "A channel needs room for at least one value");
+    let channel = Arc::new(Mutex::new(Channel {
+        queue: VecDeque::new(),
+        capacity,
+        senders: 1,
+        receiver: true,
+        receiving: None,
+        sending: vec![],
+    }));
+    (Sender(Arc::clone(&channel)), Receiver(channel))
+}
+
+impl Sender {
+    /// Sends `val` once there is space for it. The value is dropped if the receiver is gone,
+    /// like the output of a machine nobody listens to.
+    pub async fn send(&self, val: VALUE) {
+        poll_fn(|cx| {
+            let mut channel = self.0.lock().unwrap();
+            if !channel.receiver {
+                return Poll::Ready(());
+            }
+            if channel.queue.len() >= channel.capacity {
+                // A sender polled again before it was woken is already waiting
+                if !channel.sending.iter().any(|waker| waker.will_wake(cx.waker())) {
+                    channel.sending.push(cx.waker().clone());
+                }
+                return Poll::Pending;
+            }
+            channel.queue.push_back(val);
+            if let Some(waker) = channel.receiving.take() {
+                waker.wake();
+            }
+            Poll::Ready(())
+        }).await
+    }
+}
+
+impl Clone for Sender {
+    fn clone(&self) -> Sender {
+        self.0.lock().unwrap().senders += 1;
+        Sender(Arc::clone(&self.0))
+    }
+}
+
+impl Drop for Sender {
+    fn drop(&mut self) {
+        let mut channel = self.0.lock().unwrap();
+        channel.senders -= 1;
+        if channel.senders == 0 {
+            if let Some(waker) = channel.receiving.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl Receiver {
+    /// The next value, waiting until there is one. `None` once all senders are gone.
+    pub async fn recv(&mut self) -> Option<VALUE> {
+        poll_fn(|cx| {
+            let mut channel = self.0.lock().unwrap();
+            if let Some(val) = channel.queue.pop_front() {
+                for waker in channel.sending.drain(..) {
+                    waker.wake();
+                }
+                return Poll::Ready(Some(val));
+            }
+            if channel.senders == 0 {
+                return Poll::Ready(None);
+            }
+            channel.receiving = Some(cx.waker().clone());
+            Poll::Pending
+        }).await
+    }
+
+    /// The next value, if there is one already.
+    pub fn try_recv(&mut self) -> Option<VALUE> {
+        let mut channel = self.0.lock().unwrap();
+        let val = channel.queue.pop_front()?;
+        for waker in channel.sending.drain(..) {
+            waker.wake();
+        }
+        Some(val)
+    }
+}
+
+impl Drop for Receiver {
+    fn drop(&mut self) {
+        let mut channel = self.0.lock().unwrap();
+        channel.receiver = false;
+        for waker in channel.sending.drain(..) {
+            waker.wake();
+        }
+    }
+}
+
+/// Lets the other tasks run before continuing.
+pub async fn yield_now() {
+    let mut yielded = false;
+    poll_fn(|cx| {
+        if yielded {
+            return Poll::Ready(());
+        }
+        yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }).await
+}
+
+/// Runs `pc` until it halts, reading input from `input` whenever its input buffer is empty,
+/// and sending every output to `output`. Returns the last output, if there was any.
+///
+/// Fails with `ErrorKind::NoInput` if the machine waits for input after all senders of
+/// `input` are gone. The budget of the machine applies, and it yields to other tasks every
+/// `SLICE` instructions.
+pub async fn drive(mut pc: Interpreter, mut input: Receiver, output: Sender) -> Result<Option<VALUE>, InterpreterError> {
+    let budget = *pc.budget();
+    let mut last = None;
+    loop {
+        let slice = pc.usage().instructions + SLICE;
+        pc.set_budget(Budget { instructions: Some(budget.instructions.map_or(slice, |max| max.min(slice))), ..budget });
+        match pc.step_loop()? {
+            RunResult::Output(val) => {
+                last = Some(val);
+                output.send(val).await;
+            },
+            RunResult::NeedsInput => match input.recv().await {
+                Some(val) => pc.input_buffer.push_back(val),
+                None => return Err(pc.fault(ErrorKind::NoInput, Some(0))),
+            },
+            RunResult::Halted => return Ok(last),
+            RunResult::BudgetExhausted(Limit::Instructions) if budget.instructions.is_none_or(|max| pc.usage().instructions < max) => {
+                yield_now().await;
+            },
+            RunResult::BudgetExhausted(limit) => return Err(pc.fault(ErrorKind::BudgetExhausted(limit), None)),
+        }
+    }
+}
+
+/// Spawns one machine per phase setting, connected in a ring like `amplifier::Amplifiers::ring`,
+/// and sends `signal` to the first one. Returns the handles of the machines, the last of which
+/// finishes with the final signal.
+pub fn spawn_ring(executor: &mut Executor, code: &[VALUE], phases: &[VALUE], signal: VALUE) -> Vec<JoinHandle<Result<Option<VALUE>, InterpreterError>>> {
+    let (mut senders, receivers): (VecDeque<_>, Vec<_>) = phases.iter().map(|_| channel()).unzip();
+    senders.rotate_left(1);
+    phases.iter()
+        .zip(receivers)
+        .zip(senders)
+        .enumerate()
+        .map(|(k, ((&phase, input), output))| {
+            let prefix = if k == 0 { vec![phase, signal] } else { vec![phase] };
+            executor.spawn(drive(Interpreter::new(code.to_owned(), prefix.into()), input, output))
+        })
+        .collect()
+}
+
+/// Queues its task on the executor when woken.
+struct TaskWaker {
+    id: usize,
+    /// Whether the task is queued already.
+    queued: AtomicBool,
+    queue: Arc<Mutex<VecDeque<usize>>>,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        if !self.queued.swap(true, Ordering::AcqRel) {
+            self.queue.lock().unwrap().push_back(self.id);
+        }
+    }
+}
+
+struct Task {
+    future: Pin<Box<dyn Future<Output = ()>>>,
+    waker: Arc<TaskWaker>,
+}
+
+/// Runs tasks on the current thread, polling a task only after it has been woken.
+#[derive(Default)]
+pub struct Executor {
+    /// The tasks by id, `None` once finished.
+    tasks: Vec<Option<Task>>,
+    queue: Arc<Mutex<VecDeque<usize>>>,
+}
+
+impl Executor {
+    pub fn new() -> Executor {
+        Executor::default()
+    }
+
+    /// Adds a task, which first runs with the next call of `run`.
+    pub fn spawn<F: Future + 'static>(&mut self, future: F) -> JoinHandle<F::Output> {
+        let state = Rc::new(RefCell::new(JoinState { result: None, waiting: None }));
+        let slot = Rc::clone(&state);
+        let future = async move {
+            let res = future.await;
+            let mut state = slot.borrow_mut();
+            state.result = Some(res);
+            if let Some(waker) = state.waiting.take() {
+                waker.wake();
+            }
+        };
+
+        let waker = Arc::new(TaskWaker { id: self.tasks.len(), queued: AtomicBool::new(false), queue: Arc::clone(&self.queue) });
+        waker.wake_by_ref();
+        self.tasks.push(Some(Task { future: Box::pin(future), waker }));
+        JoinHandle(state)
+    }
+
+    /// Runs the tasks until all of them have finished, or wait for something no task is going
+    /// to do, eg. machines in a ring all waiting for input. Returns the number of tasks which
+    /// have not finished.
+    pub fn run(&mut self) -> usize {
+        loop {
+            let Some(id) = self.queue.lock().unwrap().pop_front() else {
+                break;
+            };
+            let Some(task) = self.tasks[id].as_mut() else {
+                continue;
+            };
+            task.waker.queued.store(false, Ordering::Release);
+            let waker = Waker::from(Arc::clone(&task.waker));
+            if task.future.as_mut().poll(&mut Context::from_waker(&waker)).is_ready() {
+                self.tasks[id] = None;
+            }
+        }
+        self.pending()
+    }
+
+    /// The number of tasks which have not finished.
+    pub fn pending(&self) -> usize {
+        self.tasks.iter().flatten().count()
+    }
+}
+
+struct JoinState<T> {
+    result: Option<T>,
+    /// The task awaiting the result.
+    waiting: Option<Waker>,
+}
+
+/// The result of a spawned task, which can be awaited by other tasks.
+pub struct JoinHandle<T>(Rc<RefCell<JoinState<T>>>);
+
+impl<T> JoinHandle<T> {
+    /// Whether the task has finished, and its result has not been taken yet.
+    pub fn is_finished(&self) -> bool {
+        self.0.borrow().result.is_some()
+    }
+
+    /// Takes the result of the task, if it has finished.
+    pub fn take(&self) -> Option<T> {
+        self.0.borrow_mut().result.take()
+    }
+}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let mut state = self.0.borrow_mut();
+        match state.result.take() {
+            Some(res) => Poll::Ready(res),
+            None => {
+                state.waiting = Some(cx.waker().clone());
+                Poll::Pending
+            },
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! Execution from a cache of decoded basic blocks, see `Engine::Blocks`.
+//!
+//! A basic block is a straight-line run of instructions, ending with the first jump, input,
+//! output or halt. Blocks are decoded on their first execution, and evicted from the cache by
+//! any write into one of their cells, so self-modifying programs run unchanged.
+
+use std::sync::Arc;
+
+use crate::memory::Memory;
+use crate::src::{InstructionSet, RunResult, VALUE};
+
+/// The most instructions in a block. Bounds the cells a block covers, which an eviction scans.
+const MAX_LENGTH: usize = 64;
+/// The most cells a block covers, as the standard instructions have at most 3 parameters.
+const MAX_SPAN: usize = 4 * MAX_LENGTH;
+
+/// Selects how an `Interpreter` executes its program, see `Interpreter::with_engine`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Engine {
+    /// Decodes every instruction before executing it.
+    #[default]
+    Step,
+    /// Executes basic blocks from a cache, decoding each of them once. Custom instructions,
+    /// faults and tracing are left to `Interpreter::step`, so both engines behave the same.
+    Blocks,
+}
+
+/// An operand of a decoded instruction.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Operand {
+    /// The value at an address.
+    Position(usize),
+    Immediate(VALUE),
+    /// The value at an offset from the relative base.
+    Relative(VALUE),
+}
+
+/// A decoded standard instruction.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Op {
+    pub opcode: u8,
+    /// The number of cells of the instruction, including the opcode.
+    pub size: usize,
+    /// The operands, of which the first `size - 1` are in use.
+    pub operands: [Operand; 3],
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Block {
+    pub ops: Vec<Op>,
+    /// One past the last cell of the block.
+    pub end: usize,
+}
+
+/// How execution continues after a cached instruction.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Flow {
+    Continue,
+    /// The instruction returned a result to the caller of `step_loop`.
+    Stop(RunResult),
+    /// The instruction has not been executed, as it has to be left to `Interpreter::step`.
+    Step,
+}
+
+/// The blocks decoded so far, indexed by their first address.
+#[derive(Clone, Debug, Default)]
+pub struct BlockCache {
+    blocks: Vec<Option<Arc<Block>>>,
+    /// The number of cached blocks covering each cell.
+    coverage: Vec<u32>,
+    /// Counts the evictions, so a running block notices that it has been evicted.
+    generation: u64,
+}
+
+impl BlockCache {
+    pub fn get(&self, start: usize) -> Option<&Arc<Block>> {
+        self.blocks.get(start)?.as_ref()
+    }
+
+    /// Caches `block` starting at `start`, unless it is empty or ends past `limit`. The cache is
+    /// indexed by address, so `limit` bounds its size; blocks past it are left to `step`.
+    pub fn insert(&mut self, start: usize, block: Block, limit: usize) -> Option<Arc<Block>> {
+        if block.ops.is_empty() || block.end > limit {
+            return None;
+        }
+        if self.blocks.len() <= start {
+            self.blocks.resize(start + 1, None);
+        }
+        if self.coverage.len() < block.end {
+            self.coverage.resize(block.end, 0);
+        }
+        for count in &mut self.coverage[start..block.end] {
+            *count += 1;
+        }
+        let block = Arc::new(block);
+        self.blocks[start] = Some(Arc::clone(&block));
+        Some(block)
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Evicts every block covering `address`, to be called on every write to memory.
+    #[inline]
+    pub fn invalidate(&mut self, address: usize) {
+        if self.coverage.get(address).is_some_and(|&count| count > 0) {
+            self.evict(address);
+        }
+    }
+
+    fn evict(&mut self, address: usize) {
+        for start in address.saturating_sub(MAX_SPAN - 1)..=address {
+            if let Some(block) = self.blocks.get_mut(start).and_then(|slot| slot.take_if(|block| block.end > address)) {
+                for count in &mut self.coverage[start..block.end] {
+                    *count -= 1;
+                }
+            }
+        }
+        self.generation += 1;
+    }
+
+    /// Evicts every block, eg. when the instruction set changes.
+    pub fn clear(&mut self) {
+        *self = BlockCache { generation: self.generation + 1, ..BlockCache::default() };
+    }
+}
+
+/// Decodes the block starting at `start`, which is empty if its first instruction has to be
+/// left to `Interpreter::step`.
+pub fn decode<I, O>(memory: &Memory, instructions: &InstructionSet<I, O>, start: usize) -> Block {
+    let mut ops = vec![];
+    let mut address = start;
+    while ops.len() < MAX_LENGTH {
+        let Some(op) = decode_instruction(memory, instructions, address) else {
+            break;
+        };
+        ops.push(op);
+        address += op.size;
+        if matches!(op.opcode, 3 | 4 | 5 | 6 | 99) {
+            break;
+        }
+    }
+    Block { ops, end: address }
+}
+
+/// The standard instruction at `address`, or `None` if it is a custom instruction, or decoding
+/// it faults.
+fn decode_instruction<I, O>(memory: &Memory, instructions: &InstructionSet<I, O>, address: usize) -> Option<Op> {
+    let word = memory.get(address)?;
+    let instruction = instructions.builtin(word)?;
+    let mut operands = [Operand::Immediate(0); 3];
+    let mut modes = word / 100;
+    for (k, operand) in operands.iter_mut().enumerate().take(instruction.number_parameters) {
+        let raw = memory.get(address + 1 + k)?;
+        *operand = match modes % 10 {
+            0 if raw >= 0 => { Operand::Position(raw as usize) },
+            1 if instruction.writes != Some(k) => { Operand::Immediate(raw) },
+            2 => { Operand::Relative(raw) },
+            _ => { return None },
+        };
+        modes /= 10;
+    }
+    (modes == 0).then_some(Op { opcode: instruction.opcode, size: 1 + instruction.number_parameters, operands })
+}
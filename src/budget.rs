@@ -0,0 +1,101 @@
+//! Execution limits, to safely run programs which might never halt.
+//!
+//! An `Interpreter` checks its budget before every instruction. Once a limit is reached, it
+//! returns `RunResult::BudgetExhausted` without executing the instruction, so the machine can
+//! be resumed after raising the limit with `Interpreter::set_budget`.
+
+use std::fmt::Display;
+use std::time::{Duration, Instant};
+
+/// Number of instructions between two checks of the deadline, as reading the clock is slow.
+pub const DEADLINE_INTERVAL: u64 = 1024;
+
+/// The limit which has been reached.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Limit {
+    Instructions,
+    Memory,
+    Outputs,
+    Deadline,
+}
+
+impl Display for Limit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Limit::Instructions => write!(f, "instructions"),
+            Limit::Memory => write!(f, "memory"),
+            Limit::Outputs => write!(f, "outputs"),
+            Limit::Deadline => write!(f, "deadline"),
+        }
+    }
+}
+
+/// Limits of an `Interpreter`, all of them unlimited by default. Instructions and outputs are
+/// counted from the creation of the machine, see `Interpreter::usage`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Budget {
+    /// The number of instructions the machine may execute.
+    pub instructions: Option<u64>,
+    /// The number of memory cells the machine may grow its memory to. Memory is never shrunk,
+    /// so a program larger than the limit still runs until it tries to grow.
+    pub memory: Option<usize>,
+    /// The number of values the machine may output, checked before every output instruction.
+    pub outputs: Option<u64>,
+    /// The time after which the machine stops, checked every `DEADLINE_INTERVAL` instructions.
+    pub deadline: Option<Instant>,
+}
+
+impl Budget {
+    pub fn unlimited() -> Budget {
+        Budget::default()
+    }
+
+    pub fn with_instructions(mut self, instructions: u64) -> Budget {
+        self.instructions = Some(instructions);
+        self
+    }
+
+    pub fn with_memory(mut self, cells: usize) -> Budget {
+        self.memory = Some(cells);
+        self
+    }
+
+    pub fn with_outputs(mut self, outputs: u64) -> Budget {
+        self.outputs = Some(outputs);
+        self
+    }
+
+    pub fn with_deadline(mut self, deadline: Instant) -> Budget {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// A deadline `timeout` from now.
+    pub fn with_timeout(self, timeout: Duration) -> Budget {
+        self.with_deadline(Instant::now() + timeout)
+    }
+
+    /// The limit `usage` has reached, except for memory, which is checked per write. The outputs
+    /// limit only stops an `output` instruction, so a program which outputs exactly the limit
+    /// still halts.
+    pub fn exhausted(&self, usage: &Usage, output: bool) -> Option<Limit> {
+        if self.instructions.is_some_and(|max| usage.instructions >= max) {
+            return Some(Limit::Instructions);
+        }
+        if output && self.outputs.is_some_and(|max| usage.outputs >= max) {
+            return Some(Limit::Outputs);
+        }
+        if usage.instructions.is_multiple_of(DEADLINE_INTERVAL) && self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return Some(Limit::Deadline);
+        }
+        None
+    }
+}
+
+/// What a machine has used of its budget so far.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Usage {
+    /// The number of instructions executed. Input instructions waiting for input do not count.
+    pub instructions: u64,
+    pub outputs: u64,
+}
@@ -0,0 +1,157 @@
+//! The control flow graph of a program, and its export to Graphviz DOT.
+//!
+//! Blocks end at jumps and halts, and before every address a jump with an immediate target
+//! leads to. Jumps with other targets are resolved at runtime only, and marked as indirect.
+//! Code which is only reached through them is found like `aot::entry_points` does, eg. the
+//! code after a call, whose address is pushed as a return address.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use crate::aot;
+use crate::disassembler::{self, Decoded, Mode};
+use crate::src::VALUE;
+
+/// How control passes from one block to another.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Edge {
+    /// Execution continues with the following address, which starts the next block unless it
+    /// is not code.
+    Next(usize),
+    /// A jump with an immediate target.
+    Jump(usize),
+    /// A jump to an address only known at runtime.
+    Indirect,
+}
+
+pub struct Block {
+    pub start: usize,
+    /// One past the last cell of the block.
+    pub end: usize,
+    pub instructions: Vec<Decoded>,
+    pub edges: Vec<Edge>,
+}
+
+pub struct Cfg {
+    /// The blocks by their first address.
+    pub blocks: BTreeMap<usize, Block>,
+}
+
+impl Cfg {
+    /// Splits the instructions of `code` into blocks.
+    pub fn new(code: &[VALUE]) -> Cfg {
+        let instructions: Vec<Decoded> = aot::entry_points(code)
+            .into_iter()
+            .filter_map(|address| disassembler::decode(code, address))
+            .collect();
+
+        let mut leaders = BTreeSet::from([0]);
+        let mut end = None;
+        for instruction in &instructions {
+            if end != Some(instruction.address) {
+                leaders.insert(instruction.address);
+            }
+            end = Some(instruction.address + instruction.size());
+            if matches!(instruction.instruction.opcode, 5 | 6 | 99) {
+                leaders.extend(end);
+                leaders.extend(edges(instruction).into_iter().filter_map(|edge| match edge {
+                    Edge::Jump(target) => Some(target),
+                    _ => None,
+                }));
+            }
+        }
+
+        let starts: BTreeSet<usize> = instructions.iter().map(|instruction| instruction.address).collect();
+        let mut blocks = BTreeMap::new();
+        let mut current: Option<Block> = None;
+        for instruction in instructions {
+            let address = instruction.address;
+            let next = address + instruction.size();
+            let mut block = current.take().unwrap_or(Block { start: address, end: address, instructions: vec![], edges: vec![] });
+
+            let terminator = matches!(instruction.instruction.opcode, 5 | 6 | 99);
+            if terminator {
+                block.edges = edges(&instruction);
+            } else if leaders.contains(&next) || !starts.contains(&next) {
+                // Falls through into the next block, or into something which is not code
+                block.edges = vec![Edge::Next(next)];
+            }
+            block.end = next;
+            block.instructions.push(instruction);
+
+            if terminator || !block.edges.is_empty() {
+                blocks.insert(block.start, block);
+            } else {
+                current = Some(block);
+            }
+        }
+
+        Cfg { blocks }
+    }
+
+    /// The graph in the DOT language of Graphviz, eg. for `dot -Tsvg`. Blocks are labelled with
+    /// their instructions, and addresses control passes to which are not code are drawn dashed.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "digraph cfg {{").unwrap();
+        writeln!(out, "    node [shape=box, fontname=\"monospace\"];").unwrap();
+
+        let mut missing = BTreeSet::new();
+        let mut indirect = false;
+        for block in self.blocks.values() {
+            let mut label = String::new();
+            for instruction in &block.instructions {
+                let operands: Vec<String> = instruction.operands.iter().map(|operand| operand.to_string()).collect();
+                write!(label, "{}: {} {}\\l", instruction.address, instruction.instruction.mnemonic(), operands.join(", ")).unwrap();
+            }
+            writeln!(out, "    b{} [label=\"{}\"];", block.start, label).unwrap();
+
+            for edge in &block.edges {
+                match *edge {
+                    Edge::Next(target) => writeln!(out, "    b{} -> b{};", block.start, target).unwrap(),
+                    Edge::Jump(target) => writeln!(out, "    b{} -> b{} [label=\"jump\"];", block.start, target).unwrap(),
+                    Edge::Indirect => {
+                        indirect = true;
+                        writeln!(out, "    b{} -> indirect [style=dashed];", block.start).unwrap();
+                    },
+                }
+                if let Edge::Next(target) | Edge::Jump(target) = *edge {
+                    if !self.blocks.contains_key(&target) {
+                        missing.insert(target);
+                    }
+                }
+            }
+        }
+
+        for target in missing {
+            writeln!(out, "    b{} [label=\"{}: not code\", style=dashed];", target, target).unwrap();
+        }
+        if indirect {
+            writeln!(out, "    indirect [label=\"indirect\", shape=ellipse, style=dashed];").unwrap();
+        }
+        writeln!(out, "}}").unwrap();
+        out
+    }
+}
+
+/// The edges leaving a block which ends with `instruction`, a jump or a halt.
+fn edges(instruction: &Decoded) -> Vec<Edge> {
+    let next = instruction.address + instruction.size();
+    let successors = instruction.successors();
+    let mut edges: Vec<Edge> = successors
+        .iter()
+        .map(|&address| if address == next { Edge::Next(address) } else { Edge::Jump(address) })
+        .collect();
+    edges.dedup();
+
+    // Jumps which are taken unless the condition says otherwise, to a target not immediate
+    let target = instruction.operands.get(1).filter(|_| matches!(instruction.instruction.opcode, 5 | 6));
+    if let Some(target) = target {
+        let condition = instruction.operands[0];
+        let never_jumps = condition.mode == Mode::Immediate && ((condition.value != 0) != (instruction.instruction.opcode == 5));
+        if target.mode != Mode::Immediate && !never_jumps {
+            edges.push(Edge::Indirect);
+        }
+    }
+    edges
+}
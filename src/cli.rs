@@ -0,0 +1,406 @@
+//! The command line interface, see `USAGE`.
+
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::aot::{self, Policy};
+use crate::ascii::Console;
+use crate::blocks::Engine;
+use crate::budget::Budget;
+use crate::io::{InputSource, ReadSource, WriteSink};
+use crate::loader::{self, LoadError};
+use crate::memory::MemoryModel;
+use crate::src::{ErrorKind, Interpreter, InterpreterError, RunResult, VALUE};
+use crate::trace::{JsonTracer, TextTracer, TraceSink};
+use crate::days::{self, DayError};
+use crate::{cfg, debugger, decompiler, disassembler};
+
+pub const USAGE: &str = "\
+Usage: intcode <command> [options]
+
+Commands:
+  run <file>              Run a program, given as text or in the binary format
+  disasm <file>           Print a listing of the reachable code of a program
+  cfg <file>              Print the control flow graph of a program in the DOT language
+  decompile <file>        Print a program and its functions as C-like pseudocode
+  trace <file>            Run a program, printing every executed instruction to stderr
+  debug <file>            Debug a program interactively
+  compile <file>          Translate a program into a Rust module, printed to stdout
+  bench <file>            Measure how long a program takes to run
+  day <n> [a|b]           Run the solutions of a day, both parts by default
+  help                    Print this help
+
+Options of run, trace and bench:
+  --input <values>        Comma separated input values, instead of reading from stdin
+  --input-file <file>     Read input values from a file, or input lines in ASCII mode
+  --ascii                 Exchange text with the program, one line at a time
+  --memory <model>        dense (default), sparse or fixed
+  --engine <engine>       step (default), or blocks to execute cached basic blocks
+
+Options of run and trace:
+  --max-instructions <n>  Stop the program after n instructions
+  --timeout <ms>          Stop the program after the given number of milliseconds
+
+Options of trace:
+  --json                  Print one JSON object per instruction
+
+Options of bench:
+  --runs <n>              Number of runs, default 10
+
+Options of compile:
+  --refuse-self-modifying Fail if the program writes to its own instructions
+
+Exit codes: 0 on success, 1 for invalid usage, 2 for I/O errors and programs which cannot be
+loaded, 3 for a day without answer, and 10 to 16 for faults of the program: invalid opcode,
+invalid parameters, overflow, jump out of bounds, out of memory, missing input and an
+exhausted budget.";
+
+/// Why the command line interface failed.
+#[derive(Debug)]
+pub enum CliError {
+    Usage(String),
+    Io(io::Error),
+    /// A program which could not be loaded, with its path.
+    Load(String, LoadError),
+    Fault(InterpreterError),
+    /// A solution of a day which found no answer, although its program ran without a fault.
+    Unsolved(String),
+}
+
+impl CliError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Usage(_) => 1,
+            CliError::Io(_) | CliError::Load(..) => 2,
+            CliError::Unsolved(_) => 3,
+            CliError::Fault(e) => match e.kind {
+                ErrorKind::InvalidOpCode => 10,
+                ErrorKind::InvalidParameters => 11,
+                ErrorKind::Overflow => 12,
+                ErrorKind::JumpOutOfBounds => 13,
+                ErrorKind::OutOfMemory => 14,
+                ErrorKind::NoInput => 15,
+                ErrorKind::BudgetExhausted(_) => 16,
+            },
+        }
+    }
+}
+
+impl Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliError::Usage(message) => write!(f, "{}\n\n{}", message, USAGE),
+            CliError::Io(e) => write!(f, "{}", e),
+            CliError::Load(path, e) => write!(f, "{}: {}", path, e),
+            CliError::Fault(e) => write!(f, "{}", e),
+            CliError::Unsolved(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for CliError {}
+
+impl From<io::Error> for CliError {
+    fn from(e: io::Error) -> CliError {
+        CliError::Io(e)
+    }
+}
+
+impl From<InterpreterError> for CliError {
+    fn from(e: InterpreterError) -> CliError {
+        CliError::Fault(e)
+    }
+}
+
+impl From<crate::ascii::ConsoleError> for CliError {
+    fn from(e: crate::ascii::ConsoleError) -> CliError {
+        match e {
+            crate::ascii::ConsoleError::Io(e) => CliError::Io(e),
+            crate::ascii::ConsoleError::Fault(e) => CliError::Fault(e),
+        }
+    }
+}
+
+fn usage(message: impl Into<String>) -> CliError {
+    CliError::Usage(message.into())
+}
+
+/// The arguments after the command, split into positional arguments and options.
+struct Arguments {
+    positional: Vec<String>,
+    options: Vec<(String, Option<String>)>,
+}
+
+/// Options which take a value.
+const VALUED: [&str; 7] = ["--input", "--input-file", "--memory", "--engine", "--runs", "--max-instructions", "--timeout"];
+const FLAGS: [&str; 3] = ["--ascii", "--json", "--refuse-self-modifying"];
+
+impl Arguments {
+    fn parse(args: &[String]) -> Result<Arguments, CliError> {
+        let mut arguments = Arguments { positional: vec![], options: vec![] };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if VALUED.contains(&arg.as_str()) {
+                let value = args.next().ok_or_else(|| usage(format!("`{}` expects a value", arg)))?;
+                arguments.options.push((arg.to_owned(), Some(value.to_owned())));
+            } else if FLAGS.contains(&arg.as_str()) {
+                arguments.options.push((arg.to_owned(), None));
+            } else if arg.starts_with("--") {
+                return Err(usage(format!("Unknown option `{}`", arg)));
+            } else {
+                arguments.positional.push(arg.to_owned());
+            }
+        }
+        Ok(arguments)
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(option, _)| option == name)
+    }
+
+    fn value(&self, name: &str) -> Option<&str> {
+        self.options.iter().rev().find(|(option, _)| option == name).and_then(|(_, value)| value.as_deref())
+    }
+
+    fn file(&self) -> Result<&str, CliError> {
+        self.positional.first().map(String::as_str).ok_or_else(|| usage("Expected a program file"))
+    }
+
+    fn program(&self) -> Result<Vec<VALUE>, CliError> {
+        let path = self.file()?;
+        loader::load_file(path).map_err(|e| match e {
+            LoadError::Io(e) => CliError::Io(e),
+            e => CliError::Load(path.to_owned(), e),
+        })
+    }
+
+    fn memory(&self) -> Result<MemoryModel, CliError> {
+        match self.value("--memory") {
+            None | Some("dense") => Ok(MemoryModel::Dense),
+            Some("sparse") => Ok(MemoryModel::Sparse),
+            Some("fixed") => Ok(MemoryModel::Fixed),
+            Some(model) => Err(usage(format!("Unknown memory model `{}`", model))),
+        }
+    }
+
+    fn engine(&self) -> Result<Engine, CliError> {
+        match self.value("--engine") {
+            None | Some("step") => Ok(Engine::Step),
+            Some("blocks") => Ok(Engine::Blocks),
+            Some(engine) => Err(usage(format!("Unknown engine `{}`", engine))),
+        }
+    }
+
+    fn budget(&self) -> Result<Budget, CliError> {
+        let mut budget = Budget::unlimited();
+        if let Some(n) = self.value("--max-instructions") {
+            budget = budget.with_instructions(n.parse().map_err(|_| usage(format!("Invalid number of instructions `{}`", n)))?);
+        }
+        if let Some(ms) = self.value("--timeout") {
+            budget = budget.with_timeout(Duration::from_millis(ms.parse().map_err(|_| usage(format!("Invalid timeout `{}`", ms)))?));
+        }
+        Ok(budget)
+    }
+
+    /// The input given by `--input` or `--input-file`, or stdin.
+    fn input(&self) -> Result<Box<dyn Read>, CliError> {
+        if let Some(values) = self.value("--input") {
+            return Ok(Box::new(io::Cursor::new(values.to_owned().into_bytes())));
+        }
+        if let Some(path) = self.value("--input-file") {
+            return Ok(Box::new(File::open(path)?));
+        }
+        Ok(Box::new(io::stdin()))
+    }
+
+}
+
+/// Runs the command line interface, writing to the streams `stdout` and `stderr`. Returns the
+/// exit code.
+pub fn main(args: &[String], stdout: &mut dyn Write, stderr: &mut (dyn Write + Send)) -> i32 {
+    match command(args, stdout, stderr) {
+        Ok(()) => 0,
+        Err(e) => {
+            let _ = writeln!(stderr, "{}", e);
+            e.exit_code()
+        },
+    }
+}
+
+fn command(args: &[String], out: &mut dyn Write, err: &mut (dyn Write + Send)) -> Result<(), CliError> {
+    let Some((command, args)) = args.split_first() else {
+        return Err(usage("Expected a command"));
+    };
+    let arguments = Arguments::parse(args)?;
+
+    match command.as_str() {
+        "run" => run(&arguments, out, None),
+        "trace" => {
+            // The tracer of an interpreter owns its stream, hence the records are written to
+            // `err` from a thread of their own
+            let json = arguments.flag("--json");
+            let (sender, records) = mpsc::channel();
+            thread::scope(|scope| {
+                scope.spawn(move || {
+                    let mut tracer: Box<dyn TraceSink> = if json { Box::new(JsonTracer(err)) } else { Box::new(TextTracer(err)) };
+                    for record in records {
+                        tracer.record(&record);
+                    }
+                });
+                run(&arguments, out, Some(Box::new(sender)))
+            })
+        },
+        "disasm" => {
+            for line in disassembler::disassemble(&arguments.program()?) {
+                writeln!(out, "{}", line)?;
+            }
+            Ok(())
+        },
+        "cfg" => {
+            write!(out, "{}", cfg::Cfg::new(&arguments.program()?).to_dot())?;
+            Ok(())
+        },
+        "decompile" => {
+            let procedures: Vec<String> = decompiler::decompile(&arguments.program()?).iter().map(|procedure| procedure.to_string()).collect();
+            writeln!(out, "{}", procedures.join("\n\n"))?;
+            Ok(())
+        },
+        "debug" => Ok(debugger::debug(arguments.program()?, out)?),
+        "compile" => {
+            let policy = if arguments.flag("--refuse-self-modifying") { Policy::Refuse } else { Policy::Fallback };
+            let path = arguments.file()?;
+            let source = aot::translate(&arguments.program()?, path, policy).map_err(|e| usage(format!("{}: {}", path, e)))?;
+            write!(out, "{}", source)?;
+            Ok(())
+        },
+        "bench" => bench(&arguments, out),
+        "day" => day(&arguments, out),
+        "help" | "--help" | "-h" => {
+            writeln!(out, "{}", USAGE)?;
+            Ok(())
+        },
+        _ => Err(usage(format!("Unknown command `{}`", command))),
+    }
+}
+
+/// Runs the program until it halts, printing its output to `out`.
+fn run(arguments: &Arguments, out: &mut dyn Write, tracer: Option<Box<dyn TraceSink + Send>>) -> Result<(), CliError> {
+    let (code, model) = (arguments.program()?, arguments.memory()?);
+    let input = BufReader::new(arguments.input()?);
+
+    if arguments.flag("--ascii") {
+        let mut pc = Interpreter::with_memory(code, vec![].into(), model).with_engine(arguments.engine()?);
+        pc.set_tracer(tracer);
+        pc.set_budget(arguments.budget()?);
+        let mut console = Console::new(pc);
+        // Input lines from a file are echoed, to make the transcript readable
+        let echo = arguments.value("--input-file").is_some();
+        return match console.drive(input, out, echo)? {
+            RunResult::NeedsInput => Err(CliError::Fault(console.pc.fault(ErrorKind::NoInput, Some(0)))),
+            _ => Ok(()),
+        };
+    }
+
+    let mut pc = Interpreter::with_io(code, model, ReadSource::new(input), WriteSink(out))
+        .with_engine(arguments.engine()?);
+    pc.set_tracer(tracer);
+    pc.set_budget(arguments.budget()?);
+    match pc.run()? {
+        RunResult::NeedsInput => Err(CliError::Fault(pc.fault(ErrorKind::NoInput, Some(0)))),
+        RunResult::BudgetExhausted(limit) => Err(CliError::Fault(pc.fault(ErrorKind::BudgetExhausted(limit), None))),
+        _ => Ok(()),
+    }
+}
+
+fn bench(arguments: &Arguments, out: &mut dyn Write) -> Result<(), CliError> {
+    let runs: usize = match arguments.value("--runs") {
+        None => 10,
+        Some(runs) => runs.parse().ok().filter(|&runs| runs > 0).ok_or_else(|| usage(format!("Invalid number of runs `{}`", runs)))?,
+    };
+    let code = arguments.program()?;
+    let mut input = vec![];
+    BufReader::new(arguments.input()?).read_to_end(&mut input)?;
+    let input: Vec<VALUE> = {
+        let mut source = ReadSource::new(input.as_slice());
+        std::iter::from_fn(|| source.next_input()).collect()
+    };
+
+    let mut times = vec![];
+    let mut instructions = 0;
+    for _ in 0..runs {
+        let mut pc = Interpreter::with_memory(code.to_owned(), input.to_owned().into(), arguments.memory()?)
+            .with_engine(arguments.engine()?);
+        let start = Instant::now();
+        match pc.run()? {
+            RunResult::NeedsInput => return Err(CliError::Fault(pc.fault(ErrorKind::NoInput, Some(0)))),
+            RunResult::BudgetExhausted(_) => unreachable!("Benchmarks run without a budget"),
+            _ => {},
+        }
+        times.push(start.elapsed());
+        instructions = pc.usage().instructions;
+    }
+
+    let total: Duration = times.iter().sum();
+    let mean = total / runs as u32;
+    let fastest = times.iter().min().unwrap();
+    writeln!(out, "{} runs of {} instructions", runs, instructions)?;
+    writeln!(out, "mean {:?}, fastest {:?}, {:.1} million instructions per second",
+             mean, fastest, instructions as f64 / mean.as_secs_f64() / 1e6)?;
+    Ok(())
+}
+
+/// Prints the answer to a puzzle to the stream and returns it.
+type Solution = fn(&mut dyn Write) -> Result<VALUE, DayError>;
+
+/// The solutions of every day, as day, part and solution.
+const DAYS: [(u32, &str, Solution); 16] = [
+    (2, "a", days::day2::day2a),
+    (2, "b", days::day2::day2b),
+    (5, "a", days::day5::day5a),
+    (5, "b", days::day5::day5b),
+    (7, "a", days::day7::day7a),
+    (7, "b", days::day7::day7b),
+    (9, "a", days::day9::day9a),
+    (9, "b", days::day9::day9b),
+    (11, "a", days::day11::day11a),
+    (11, "b", days::day11::day11b),
+    (13, "a", days::day13::day13a),
+    (13, "b", days::day13::day13b),
+    (15, "a", days::day15::day15a),
+    (15, "b", days::day15::day15b),
+    (17, "a", days::day17::day17a),
+    (17, "b", days::day17::day17b),
+];
+
+fn day(arguments: &Arguments, out: &mut dyn Write) -> Result<(), CliError> {
+    let day: u32 = arguments.positional.first()
+        .ok_or_else(|| usage("Expected a day"))?
+        .parse()
+        .map_err(|_| usage(format!("Invalid day `{}`", arguments.positional[0])))?;
+    let part = arguments.positional.get(1).map(String::as_str);
+    if !matches!(part, None | Some("a" | "b")) {
+        return Err(usage(format!("Invalid part `{}`, expected a or b", part.unwrap())));
+    }
+
+    let solutions: Vec<_> = DAYS.iter().filter(|&&(d, p, _)| d == day && part.is_none_or(|part| part == p)).collect();
+    if solutions.is_empty() {
+        let days: BTreeSet<_> = DAYS.iter().map(|(d, _, _)| d).collect();
+        let days: Vec<_> = days.iter().map(|d| d.to_string()).collect();
+        return Err(usage(format!("No solution for day {}, available are days {}", day, days.join(", "))));
+    }
+    for (_, p, solution) in solutions {
+        solution(out).map_err(|e| match e {
+            DayError::Io(e) => CliError::Io(e),
+            DayError::Load(e) => CliError::Load(format!("data/day{}.txt", day), e),
+            DayError::Fault(e) => CliError::Fault(e),
+            DayError::Unexpected(message) => CliError::Unsolved(format!("day {}{}: {}", day, p, message)),
+        })?;
+    }
+    Ok(())
+}
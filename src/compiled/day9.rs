@@ -0,0 +1,1929 @@
+//! Generated by `intcode compile data/day9.txt`, do not edit.
+
+use std::collections::VecDeque;
+
+use crate::aot::{Compiled, Exit, Native};
+use crate::src::VALUE;
+
+pub static CODE: [VALUE; 971] = [
+    1102, 34463338, 34463338, 63, 1007, 63, 34463338, 63, 1005, 63, 53, 1101, 3, 0, 1000, 109,
+    988, 209, 12, 9, 1000, 209, 6, 209, 3, 203, 0, 1008, 1000, 1, 63, 1005,
+    63, 65, 1008, 1000, 2, 63, 1005, 63, 902, 1008, 1000, 0, 63, 1005, 63, 58,
+    4, 25, 104, 0, 99, 4, 0, 104, 0, 99, 4, 17, 104, 0, 99, 0,
+    0, 1101, 26, 0, 1015, 1101, 29, 0, 1010, 1102, 1, 24, 1013, 1102, 1, 33,
+    1008, 1102, 36, 1, 1012, 1101, 0, 572, 1023, 1101, 35, 0, 1014, 1101, 0, 38,
+    1019, 1102, 1, 30, 1006, 1101, 0, 890, 1029, 1101, 34, 0, 1011, 1101, 28, 0,
+    1002, 1102, 1, 1, 1021, 1101, 0, 37, 1001, 1101, 0, 197, 1026, 1101, 22, 0,
+    1017, 1102, 1, 895, 1028, 1101, 0, 20, 1007, 1102, 21, 1, 1004, 1102, 1, 39,
+    1016, 1101, 0, 0, 1020, 1102, 1, 190, 1027, 1101, 0, 775, 1024, 1102, 31, 1,
+    1018, 1101, 0, 23, 1003, 1101, 0, 25, 1009, 1101, 770, 0, 1025, 1101, 0, 27,
+    1000, 1102, 1, 575, 1022, 1101, 0, 32, 1005, 109, 27, 2106, 0, 0, 1001, 64,
+    1, 64, 1106, 0, 199, 4, 187, 1002, 64, 2, 64, 109, -18, 21101, 40, 0,
+    5, 1008, 1014, 39, 63, 1005, 63, 219, 1106, 0, 225, 4, 205, 1001, 64, 1,
+    64, 1002, 64, 2, 64, 109, -6, 1201, -1, 0, 63, 1008, 63, 28, 63, 1005,
+    63, 251, 4, 231, 1001, 64, 1, 64, 1105, 1, 251, 1002, 64, 2, 64, 109,
+    5, 21102, 41, 1, 3, 1008, 1011, 38, 63, 1005, 63, 271, 1105, 1, 277, 4,
+    257, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, -7, 2102, 1, 1, 63, 1008,
+    63, 28, 63, 1005, 63, 299, 4, 283, 1106, 0, 303, 1001, 64, 1, 64, 1002,
+    64, 2, 64, 109, -7, 1207, 10, 22, 63, 1005, 63, 321, 4, 309, 1106, 0,
+    325, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, 16, 2107, 31, -4, 63, 1005,
+    63, 345, 1001, 64, 1, 64, 1105, 1, 347, 4, 331, 1002, 64, 2, 64, 109,
+    -9, 1201, 3, 0, 63, 1008, 63, 18, 63, 1005, 63, 371, 1001, 64, 1, 64,
+    1106, 0, 373, 4, 353, 1002, 64, 2, 64, 109, 7, 1202, -7, 1, 63, 1008,
+    63, 40, 63, 1005, 63, 393, 1106, 0, 399, 4, 379, 1001, 64, 1, 64, 1002,
+    64, 2, 64, 109, -5, 1208, 5, 33, 63, 1005, 63, 417, 4, 405, 1106, 0,
+    421, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, 1, 1202, 2, 1, 63, 1008,
+    63, 30, 63, 1005, 63, 443, 4, 427, 1105, 1, 447, 1001, 64, 1, 64, 1002,
+    64, 2, 64, 109, -7, 2102, 1, 10, 63, 1008, 63, 19, 63, 1005, 63, 471,
+    1001, 64, 1, 64, 1105, 1, 473, 4, 453, 1002, 64, 2, 64, 109, 6, 2108,
+    21, 0, 63, 1005, 63, 489, 1105, 1, 495, 4, 479, 1001, 64, 1, 64, 1002,
+    64, 2, 64, 109, 9, 21108, 42, 42, 0, 1005, 1012, 513, 4, 501, 1105, 1,
+    517, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, 7, 21107, 43, 44, -1, 1005,
+    1018, 535, 4, 523, 1106, 0, 539, 1001, 64, 1, 64, 1002, 64, 2, 64, 109,
+    -5, 21101, 44, 0, 2, 1008, 1016, 44, 63, 1005, 63, 561, 4, 545, 1105, 1,
+    565, 1001, 64, 1, 64, 1002, 64, 2, 64, 2105, 1, 9, 1106, 0, 581, 4,
+    569, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, 13, 21107, 45, 44, -9, 1005,
+    1018, 597, 1105, 1, 603, 4, 587, 1001, 64, 1, 64, 1002, 64, 2, 64, 109,
+    -25, 2101, 0, 3, 63, 1008, 63, 32, 63, 1005, 63, 625, 4, 609, 1105, 1,
+    629, 1001, 64, 1, 64, 1002, 64, 2, 64, 109, 7, 1208, -7, 30, 63, 1005,
+    63, 645, 1105, 1, 651, 4, 635, 1001, 64, 1, 64, 1002, 64, 2, 64, 109,
+    -2, 21102, 46, 1, 9, 1008, 1016, 46, 63, 1005, 63, 677, 4, 657, 1001, 64,
+    1, 64, 1106, 0, 677, 1002, 64, 2, 64, 109, -2, 21108, 47, 48, 9, 1005,
+    1014, 697, 1001, 64, 1, 64, 1105, 1, 699, 4, 683, 1002, 64, 2, 64, 109,
+    14, 1205, 2, 713, 4, 705, 1105, 1, 717, 1001, 64, 1, 64, 1002, 64, 2,
+    64, 109, -7, 1206, 8, 735, 4, 723, 1001, 64, 1, 64, 1106, 0, 735, 1002,
+    64, 2, 64, 109, -18, 2101, 0, 6, 63, 1008, 63, 24, 63, 1005, 63, 759,
+    1001, 64, 1, 64, 1106, 0, 761, 4, 741, 1002, 64, 2, 64, 109, 29, 2105,
+    1, 1, 4, 767, 1106, 0, 779, 1001, 64, 1, 64, 1002, 64, 2, 64, 109,
+    -5, 1206, 3, 791, 1106, 0, 797, 4, 785, 1001, 64, 1, 64, 1002, 64, 2,
+    64, 109, -12, 2107, 31, -1, 63, 1005, 63, 819, 4, 803, 1001, 64, 1, 64,
+    1105, 1, 819, 1002, 64, 2, 64, 109, 7, 1205, 7, 835, 1001, 64, 1, 64,
+    1105, 1, 837, 4, 825, 1002, 64, 2, 64, 109, -11, 1207, 7, 24, 63, 1005,
+    63, 853, 1106, 0, 859, 4, 843, 1001, 64, 1, 64, 1002, 64, 2, 64, 109,
+    4, 2108, 27, -6, 63, 1005, 63, 881, 4, 865, 1001, 64, 1, 64, 1106, 0,
+    881, 1002, 64, 2, 64, 109, 24, 2106, 0, -2, 4, 887, 1106, 0, 899, 1001,
+    64, 1, 64, 4, 64, 99, 21102, 27, 1, 1, 21101, 0, 913, 0, 1106, 0,
+    920, 21201, 1, 61934, 1, 204, 1, 99, 109, 3, 1207, -2, 3, 63, 1005, 63,
+    962, 21201, -2, -1, 1, 21101, 0, 940, 0, 1106, 0, 920, 21202, 1, 1, -1,
+    21201, -2, -3, 1, 21101, 0, 955, 0, 1105, 1, 920, 22201, 1, -1, -2, 1105,
+    1, 966, 22102, 1, -2, -2, 109, -3, 2105, 1, 0,
+];
+
+/// One bit per cell of a compiled instruction.
+const COMPILED: [u64; 16] = [
+    0x7fffffffffffffff, 0xfffffffffffffffe, 0xffffffffffffffff, 0xffffffffffffffff,
+    0xffffffffffffffff, 0xffffffffffffffff, 0xffffffffffffffff, 0xffffffffffffffff,
+    0xffffffffffffffff, 0xffffffffffffffff, 0xffffffffffffffff, 0xffffffffffffffff,
+    0xffffffffffffffff, 0xffffffffffffffff, 0xffffffffffffffff, 0x00000000000007ff,
+];
+
+pub fn machine(input_buffer: VecDeque<VALUE>) -> Compiled {
+    Compiled::new(&CODE, &COMPILED, resume, input_buffer)
+}
+
+fn resume(m: &mut Native) -> Exit {
+    loop {
+        match m.ip {
+            // multiply #34463338, #34463338, 63
+            0 => {
+                m.store(63, 1187721666102244);
+                m.ip = 4;
+            },
+            // less-than 63, #34463338, 63
+            4 => {
+                let val = (m.load(63) < 34463338) as VALUE;
+                m.store(63, val);
+                m.ip = 8;
+            },
+            // jump-if-true 63, #53
+            8 => {
+                if m.load(63) != 0 {
+                    m.ip = 53;
+                } else {
+                    m.ip = 11;
+                }
+            },
+            // add #3, #0, 1000
+            11 => {
+                m.store(1000, 3);
+                m.ip = 15;
+            },
+            // relative-base-offset #988
+            15 => {
+                let Some(base) = m.relative_base.checked_add(988) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 17;
+            },
+            // relative-base-offset @12
+            17 => {
+                let Some(a) = m.relative(12) else { return Exit::Fallback };
+                let Some(base) = m.relative_base.checked_add(m.load(a)) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 19;
+            },
+            // relative-base-offset 1000
+            19 => {
+                let Some(base) = m.relative_base.checked_add(m.load(1000)) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 21;
+            },
+            // relative-base-offset @6
+            21 => {
+                let Some(a) = m.relative(6) else { return Exit::Fallback };
+                let Some(base) = m.relative_base.checked_add(m.load(a)) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 23;
+            },
+            // relative-base-offset @3
+            23 => {
+                let Some(a) = m.relative(3) else { return Exit::Fallback };
+                let Some(base) = m.relative_base.checked_add(m.load(a)) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 25;
+            },
+            // input @0
+            25 => {
+                let Some(a) = m.relative(0) else { return Exit::Fallback };
+                if !m.writable(a) { return Exit::Fallback }
+                let Some(val) = m.input_buffer.pop_front() else { return Exit::NeedsInput };
+                m.store(a, val);
+                m.ip = 27;
+            },
+            // equals 1000, #1, 63
+            27 => {
+                let val = (m.load(1000) == 1) as VALUE;
+                m.store(63, val);
+                m.ip = 31;
+            },
+            // jump-if-true 63, #65
+            31 => {
+                if m.load(63) != 0 {
+                    m.ip = 65;
+                } else {
+                    m.ip = 34;
+                }
+            },
+            // equals 1000, #2, 63
+            34 => {
+                let val = (m.load(1000) == 2) as VALUE;
+                m.store(63, val);
+                m.ip = 38;
+            },
+            // jump-if-true 63, #902
+            38 => {
+                if m.load(63) != 0 {
+                    m.ip = 902;
+                } else {
+                    m.ip = 41;
+                }
+            },
+            // equals 1000, #0, 63
+            41 => {
+                let val = (m.load(1000) == 0) as VALUE;
+                m.store(63, val);
+                m.ip = 45;
+            },
+            // jump-if-true 63, #58
+            45 => {
+                if m.load(63) != 0 {
+                    m.ip = 58;
+                } else {
+                    m.ip = 48;
+                }
+            },
+            // output 25
+            48 => {
+                let val = m.load(25);
+                m.ip = 50;
+                return Exit::Output(val);
+            },
+            // output #0
+            50 => {
+                let val = 0;
+                m.ip = 52;
+                return Exit::Output(val);
+            },
+            // halt 
+            52 => {
+                return Exit::Halted;
+            },
+            // output 0
+            53 => {
+                let val = m.load(0);
+                m.ip = 55;
+                return Exit::Output(val);
+            },
+            // output #0
+            55 => {
+                let val = 0;
+                m.ip = 57;
+                return Exit::Output(val);
+            },
+            // halt 
+            57 => {
+                return Exit::Halted;
+            },
+            // output 17
+            58 => {
+                let val = m.load(17);
+                m.ip = 60;
+                return Exit::Output(val);
+            },
+            // output #0
+            60 => {
+                let val = 0;
+                m.ip = 62;
+                return Exit::Output(val);
+            },
+            // halt 
+            62 => {
+                return Exit::Halted;
+            },
+            // add #26, #0, 1015
+            65 => {
+                m.store(1015, 26);
+                m.ip = 69;
+            },
+            // add #29, #0, 1010
+            69 => {
+                m.store(1010, 29);
+                m.ip = 73;
+            },
+            // multiply #1, #24, 1013
+            73 => {
+                m.store(1013, 24);
+                m.ip = 77;
+            },
+            // multiply #1, #33, 1008
+            77 => {
+                m.store(1008, 33);
+                m.ip = 81;
+            },
+            // multiply #36, #1, 1012
+            81 => {
+                m.store(1012, 36);
+                m.ip = 85;
+            },
+            // add #0, #572, 1023
+            85 => {
+                m.store(1023, 572);
+                m.ip = 89;
+            },
+            // add #35, #0, 1014
+            89 => {
+                m.store(1014, 35);
+                m.ip = 93;
+            },
+            // add #0, #38, 1019
+            93 => {
+                m.store(1019, 38);
+                m.ip = 97;
+            },
+            // multiply #1, #30, 1006
+            97 => {
+                m.store(1006, 30);
+                m.ip = 101;
+            },
+            // add #0, #890, 1029
+            101 => {
+                m.store(1029, 890);
+                m.ip = 105;
+            },
+            // add #34, #0, 1011
+            105 => {
+                m.store(1011, 34);
+                m.ip = 109;
+            },
+            // add #28, #0, 1002
+            109 => {
+                m.store(1002, 28);
+                m.ip = 113;
+            },
+            // multiply #1, #1, 1021
+            113 => {
+                m.store(1021, 1);
+                m.ip = 117;
+            },
+            // add #0, #37, 1001
+            117 => {
+                m.store(1001, 37);
+                m.ip = 121;
+            },
+            // add #0, #197, 1026
+            121 => {
+                m.store(1026, 197);
+                m.ip = 125;
+            },
+            // add #22, #0, 1017
+            125 => {
+                m.store(1017, 22);
+                m.ip = 129;
+            },
+            // multiply #1, #895, 1028
+            129 => {
+                m.store(1028, 895);
+                m.ip = 133;
+            },
+            // add #0, #20, 1007
+            133 => {
+                m.store(1007, 20);
+                m.ip = 137;
+            },
+            // multiply #21, #1, 1004
+            137 => {
+                m.store(1004, 21);
+                m.ip = 141;
+            },
+            // multiply #1, #39, 1016
+            141 => {
+                m.store(1016, 39);
+                m.ip = 145;
+            },
+            // add #0, #0, 1020
+            145 => {
+                m.store(1020, 0);
+                m.ip = 149;
+            },
+            // multiply #1, #190, 1027
+            149 => {
+                m.store(1027, 190);
+                m.ip = 153;
+            },
+            // add #0, #775, 1024
+            153 => {
+                m.store(1024, 775);
+                m.ip = 157;
+            },
+            // multiply #31, #1, 1018
+            157 => {
+                m.store(1018, 31);
+                m.ip = 161;
+            },
+            // add #0, #23, 1003
+            161 => {
+                m.store(1003, 23);
+                m.ip = 165;
+            },
+            // add #0, #25, 1009
+            165 => {
+                m.store(1009, 25);
+                m.ip = 169;
+            },
+            // add #770, #0, 1025
+            169 => {
+                m.store(1025, 770);
+                m.ip = 173;
+            },
+            // add #0, #27, 1000
+            173 => {
+                m.store(1000, 27);
+                m.ip = 177;
+            },
+            // multiply #1, #575, 1022
+            177 => {
+                m.store(1022, 575);
+                m.ip = 181;
+            },
+            // add #0, #32, 1005
+            181 => {
+                m.store(1005, 32);
+                m.ip = 185;
+            },
+            // relative-base-offset #27
+            185 => {
+                let Some(base) = m.relative_base.checked_add(27) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 187;
+            },
+            // jump-if-false #0, @0
+            187 => {
+                let Some(b) = m.relative(0) else { return Exit::Fallback };
+                let target = m.load(b);
+                if target < 0 { return Exit::Fallback }
+                m.ip = target as usize;
+            },
+            // add 64, #1, 64
+            190 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 194;
+            },
+            // jump-if-false #0, #199
+            194 => {
+                m.ip = 199;
+            },
+            // output 187
+            197 => {
+                let val = m.load(187);
+                m.ip = 199;
+                return Exit::Output(val);
+            },
+            // multiply 64, #2, 64
+            199 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 203;
+            },
+            // relative-base-offset #-18
+            203 => {
+                let Some(base) = m.relative_base.checked_add(-18) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 205;
+            },
+            // add #40, #0, @5
+            205 => {
+                let Some(c) = m.relative(5) else { return Exit::Fallback };
+                if !m.writable(c) { return Exit::Fallback }
+                m.store(c, 40);
+                m.ip = 209;
+            },
+            // equals 1014, #39, 63
+            209 => {
+                let val = (m.load(1014) == 39) as VALUE;
+                m.store(63, val);
+                m.ip = 213;
+            },
+            // jump-if-true 63, #219
+            213 => {
+                if m.load(63) != 0 {
+                    m.ip = 219;
+                } else {
+                    m.ip = 216;
+                }
+            },
+            // jump-if-false #0, #225
+            216 => {
+                m.ip = 225;
+            },
+            // output 205
+            219 => {
+                let val = m.load(205);
+                m.ip = 221;
+                return Exit::Output(val);
+            },
+            // add 64, #1, 64
+            221 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 225;
+            },
+            // multiply 64, #2, 64
+            225 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 229;
+            },
+            // relative-base-offset #-6
+            229 => {
+                let Some(base) = m.relative_base.checked_add(-6) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 231;
+            },
+            // add @-1, #0, 63
+            231 => {
+                let Some(a) = m.relative(-1) else { return Exit::Fallback };
+                let Some(val) = VALUE::checked_add(m.load(a), 0) else { return Exit::Fallback };
+                m.store(63, val);
+                m.ip = 235;
+            },
+            // equals 63, #28, 63
+            235 => {
+                let val = (m.load(63) == 28) as VALUE;
+                m.store(63, val);
+                m.ip = 239;
+            },
+            // jump-if-true 63, #251
+            239 => {
+                if m.load(63) != 0 {
+                    m.ip = 251;
+                } else {
+                    m.ip = 242;
+                }
+            },
+            // output 231
+            242 => {
+                let val = m.load(231);
+                m.ip = 244;
+                return Exit::Output(val);
+            },
+            // add 64, #1, 64
+            244 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 248;
+            },
+            // jump-if-true #1, #251
+            248 => {
+                m.ip = 251;
+            },
+            // multiply 64, #2, 64
+            251 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 255;
+            },
+            // relative-base-offset #5
+            255 => {
+                let Some(base) = m.relative_base.checked_add(5) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 257;
+            },
+            // multiply #41, #1, @3
+            257 => {
+                let Some(c) = m.relative(3) else { return Exit::Fallback };
+                if !m.writable(c) { return Exit::Fallback }
+                m.store(c, 41);
+                m.ip = 261;
+            },
+            // equals 1011, #38, 63
+            261 => {
+                let val = (m.load(1011) == 38) as VALUE;
+                m.store(63, val);
+                m.ip = 265;
+            },
+            // jump-if-true 63, #271
+            265 => {
+                if m.load(63) != 0 {
+                    m.ip = 271;
+                } else {
+                    m.ip = 268;
+                }
+            },
+            // jump-if-true #1, #277
+            268 => {
+                m.ip = 277;
+            },
+            // output 257
+            271 => {
+                let val = m.load(257);
+                m.ip = 273;
+                return Exit::Output(val);
+            },
+            // add 64, #1, 64
+            273 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 277;
+            },
+            // multiply 64, #2, 64
+            277 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 281;
+            },
+            // relative-base-offset #-7
+            281 => {
+                let Some(base) = m.relative_base.checked_add(-7) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 283;
+            },
+            // multiply #1, @1, 63
+            283 => {
+                let Some(b) = m.relative(1) else { return Exit::Fallback };
+                let Some(val) = VALUE::checked_mul(1, m.load(b)) else { return Exit::Fallback };
+                m.store(63, val);
+                m.ip = 287;
+            },
+            // equals 63, #28, 63
+            287 => {
+                let val = (m.load(63) == 28) as VALUE;
+                m.store(63, val);
+                m.ip = 291;
+            },
+            // jump-if-true 63, #299
+            291 => {
+                if m.load(63) != 0 {
+                    m.ip = 299;
+                } else {
+                    m.ip = 294;
+                }
+            },
+            // output 283
+            294 => {
+                let val = m.load(283);
+                m.ip = 296;
+                return Exit::Output(val);
+            },
+            // jump-if-false #0, #303
+            296 => {
+                m.ip = 303;
+            },
+            // add 64, #1, 64
+            299 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 303;
+            },
+            // multiply 64, #2, 64
+            303 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 307;
+            },
+            // relative-base-offset #-7
+            307 => {
+                let Some(base) = m.relative_base.checked_add(-7) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 309;
+            },
+            // less-than @10, #22, 63
+            309 => {
+                let Some(a) = m.relative(10) else { return Exit::Fallback };
+                let val = (m.load(a) < 22) as VALUE;
+                m.store(63, val);
+                m.ip = 313;
+            },
+            // jump-if-true 63, #321
+            313 => {
+                if m.load(63) != 0 {
+                    m.ip = 321;
+                } else {
+                    m.ip = 316;
+                }
+            },
+            // output 309
+            316 => {
+                let val = m.load(309);
+                m.ip = 318;
+                return Exit::Output(val);
+            },
+            // jump-if-false #0, #325
+            318 => {
+                m.ip = 325;
+            },
+            // add 64, #1, 64
+            321 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 325;
+            },
+            // multiply 64, #2, 64
+            325 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 329;
+            },
+            // relative-base-offset #16
+            329 => {
+                let Some(base) = m.relative_base.checked_add(16) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 331;
+            },
+            // less-than #31, @-4, 63
+            331 => {
+                let Some(b) = m.relative(-4) else { return Exit::Fallback };
+                let val = (31 < m.load(b)) as VALUE;
+                m.store(63, val);
+                m.ip = 335;
+            },
+            // jump-if-true 63, #345
+            335 => {
+                if m.load(63) != 0 {
+                    m.ip = 345;
+                } else {
+                    m.ip = 338;
+                }
+            },
+            // add 64, #1, 64
+            338 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 342;
+            },
+            // jump-if-true #1, #347
+            342 => {
+                m.ip = 347;
+            },
+            // output 331
+            345 => {
+                let val = m.load(331);
+                m.ip = 347;
+                return Exit::Output(val);
+            },
+            // multiply 64, #2, 64
+            347 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 351;
+            },
+            // relative-base-offset #-9
+            351 => {
+                let Some(base) = m.relative_base.checked_add(-9) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 353;
+            },
+            // add @3, #0, 63
+            353 => {
+                let Some(a) = m.relative(3) else { return Exit::Fallback };
+                let Some(val) = VALUE::checked_add(m.load(a), 0) else { return Exit::Fallback };
+                m.store(63, val);
+                m.ip = 357;
+            },
+            // equals 63, #18, 63
+            357 => {
+                let val = (m.load(63) == 18) as VALUE;
+                m.store(63, val);
+                m.ip = 361;
+            },
+            // jump-if-true 63, #371
+            361 => {
+                if m.load(63) != 0 {
+                    m.ip = 371;
+                } else {
+                    m.ip = 364;
+                }
+            },
+            // add 64, #1, 64
+            364 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 368;
+            },
+            // jump-if-false #0, #373
+            368 => {
+                m.ip = 373;
+            },
+            // output 353
+            371 => {
+                let val = m.load(353);
+                m.ip = 373;
+                return Exit::Output(val);
+            },
+            // multiply 64, #2, 64
+            373 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 377;
+            },
+            // relative-base-offset #7
+            377 => {
+                let Some(base) = m.relative_base.checked_add(7) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 379;
+            },
+            // multiply @-7, #1, 63
+            379 => {
+                let Some(a) = m.relative(-7) else { return Exit::Fallback };
+                let Some(val) = VALUE::checked_mul(m.load(a), 1) else { return Exit::Fallback };
+                m.store(63, val);
+                m.ip = 383;
+            },
+            // equals 63, #40, 63
+            383 => {
+                let val = (m.load(63) == 40) as VALUE;
+                m.store(63, val);
+                m.ip = 387;
+            },
+            // jump-if-true 63, #393
+            387 => {
+                if m.load(63) != 0 {
+                    m.ip = 393;
+                } else {
+                    m.ip = 390;
+                }
+            },
+            // jump-if-false #0, #399
+            390 => {
+                m.ip = 399;
+            },
+            // output 379
+            393 => {
+                let val = m.load(379);
+                m.ip = 395;
+                return Exit::Output(val);
+            },
+            // add 64, #1, 64
+            395 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 399;
+            },
+            // multiply 64, #2, 64
+            399 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 403;
+            },
+            // relative-base-offset #-5
+            403 => {
+                let Some(base) = m.relative_base.checked_add(-5) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 405;
+            },
+            // equals @5, #33, 63
+            405 => {
+                let Some(a) = m.relative(5) else { return Exit::Fallback };
+                let val = (m.load(a) == 33) as VALUE;
+                m.store(63, val);
+                m.ip = 409;
+            },
+            // jump-if-true 63, #417
+            409 => {
+                if m.load(63) != 0 {
+                    m.ip = 417;
+                } else {
+                    m.ip = 412;
+                }
+            },
+            // output 405
+            412 => {
+                let val = m.load(405);
+                m.ip = 414;
+                return Exit::Output(val);
+            },
+            // jump-if-false #0, #421
+            414 => {
+                m.ip = 421;
+            },
+            // add 64, #1, 64
+            417 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 421;
+            },
+            // multiply 64, #2, 64
+            421 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 425;
+            },
+            // relative-base-offset #1
+            425 => {
+                let Some(base) = m.relative_base.checked_add(1) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 427;
+            },
+            // multiply @2, #1, 63
+            427 => {
+                let Some(a) = m.relative(2) else { return Exit::Fallback };
+                let Some(val) = VALUE::checked_mul(m.load(a), 1) else { return Exit::Fallback };
+                m.store(63, val);
+                m.ip = 431;
+            },
+            // equals 63, #30, 63
+            431 => {
+                let val = (m.load(63) == 30) as VALUE;
+                m.store(63, val);
+                m.ip = 435;
+            },
+            // jump-if-true 63, #443
+            435 => {
+                if m.load(63) != 0 {
+                    m.ip = 443;
+                } else {
+                    m.ip = 438;
+                }
+            },
+            // output 427
+            438 => {
+                let val = m.load(427);
+                m.ip = 440;
+                return Exit::Output(val);
+            },
+            // jump-if-true #1, #447
+            440 => {
+                m.ip = 447;
+            },
+            // add 64, #1, 64
+            443 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 447;
+            },
+            // multiply 64, #2, 64
+            447 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 451;
+            },
+            // relative-base-offset #-7
+            451 => {
+                let Some(base) = m.relative_base.checked_add(-7) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 453;
+            },
+            // multiply #1, @10, 63
+            453 => {
+                let Some(b) = m.relative(10) else { return Exit::Fallback };
+                let Some(val) = VALUE::checked_mul(1, m.load(b)) else { return Exit::Fallback };
+                m.store(63, val);
+                m.ip = 457;
+            },
+            // equals 63, #19, 63
+            457 => {
+                let val = (m.load(63) == 19) as VALUE;
+                m.store(63, val);
+                m.ip = 461;
+            },
+            // jump-if-true 63, #471
+            461 => {
+                if m.load(63) != 0 {
+                    m.ip = 471;
+                } else {
+                    m.ip = 464;
+                }
+            },
+            // add 64, #1, 64
+            464 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 468;
+            },
+            // jump-if-true #1, #473
+            468 => {
+                m.ip = 473;
+            },
+            // output 453
+            471 => {
+                let val = m.load(453);
+                m.ip = 473;
+                return Exit::Output(val);
+            },
+            // multiply 64, #2, 64
+            473 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 477;
+            },
+            // relative-base-offset #6
+            477 => {
+                let Some(base) = m.relative_base.checked_add(6) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 479;
+            },
+            // equals #21, @0, 63
+            479 => {
+                let Some(b) = m.relative(0) else { return Exit::Fallback };
+                let val = (21 == m.load(b)) as VALUE;
+                m.store(63, val);
+                m.ip = 483;
+            },
+            // jump-if-true 63, #489
+            483 => {
+                if m.load(63) != 0 {
+                    m.ip = 489;
+                } else {
+                    m.ip = 486;
+                }
+            },
+            // jump-if-true #1, #495
+            486 => {
+                m.ip = 495;
+            },
+            // output 479
+            489 => {
+                let val = m.load(479);
+                m.ip = 491;
+                return Exit::Output(val);
+            },
+            // add 64, #1, 64
+            491 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 495;
+            },
+            // multiply 64, #2, 64
+            495 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 499;
+            },
+            // relative-base-offset #9
+            499 => {
+                let Some(base) = m.relative_base.checked_add(9) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 501;
+            },
+            // equals #42, #42, @0
+            501 => {
+                let Some(c) = m.relative(0) else { return Exit::Fallback };
+                if !m.writable(c) { return Exit::Fallback }
+                m.store(c, 1);
+                m.ip = 505;
+            },
+            // jump-if-true 1012, #513
+            505 => {
+                if m.load(1012) != 0 {
+                    m.ip = 513;
+                } else {
+                    m.ip = 508;
+                }
+            },
+            // output 501
+            508 => {
+                let val = m.load(501);
+                m.ip = 510;
+                return Exit::Output(val);
+            },
+            // jump-if-true #1, #517
+            510 => {
+                m.ip = 517;
+            },
+            // add 64, #1, 64
+            513 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 517;
+            },
+            // multiply 64, #2, 64
+            517 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 521;
+            },
+            // relative-base-offset #7
+            521 => {
+                let Some(base) = m.relative_base.checked_add(7) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 523;
+            },
+            // less-than #43, #44, @-1
+            523 => {
+                let Some(c) = m.relative(-1) else { return Exit::Fallback };
+                if !m.writable(c) { return Exit::Fallback }
+                m.store(c, 1);
+                m.ip = 527;
+            },
+            // jump-if-true 1018, #535
+            527 => {
+                if m.load(1018) != 0 {
+                    m.ip = 535;
+                } else {
+                    m.ip = 530;
+                }
+            },
+            // output 523
+            530 => {
+                let val = m.load(523);
+                m.ip = 532;
+                return Exit::Output(val);
+            },
+            // jump-if-false #0, #539
+            532 => {
+                m.ip = 539;
+            },
+            // add 64, #1, 64
+            535 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 539;
+            },
+            // multiply 64, #2, 64
+            539 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 543;
+            },
+            // relative-base-offset #-5
+            543 => {
+                let Some(base) = m.relative_base.checked_add(-5) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 545;
+            },
+            // add #44, #0, @2
+            545 => {
+                let Some(c) = m.relative(2) else { return Exit::Fallback };
+                if !m.writable(c) { return Exit::Fallback }
+                m.store(c, 44);
+                m.ip = 549;
+            },
+            // equals 1016, #44, 63
+            549 => {
+                let val = (m.load(1016) == 44) as VALUE;
+                m.store(63, val);
+                m.ip = 553;
+            },
+            // jump-if-true 63, #561
+            553 => {
+                if m.load(63) != 0 {
+                    m.ip = 561;
+                } else {
+                    m.ip = 556;
+                }
+            },
+            // output 545
+            556 => {
+                let val = m.load(545);
+                m.ip = 558;
+                return Exit::Output(val);
+            },
+            // jump-if-true #1, #565
+            558 => {
+                m.ip = 565;
+            },
+            // add 64, #1, 64
+            561 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 565;
+            },
+            // multiply 64, #2, 64
+            565 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 569;
+            },
+            // jump-if-true #1, @9
+            569 => {
+                let Some(b) = m.relative(9) else { return Exit::Fallback };
+                let target = m.load(b);
+                if target < 0 { return Exit::Fallback }
+                m.ip = target as usize;
+            },
+            // jump-if-false #0, #581
+            572 => {
+                m.ip = 581;
+            },
+            // output 569
+            575 => {
+                let val = m.load(569);
+                m.ip = 577;
+                return Exit::Output(val);
+            },
+            // add 64, #1, 64
+            577 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 581;
+            },
+            // multiply 64, #2, 64
+            581 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 585;
+            },
+            // relative-base-offset #13
+            585 => {
+                let Some(base) = m.relative_base.checked_add(13) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 587;
+            },
+            // less-than #45, #44, @-9
+            587 => {
+                let Some(c) = m.relative(-9) else { return Exit::Fallback };
+                if !m.writable(c) { return Exit::Fallback }
+                m.store(c, 0);
+                m.ip = 591;
+            },
+            // jump-if-true 1018, #597
+            591 => {
+                if m.load(1018) != 0 {
+                    m.ip = 597;
+                } else {
+                    m.ip = 594;
+                }
+            },
+            // jump-if-true #1, #603
+            594 => {
+                m.ip = 603;
+            },
+            // output 587
+            597 => {
+                let val = m.load(587);
+                m.ip = 599;
+                return Exit::Output(val);
+            },
+            // add 64, #1, 64
+            599 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 603;
+            },
+            // multiply 64, #2, 64
+            603 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 607;
+            },
+            // relative-base-offset #-25
+            607 => {
+                let Some(base) = m.relative_base.checked_add(-25) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 609;
+            },
+            // add #0, @3, 63
+            609 => {
+                let Some(b) = m.relative(3) else { return Exit::Fallback };
+                let Some(val) = VALUE::checked_add(0, m.load(b)) else { return Exit::Fallback };
+                m.store(63, val);
+                m.ip = 613;
+            },
+            // equals 63, #32, 63
+            613 => {
+                let val = (m.load(63) == 32) as VALUE;
+                m.store(63, val);
+                m.ip = 617;
+            },
+            // jump-if-true 63, #625
+            617 => {
+                if m.load(63) != 0 {
+                    m.ip = 625;
+                } else {
+                    m.ip = 620;
+                }
+            },
+            // output 609
+            620 => {
+                let val = m.load(609);
+                m.ip = 622;
+                return Exit::Output(val);
+            },
+            // jump-if-true #1, #629
+            622 => {
+                m.ip = 629;
+            },
+            // add 64, #1, 64
+            625 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 629;
+            },
+            // multiply 64, #2, 64
+            629 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 633;
+            },
+            // relative-base-offset #7
+            633 => {
+                let Some(base) = m.relative_base.checked_add(7) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 635;
+            },
+            // equals @-7, #30, 63
+            635 => {
+                let Some(a) = m.relative(-7) else { return Exit::Fallback };
+                let val = (m.load(a) == 30) as VALUE;
+                m.store(63, val);
+                m.ip = 639;
+            },
+            // jump-if-true 63, #645
+            639 => {
+                if m.load(63) != 0 {
+                    m.ip = 645;
+                } else {
+                    m.ip = 642;
+                }
+            },
+            // jump-if-true #1, #651
+            642 => {
+                m.ip = 651;
+            },
+            // output 635
+            645 => {
+                let val = m.load(635);
+                m.ip = 647;
+                return Exit::Output(val);
+            },
+            // add 64, #1, 64
+            647 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 651;
+            },
+            // multiply 64, #2, 64
+            651 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 655;
+            },
+            // relative-base-offset #-2
+            655 => {
+                let Some(base) = m.relative_base.checked_add(-2) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 657;
+            },
+            // multiply #46, #1, @9
+            657 => {
+                let Some(c) = m.relative(9) else { return Exit::Fallback };
+                if !m.writable(c) { return Exit::Fallback }
+                m.store(c, 46);
+                m.ip = 661;
+            },
+            // equals 1016, #46, 63
+            661 => {
+                let val = (m.load(1016) == 46) as VALUE;
+                m.store(63, val);
+                m.ip = 665;
+            },
+            // jump-if-true 63, #677
+            665 => {
+                if m.load(63) != 0 {
+                    m.ip = 677;
+                } else {
+                    m.ip = 668;
+                }
+            },
+            // output 657
+            668 => {
+                let val = m.load(657);
+                m.ip = 670;
+                return Exit::Output(val);
+            },
+            // add 64, #1, 64
+            670 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 674;
+            },
+            // jump-if-false #0, #677
+            674 => {
+                m.ip = 677;
+            },
+            // multiply 64, #2, 64
+            677 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 681;
+            },
+            // relative-base-offset #-2
+            681 => {
+                let Some(base) = m.relative_base.checked_add(-2) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 683;
+            },
+            // equals #47, #48, @9
+            683 => {
+                let Some(c) = m.relative(9) else { return Exit::Fallback };
+                if !m.writable(c) { return Exit::Fallback }
+                m.store(c, 0);
+                m.ip = 687;
+            },
+            // jump-if-true 1014, #697
+            687 => {
+                if m.load(1014) != 0 {
+                    m.ip = 697;
+                } else {
+                    m.ip = 690;
+                }
+            },
+            // add 64, #1, 64
+            690 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 694;
+            },
+            // jump-if-true #1, #699
+            694 => {
+                m.ip = 699;
+            },
+            // output 683
+            697 => {
+                let val = m.load(683);
+                m.ip = 699;
+                return Exit::Output(val);
+            },
+            // multiply 64, #2, 64
+            699 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 703;
+            },
+            // relative-base-offset #14
+            703 => {
+                let Some(base) = m.relative_base.checked_add(14) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 705;
+            },
+            // jump-if-true @2, #713
+            705 => {
+                let Some(a) = m.relative(2) else { return Exit::Fallback };
+                if m.load(a) != 0 {
+                    m.ip = 713;
+                } else {
+                    m.ip = 708;
+                }
+            },
+            // output 705
+            708 => {
+                let val = m.load(705);
+                m.ip = 710;
+                return Exit::Output(val);
+            },
+            // jump-if-true #1, #717
+            710 => {
+                m.ip = 717;
+            },
+            // add 64, #1, 64
+            713 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 717;
+            },
+            // multiply 64, #2, 64
+            717 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 721;
+            },
+            // relative-base-offset #-7
+            721 => {
+                let Some(base) = m.relative_base.checked_add(-7) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 723;
+            },
+            // jump-if-false @8, #735
+            723 => {
+                let Some(a) = m.relative(8) else { return Exit::Fallback };
+                if m.load(a) == 0 {
+                    m.ip = 735;
+                } else {
+                    m.ip = 726;
+                }
+            },
+            // output 723
+            726 => {
+                let val = m.load(723);
+                m.ip = 728;
+                return Exit::Output(val);
+            },
+            // add 64, #1, 64
+            728 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 732;
+            },
+            // jump-if-false #0, #735
+            732 => {
+                m.ip = 735;
+            },
+            // multiply 64, #2, 64
+            735 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 739;
+            },
+            // relative-base-offset #-18
+            739 => {
+                let Some(base) = m.relative_base.checked_add(-18) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 741;
+            },
+            // add #0, @6, 63
+            741 => {
+                let Some(b) = m.relative(6) else { return Exit::Fallback };
+                let Some(val) = VALUE::checked_add(0, m.load(b)) else { return Exit::Fallback };
+                m.store(63, val);
+                m.ip = 745;
+            },
+            // equals 63, #24, 63
+            745 => {
+                let val = (m.load(63) == 24) as VALUE;
+                m.store(63, val);
+                m.ip = 749;
+            },
+            // jump-if-true 63, #759
+            749 => {
+                if m.load(63) != 0 {
+                    m.ip = 759;
+                } else {
+                    m.ip = 752;
+                }
+            },
+            // add 64, #1, 64
+            752 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 756;
+            },
+            // jump-if-false #0, #761
+            756 => {
+                m.ip = 761;
+            },
+            // output 741
+            759 => {
+                let val = m.load(741);
+                m.ip = 761;
+                return Exit::Output(val);
+            },
+            // multiply 64, #2, 64
+            761 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 765;
+            },
+            // relative-base-offset #29
+            765 => {
+                let Some(base) = m.relative_base.checked_add(29) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 767;
+            },
+            // jump-if-true #1, @1
+            767 => {
+                let Some(b) = m.relative(1) else { return Exit::Fallback };
+                let target = m.load(b);
+                if target < 0 { return Exit::Fallback }
+                m.ip = target as usize;
+            },
+            // output 767
+            770 => {
+                let val = m.load(767);
+                m.ip = 772;
+                return Exit::Output(val);
+            },
+            // jump-if-false #0, #779
+            772 => {
+                m.ip = 779;
+            },
+            // add 64, #1, 64
+            775 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 779;
+            },
+            // multiply 64, #2, 64
+            779 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 783;
+            },
+            // relative-base-offset #-5
+            783 => {
+                let Some(base) = m.relative_base.checked_add(-5) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 785;
+            },
+            // jump-if-false @3, #791
+            785 => {
+                let Some(a) = m.relative(3) else { return Exit::Fallback };
+                if m.load(a) == 0 {
+                    m.ip = 791;
+                } else {
+                    m.ip = 788;
+                }
+            },
+            // jump-if-false #0, #797
+            788 => {
+                m.ip = 797;
+            },
+            // output 785
+            791 => {
+                let val = m.load(785);
+                m.ip = 793;
+                return Exit::Output(val);
+            },
+            // add 64, #1, 64
+            793 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 797;
+            },
+            // multiply 64, #2, 64
+            797 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 801;
+            },
+            // relative-base-offset #-12
+            801 => {
+                let Some(base) = m.relative_base.checked_add(-12) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 803;
+            },
+            // less-than #31, @-1, 63
+            803 => {
+                let Some(b) = m.relative(-1) else { return Exit::Fallback };
+                let val = (31 < m.load(b)) as VALUE;
+                m.store(63, val);
+                m.ip = 807;
+            },
+            // jump-if-true 63, #819
+            807 => {
+                if m.load(63) != 0 {
+                    m.ip = 819;
+                } else {
+                    m.ip = 810;
+                }
+            },
+            // output 803
+            810 => {
+                let val = m.load(803);
+                m.ip = 812;
+                return Exit::Output(val);
+            },
+            // add 64, #1, 64
+            812 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 816;
+            },
+            // jump-if-true #1, #819
+            816 => {
+                m.ip = 819;
+            },
+            // multiply 64, #2, 64
+            819 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 823;
+            },
+            // relative-base-offset #7
+            823 => {
+                let Some(base) = m.relative_base.checked_add(7) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 825;
+            },
+            // jump-if-true @7, #835
+            825 => {
+                let Some(a) = m.relative(7) else { return Exit::Fallback };
+                if m.load(a) != 0 {
+                    m.ip = 835;
+                } else {
+                    m.ip = 828;
+                }
+            },
+            // add 64, #1, 64
+            828 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 832;
+            },
+            // jump-if-true #1, #837
+            832 => {
+                m.ip = 837;
+            },
+            // output 825
+            835 => {
+                let val = m.load(825);
+                m.ip = 837;
+                return Exit::Output(val);
+            },
+            // multiply 64, #2, 64
+            837 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 841;
+            },
+            // relative-base-offset #-11
+            841 => {
+                let Some(base) = m.relative_base.checked_add(-11) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 843;
+            },
+            // less-than @7, #24, 63
+            843 => {
+                let Some(a) = m.relative(7) else { return Exit::Fallback };
+                let val = (m.load(a) < 24) as VALUE;
+                m.store(63, val);
+                m.ip = 847;
+            },
+            // jump-if-true 63, #853
+            847 => {
+                if m.load(63) != 0 {
+                    m.ip = 853;
+                } else {
+                    m.ip = 850;
+                }
+            },
+            // jump-if-false #0, #859
+            850 => {
+                m.ip = 859;
+            },
+            // output 843
+            853 => {
+                let val = m.load(843);
+                m.ip = 855;
+                return Exit::Output(val);
+            },
+            // add 64, #1, 64
+            855 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 859;
+            },
+            // multiply 64, #2, 64
+            859 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 863;
+            },
+            // relative-base-offset #4
+            863 => {
+                let Some(base) = m.relative_base.checked_add(4) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 865;
+            },
+            // equals #27, @-6, 63
+            865 => {
+                let Some(b) = m.relative(-6) else { return Exit::Fallback };
+                let val = (27 == m.load(b)) as VALUE;
+                m.store(63, val);
+                m.ip = 869;
+            },
+            // jump-if-true 63, #881
+            869 => {
+                if m.load(63) != 0 {
+                    m.ip = 881;
+                } else {
+                    m.ip = 872;
+                }
+            },
+            // output 865
+            872 => {
+                let val = m.load(865);
+                m.ip = 874;
+                return Exit::Output(val);
+            },
+            // add 64, #1, 64
+            874 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 878;
+            },
+            // jump-if-false #0, #881
+            878 => {
+                m.ip = 881;
+            },
+            // multiply 64, #2, 64
+            881 => {
+                let Some(val) = VALUE::checked_mul(m.load(64), 2) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 885;
+            },
+            // relative-base-offset #24
+            885 => {
+                let Some(base) = m.relative_base.checked_add(24) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 887;
+            },
+            // jump-if-false #0, @-2
+            887 => {
+                let Some(b) = m.relative(-2) else { return Exit::Fallback };
+                let target = m.load(b);
+                if target < 0 { return Exit::Fallback }
+                m.ip = target as usize;
+            },
+            // output 887
+            890 => {
+                let val = m.load(887);
+                m.ip = 892;
+                return Exit::Output(val);
+            },
+            // jump-if-false #0, #899
+            892 => {
+                m.ip = 899;
+            },
+            // add 64, #1, 64
+            895 => {
+                let Some(val) = VALUE::checked_add(m.load(64), 1) else { return Exit::Fallback };
+                m.store(64, val);
+                m.ip = 899;
+            },
+            // output 64
+            899 => {
+                let val = m.load(64);
+                m.ip = 901;
+                return Exit::Output(val);
+            },
+            // halt 
+            901 => {
+                return Exit::Halted;
+            },
+            // multiply #27, #1, @1
+            902 => {
+                let Some(c) = m.relative(1) else { return Exit::Fallback };
+                if !m.writable(c) { return Exit::Fallback }
+                m.store(c, 27);
+                m.ip = 906;
+            },
+            // add #0, #913, @0
+            906 => {
+                let Some(c) = m.relative(0) else { return Exit::Fallback };
+                if !m.writable(c) { return Exit::Fallback }
+                m.store(c, 913);
+                m.ip = 910;
+            },
+            // jump-if-false #0, #920
+            910 => {
+                m.ip = 920;
+            },
+            // add @1, #61934, @1
+            913 => {
+                let Some(a) = m.relative(1) else { return Exit::Fallback };
+                let Some(c) = m.relative(1) else { return Exit::Fallback };
+                if !m.writable(c) { return Exit::Fallback }
+                let Some(val) = VALUE::checked_add(m.load(a), 61934) else { return Exit::Fallback };
+                m.store(c, val);
+                m.ip = 917;
+            },
+            // output @1
+            917 => {
+                let Some(a) = m.relative(1) else { return Exit::Fallback };
+                let val = m.load(a);
+                m.ip = 919;
+                return Exit::Output(val);
+            },
+            // halt 
+            919 => {
+                return Exit::Halted;
+            },
+            // relative-base-offset #3
+            920 => {
+                let Some(base) = m.relative_base.checked_add(3) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 922;
+            },
+            // less-than @-2, #3, 63
+            922 => {
+                let Some(a) = m.relative(-2) else { return Exit::Fallback };
+                let val = (m.load(a) < 3) as VALUE;
+                m.store(63, val);
+                m.ip = 926;
+            },
+            // jump-if-true 63, #962
+            926 => {
+                if m.load(63) != 0 {
+                    m.ip = 962;
+                } else {
+                    m.ip = 929;
+                }
+            },
+            // add @-2, #-1, @1
+            929 => {
+                let Some(a) = m.relative(-2) else { return Exit::Fallback };
+                let Some(c) = m.relative(1) else { return Exit::Fallback };
+                if !m.writable(c) { return Exit::Fallback }
+                let Some(val) = VALUE::checked_add(m.load(a), -1) else { return Exit::Fallback };
+                m.store(c, val);
+                m.ip = 933;
+            },
+            // add #0, #940, @0
+            933 => {
+                let Some(c) = m.relative(0) else { return Exit::Fallback };
+                if !m.writable(c) { return Exit::Fallback }
+                m.store(c, 940);
+                m.ip = 937;
+            },
+            // jump-if-false #0, #920
+            937 => {
+                m.ip = 920;
+            },
+            // multiply @1, #1, @-1
+            940 => {
+                let Some(a) = m.relative(1) else { return Exit::Fallback };
+                let Some(c) = m.relative(-1) else { return Exit::Fallback };
+                if !m.writable(c) { return Exit::Fallback }
+                let Some(val) = VALUE::checked_mul(m.load(a), 1) else { return Exit::Fallback };
+                m.store(c, val);
+                m.ip = 944;
+            },
+            // add @-2, #-3, @1
+            944 => {
+                let Some(a) = m.relative(-2) else { return Exit::Fallback };
+                let Some(c) = m.relative(1) else { return Exit::Fallback };
+                if !m.writable(c) { return Exit::Fallback }
+                let Some(val) = VALUE::checked_add(m.load(a), -3) else { return Exit::Fallback };
+                m.store(c, val);
+                m.ip = 948;
+            },
+            // add #0, #955, @0
+            948 => {
+                let Some(c) = m.relative(0) else { return Exit::Fallback };
+                if !m.writable(c) { return Exit::Fallback }
+                m.store(c, 955);
+                m.ip = 952;
+            },
+            // jump-if-true #1, #920
+            952 => {
+                m.ip = 920;
+            },
+            // add @1, @-1, @-2
+            955 => {
+                let Some(a) = m.relative(1) else { return Exit::Fallback };
+                let Some(b) = m.relative(-1) else { return Exit::Fallback };
+                let Some(c) = m.relative(-2) else { return Exit::Fallback };
+                if !m.writable(c) { return Exit::Fallback }
+                let Some(val) = VALUE::checked_add(m.load(a), m.load(b)) else { return Exit::Fallback };
+                m.store(c, val);
+                m.ip = 959;
+            },
+            // jump-if-true #1, #966
+            959 => {
+                m.ip = 966;
+            },
+            // multiply #1, @-2, @-2
+            962 => {
+                let Some(b) = m.relative(-2) else { return Exit::Fallback };
+                let Some(c) = m.relative(-2) else { return Exit::Fallback };
+                if !m.writable(c) { return Exit::Fallback }
+                let Some(val) = VALUE::checked_mul(1, m.load(b)) else { return Exit::Fallback };
+                m.store(c, val);
+                m.ip = 966;
+            },
+            // relative-base-offset #-3
+            966 => {
+                let Some(base) = m.relative_base.checked_add(-3) else { return Exit::Fallback };
+                m.relative_base = base;
+                m.ip = 968;
+            },
+            // jump-if-true #1, @0
+            968 => {
+                let Some(b) = m.relative(0) else { return Exit::Fallback };
+                let target = m.load(b);
+                if target < 0 { return Exit::Fallback }
+                m.ip = target as usize;
+            },
+            _ => return Exit::Fallback,
+        }
+    }
+}
@@ -0,0 +1,4 @@
+//! Programs of `data` translated by `aot::translate` which run natively to
+//! the end, regenerated with `cargo run -- compile data/day9.txt > src/compiled/day9.rs`.
+
+pub mod day9;
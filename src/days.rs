@@ -1,29 +1,252 @@
 use src::Interpreter;
 use std::collections::HashMap;
+use std::error::Error;
 use std::fmt::Debug;
 use std::fmt::Display;
+use std::io::{self, Write};
 use num_complex::Complex;
 
+use crate::ascii::ConsoleError;
+use crate::loader::LoadError;
 use crate::src;
+use crate::src::{ErrorKind, InterpreterError, RunResult};
 use crate::{
     execute,
-    string_to_code,
+    loader,
 };
 
+/// Why a solution found no answer.
+#[derive(Debug)]
+pub enum DayError {
+    Io(io::Error),
+    Load(LoadError),
+    Fault(InterpreterError),
+    /// The program behaved unlike the puzzle describes, eg. it halted before the answer.
+    Unexpected(String),
+}
+
+impl Display for DayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DayError::Io(e) => write!(f, "{}", e),
+            DayError::Load(e) => write!(f, "{}", e),
+            DayError::Fault(e) => write!(f, "{}", e),
+            DayError::Unexpected(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for DayError {}
+
+impl From<io::Error> for DayError {
+    fn from(e: io::Error) -> DayError {
+        DayError::Io(e)
+    }
+}
+
+impl From<LoadError> for DayError {
+    fn from(e: LoadError) -> DayError {
+        DayError::Load(e)
+    }
+}
+
+impl From<InterpreterError> for DayError {
+    fn from(e: InterpreterError) -> DayError {
+        DayError::Fault(e)
+    }
+}
+
+impl From<ConsoleError> for DayError {
+    fn from(e: ConsoleError) -> DayError {
+        match e {
+            ConsoleError::Io(e) => DayError::Io(e),
+            ConsoleError::Fault(e) => DayError::Fault(e),
+        }
+    }
+}
+
+/// The error for a result of `pc` other than the `expected` one. Missing input and an
+/// exhausted budget are reported as faults, like the command line interface does.
+fn unexpected(pc: &Interpreter, res: Result<RunResult, InterpreterError>, expected: &str) -> DayError {
+    match res {
+        Err(e) => e.into(),
+        Ok(RunResult::NeedsInput) => pc.fault(ErrorKind::NoInput, Some(0)).into(),
+        Ok(RunResult::BudgetExhausted(limit)) => pc.fault(ErrorKind::BudgetExhausted(limit), None).into(),
+        Ok(res) => DayError::Unexpected(format!("Expected {}, got {:?}", expected, res)),
+    }
+}
+
+/// Runs the program until its next output, which it expects to exist.
+fn next_output(pc: &mut Interpreter) -> Result<src::VALUE, DayError> {
+    match pc.step_loop() {
+        Ok(RunResult::Output(val)) => Ok(val),
+        res => Err(unexpected(pc, res, "output")),
+    }
+}
+
+/// Runs the program on `input` and returns its last output, eg. a diagnostic code.
+fn last_output(code: &str, input: src::VALUE) -> Result<src::VALUE, DayError> {
+    let outputs = execute(loader::parse(code)?, vec![input].into())?;
+    outputs.last().copied().ok_or_else(|| DayError::Unexpected("Expected output, got none".to_owned()))
+}
+
 
 pub mod day17 {
     use super::*;
-    pub fn camera() {
-        let data = string_to_code(include_str!("../data/day17.txt"));
-        let mut pc = Interpreter::new(data, vec![].into());
+    use crate::ascii::{Console, Event};
+
+    /// The longest movement function or main routine the robot accepts, in characters.
+    const MAX_ROUTINE: usize = 20;
+
+    fn console() -> Result<Console, DayError> {
+        Ok(Console::new(Interpreter::new(loader::parse(include_str!("../data/day17.txt"))?, vec![].into())))
+    }
+
+    /// The image of the cameras, one line per row.
+    fn scaffold() -> Result<Vec<Vec<u8>>, DayError> {
+        let mut console = console()?;
+        let mut image = vec![];
+        loop {
+            match console.next_event()? {
+                Event::Line(line) if !line.is_empty() => image.push(line.into_bytes()),
+                Event::Line(_) => {},
+                Event::Halted => break,
+                event => return Err(DayError::Unexpected(format!("Unexpected camera output {:?}", event))),
+            }
+        }
+        Ok(image)
+    }
+
+    pub fn camera(out: &mut dyn Write) -> Result<(), DayError> {
+        for line in scaffold()? {
+            out.write_all(&line)?;
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    fn is_scaffold(image: &[Vec<u8>], pos: Complex<i32>) -> bool {
+        usize::try_from(pos.im).ok()
+            .and_then(|y| image.get(y))
+            .and_then(|row| usize::try_from(pos.re).ok().and_then(|x| row.get(x)))
+            .is_some_and(|&c| c != b'.')
+    }
+
+    pub fn day17a(out: &mut dyn Write) -> Result<src::VALUE, DayError> {
+        let image = scaffold()?;
+        let mut result = 0;
+        for (y, row) in image.iter().enumerate() {
+            for x in 0..row.len() {
+                let pos = Complex::new(x as i32, y as i32);
+                let neighbours = [1, -1].iter().flat_map(|&d| [Complex::new(d, 0), Complex::new(0, d)]);
+                if is_scaffold(&image, pos) && neighbours.into_iter().all(|d| is_scaffold(&image, pos + d)) {
+                    result += (x * y) as src::VALUE;
+                }
+            }
+        }
 
+        writeln!(out, "Result / Sum of alignment parameters: {}", result)?;
+        Ok(result)
+    }
+
+    /// The path of the robot to the end of the scaffold, going straight at intersections, eg.
+    /// `["R,8", "L,10"]`. `None` if there is no robot on the scaffold.
+    fn path(image: &[Vec<u8>]) -> Option<Vec<String>> {
+        let (mut pos, mut dir) = image.iter().enumerate()
+            .find_map(|(y, row)| {
+                let x = row.iter().position(|c| b"^v<>".contains(c))?;
+                let dir = match row[x] {
+                    b'^' => Complex::new(0, -1),
+                    b'v' => Complex::new(0, 1),
+                    b'<' => Complex::new(-1, 0),
+                    _ => Complex::new(1, 0),
+                };
+                Some((Complex::new(x as i32, y as i32), dir))
+            })?;
+
+        let mut path = vec![];
         loop {
-            let c = pc.step_loop();
+            // y grows downwards, hence multiplying by -i turns left
+            let turn = if is_scaffold(image, pos + dir * Complex::new(0, -1)) {
+                dir *= Complex::new(0, -1);
+                'L'
+            } else if is_scaffold(image, pos + dir * Complex::new(0, 1)) {
+                dir *= Complex::new(0, 1);
+                'R'
+            } else {
+                return Some(path);
+            };
 
-            match c {
-                Err(src::InterpreterError::Terminated) => { break; },
-                Err(e) => println!("Error: {:?}", e),
-                Ok(c) => print!("{}", c as u8 as char),
+            let mut steps = 0;
+            while is_scaffold(image, pos + dir) {
+                pos += dir;
+                steps += 1;
+            }
+            path.push(format!("{},{}", turn, steps));
+        }
+    }
+
+    /// Splits `path` into calls of at most three movement functions, which fit the robot's memory.
+    fn compress<'a>(path: &'a [String], functions: &mut Vec<&'a [String]>, main: &mut Vec<usize>) -> bool {
+        if path.is_empty() {
+            return true;
+        }
+        if 2 * main.len() + 1 > MAX_ROUTINE {
+            return false;
+        }
+
+        for k in 0..functions.len() {
+            let function = functions[k];
+            if path.starts_with(function) {
+                main.push(k);
+                if compress(&path[function.len()..], functions, main) {
+                    return true;
+                }
+                main.pop();
+            }
+        }
+
+        if functions.len() < 3 {
+            for len in 1..=path.len() {
+                if path[..len].join(",").len() > MAX_ROUTINE {
+                    break;
+                }
+                functions.push(&path[..len]);
+                main.push(functions.len() - 1);
+                if compress(&path[len..], functions, main) {
+                    return true;
+                }
+                main.pop();
+                functions.pop();
+            }
+        }
+        false
+    }
+
+    pub fn day17b(out: &mut dyn Write) -> Result<src::VALUE, DayError> {
+        let path = path(&scaffold()?).ok_or_else(|| DayError::Unexpected("No robot on the scaffold".to_owned()))?;
+        let (mut functions, mut main) = (vec![], vec![]);
+        if !compress(&path, &mut functions, &mut main) {
+            return Err(DayError::Unexpected("The path does not fit into three functions".to_owned()));
+        }
+
+        let mut console = console()?;
+        console.pc.write(0, 2)?;
+        console.send_line(&main.iter().map(|&k| ["A", "B", "C"][k]).collect::<Vec<_>>().join(","));
+        for k in 0..3 {
+            console.send_line(&functions.get(k).map_or(String::new(), |function| function.join(",")));
+        }
+        // No continuous video feed
+        console.send_line("n");
+
+        loop {
+            match console.next_event()? {
+                Event::Line(_) => {},
+                Event::Number(result) => {
+                    writeln!(out, "Result / Collected dust: {}", result)?;
+                    return Ok(result);
+                },
+                event => return Err(DayError::Unexpected(format!("Unexpected output {:?}", event))),
             }
         }
     }
@@ -58,144 +281,135 @@ pub mod day15 {
     }
 
     impl Game {
-        pub fn new() -> Game {
-            let data = string_to_code(include_str!("../data/day15.txt"));
+        pub fn new() -> Result<Game, DayError> {
+            let data = loader::parse(include_str!("../data/day15.txt"))?;
             let pc = Interpreter::new(data, vec![].into());
 
-            Game {
+            Ok(Game {
                 pc,
                 grid: [[(Tile::Unknown, 0usize); SIZE.0]; SIZE.1],
                 pos: (CENTER.0, CENTER.1),
-            }
+            })
         }
 
+        /// Moves the droid in direction `d` and returns the tile it reports there.
+        fn probe(&mut self, d: Dir) -> Result<Tile, DayError> {
+            self.pc.input_buffer.push_back(d as i64);
+            match next_output(&mut self.pc)? {
+                0 => { Ok(Tile::Wall) },
+                1 => { Ok(Tile::Empty) },
+                2 => { Ok(Tile::Goal) },
+                status => { Err(DayError::Unexpected(format!("Unknown status {}", status))) }
+            }
+        }
 
-        fn dfs(&mut self) -> Option<usize> {
-            println!("{}", self);
+        fn dfs(&mut self) -> Result<Option<usize>, DayError> {
             for d in [Dir::N, Dir::S, Dir::W, Dir::E] {
                 let prev = self.pos;
 
                 let next = match d {
-                    Dir::N => {(prev.0 + 0, prev.1 - 1)},
-                    Dir::S => {(prev.0 + 0, prev.1 + 1)},
-                    Dir::W => {(prev.0 - 1, prev.1 + 0)},
-                    Dir::E => {(prev.0 + 1, prev.1 + 0)},
+                    Dir::N => {(prev.0, prev.1 - 1)},
+                    Dir::S => {(prev.0, prev.1 + 1)},
+                    Dir::W => {(prev.0 - 1, prev.1)},
+                    Dir::E => {(prev.0 + 1, prev.1)},
                 };
 
 
                 if let Tile::Unknown = self.grid[next.1][next.0].0 {
-                    self.pc.input_buffer.push_back(d as i64);
+                    // Fork the droid, instead of walking it back afterwards
+                    let saved = self.pc.clone();
 
                     let val_prev = self.grid[prev.1][prev.0].1;
-                    let res = match self.pc.step_loop() {
-                        Err(_) => { panic!() },
-                        Ok(0) => { Tile::Wall },
-                        Ok(1) => { Tile::Empty },
-                        Ok(2) => { Tile::Goal },
-                        Ok(_) => { panic!() }
-                    };
+                    let res = self.probe(d)?;
 
                     self.grid[next.1][next.0] = (res, val_prev + 1);
 
                     if res == Tile::Wall {
+                        self.pc = saved;
                         continue;
                     }
 
                     self.pos = next;
                     if res == Tile::Goal {
-                        return Some(val_prev + 1);
+                        return Ok(Some(val_prev + 1));
                     }
 
-                    if let Some(distance) = self.dfs() {
-                        return Some(distance);
+                    if let Some(distance) = self.dfs()? {
+                        return Ok(Some(distance));
                     }
 
-                    self.pc.input_buffer.push_back(
-                        match d {
-                            Dir::N => Dir::S as i64,
-                            Dir::S => Dir::N as i64,
-                            Dir::W => Dir::E as i64,
-                            Dir::E => Dir::W as i64,
-                        });
-
-                    self.pc.step_loop().unwrap();
+                    self.pc = saved;
                     self.pos = prev;
                 }
             }
-            None
+            Ok(None)
         }
 
-        fn dfs_b(&mut self) -> usize {
-            println!("{}", self);
+        fn dfs_b(&mut self) -> Result<usize, DayError> {
             let mut maxi = 0;
             for d in [Dir::N, Dir::S, Dir::W, Dir::E] {
                 let prev = self.pos;
 
                 let next = match d {
-                    Dir::N => {(prev.0 + 0, prev.1 - 1)},
-                    Dir::S => {(prev.0 + 0, prev.1 + 1)},
-                    Dir::W => {(prev.0 - 1, prev.1 + 0)},
-                    Dir::E => {(prev.0 + 1, prev.1 + 0)},
+                    Dir::N => {(prev.0, prev.1 - 1)},
+                    Dir::S => {(prev.0, prev.1 + 1)},
+                    Dir::W => {(prev.0 - 1, prev.1)},
+                    Dir::E => {(prev.0 + 1, prev.1)},
                 };
 
 
                 if let Tile::Unknown = self.grid[next.1][next.0].0 {
-                    self.pc.input_buffer.push_back(d as i64);
+                    // Fork the droid, instead of walking it back afterwards
+                    let saved = self.pc.clone();
 
                     let val_prev = self.grid[prev.1][prev.0].1;
-                    let res = match self.pc.step_loop() {
-                        Err(_) => { panic!() },
-                        Ok(0) => { Tile::Wall },
-                        Ok(1) => { Tile::Empty },
-                        Ok(2) => { Tile::Goal },
-                        Ok(_) => { panic!() }
-                    };
+                    let res = self.probe(d)?;
 
                     self.grid[next.1][next.0] = (res, val_prev + 1);
 
                     if res == Tile::Wall {
+                        self.pc = saved;
                         continue;
                     }
 
                     self.pos = next;
 
-                    maxi = maxi.max(1 + self.dfs_b());
-
+                    maxi = maxi.max(1 + self.dfs_b()?);
 
-                    self.pc.input_buffer.push_back(
-                        match d {
-                            Dir::N => Dir::S as i64,
-                            Dir::S => Dir::N as i64,
-                            Dir::W => Dir::E as i64,
-                            Dir::E => Dir::W as i64,
-                        });
 
-                    self.pc.step_loop().unwrap();
+                    self.pc = saved;
                     self.pos = prev;
                 }
             }
-            maxi
+            Ok(maxi)
         }
 
     }
 
-    pub fn day15a() -> src::VALUE {
-        let mut game = Game::new();
-        let dist = game.dfs().unwrap();
-        println!("Distance to oxygen generator: {}", dist);
-        dist as src::VALUE
+    /// Searches the oxygen generator, leaving the droid there.
+    fn oxygen_generator(game: &mut Game, out: &mut dyn Write) -> Result<usize, DayError> {
+        let dist = game.dfs()?.ok_or_else(|| DayError::Unexpected("No oxygen generator".to_owned()))?;
+        write!(out, "{}", game)?;
+        writeln!(out, "Distance to oxygen generator: {}", dist)?;
+        Ok(dist)
+    }
+
+    pub fn day15a(out: &mut dyn Write) -> Result<src::VALUE, DayError> {
+        let mut game = Game::new()?;
+        let dist = oxygen_generator(&mut game, out)?;
+        Ok(dist as src::VALUE)
     }
 
-    pub fn day15b() -> src::VALUE {
-        let mut game = Game::new();
-        let dist = game.dfs().unwrap();
-        println!("Distance to oxygen generator: {}", dist);
+    pub fn day15b(out: &mut dyn Write) -> Result<src::VALUE, DayError> {
+        let mut game = Game::new()?;
+        oxygen_generator(&mut game, out)?;
 
         // Reset game:
         game.grid = [[(Tile::Unknown, 0usize); SIZE.0]; SIZE.1];
-        let dist = game.dfs_b();
-        println!("Maximal distance from oxygen generator: {}", dist);
-        dist as src::VALUE
+        let dist = game.dfs_b()?;
+        write!(out, "{}", game)?;
+        writeln!(out, "Maximal distance from oxygen generator: {}", dist)?;
+        Ok(dist as src::VALUE)
     }
 
 
@@ -230,8 +444,6 @@ pub mod day15 {
 }
 
 pub mod day13 {
-    use std::{process::exit, time::Duration};
-
     use super::*;
 
     const SIZE_X: usize = 46;
@@ -247,28 +459,17 @@ pub mod day13 {
     }
 
     impl Game {
-        pub fn new() -> Game {
-            let mut data = string_to_code(include_str!("../data/day13.txt"));
+        pub fn new() -> Result<Game, DayError> {
+            let mut data = loader::parse(include_str!("../data/day13.txt"))?;
             data[0] = 2;
             let mut pc = Interpreter::new(data, vec![].into());
 
             let mut grid = [[Tile::Empty; 46]; 26];
 
             loop {
-                let posx = match pc.step_loop() {
-                    //Err(src::InterpreterError::NoInputError) => { break; },
-                    Err(_) => { panic!() },
-                    Ok(val) => { val },
-                };
-                let posy = pc.step_loop().unwrap();
-                let tile_tyle: Tile = match pc.step_loop().unwrap() {
-                    0 => { Tile::Empty },
-                    1 => { Tile::Wall },
-                    2 => { Tile::Block },
-                    3 => { Tile::Paddle },
-                    4 => { Tile::Ball },
-                    _ => { panic!() },
-                };
+                let posx = next_output(&mut pc)?;
+                let posy = next_output(&mut pc)?;
+                let tile_tyle = Tile::new(next_output(&mut pc)?)?;
 
                 if (posx, posy) == (-1, 0) { break; }
 
@@ -281,27 +482,25 @@ pub mod day13 {
                 .filter(|&x| *x == Tile::Block )
                 .count();
 
-            Game {
+            Ok(Game {
                 pc,
                 score: 0,
                 grid,
                 number_blocks,
                 pos_ball: (0,0),
                 pos_paddle: (0,0),
-            }
+            })
         }
 
-        fn solve(&mut self) {
+        /// Plays until the game halts, returning the final score.
+        fn solve(&mut self) -> Result<src::VALUE, DayError> {
             loop {
                 let res = self.pc.step_loop();
-                //std::thread::sleep(Duration::new(0, 1_000_000));
 
                 let posx = match res {
-                    Err(src::InterpreterError::NoInputError) => { 
+                    Ok(RunResult::NeedsInput) => { 
                         //let mut input: String = String::new();
                         assert!(self.pc.input_buffer.is_empty());
-                        print!("{}", self);
-                        print!("Ball: {:?}, Paddle: {:?}", self.pos_ball, self.pos_paddle);
                         //io::stdin().read_line(&mut input).unwrap();
                         
                         let next_in = if self.pos_paddle.0 > self.pos_ball.0 {
@@ -316,24 +515,17 @@ pub mod day13 {
                         self.pc.input_buffer.push_back(next_in);
                         continue;
                     }
-                    Err(src::InterpreterError::Terminated) => { println!("FINAL SCORE: {}", self.score); exit(0) }
-                    Err(_) => {panic!()}
-                    Ok(val) => { val }
+                    Ok(RunResult::Halted) => { return Ok(self.score) }
+                    Ok(RunResult::Output(val)) => { val }
+                    res => { return Err(unexpected(&self.pc, res, "output")) }
                 };
-                let posy = self.pc.step_loop().unwrap();
-                let val = self.pc.step_loop().unwrap();
+                let posy = next_output(&mut self.pc)?;
+                let val = next_output(&mut self.pc)?;
 
                 if (posx, posy) == (-1, 0) { 
                     self.score = val;
                 } else {
-                    let val = match val {
-                        0 => { Tile::Empty },
-                        1 => { Tile::Wall },
-                        2 => { Tile::Block },
-                        3 => { Tile::Paddle },
-                        4 => { Tile::Ball },
-                        _ => { panic!() },
-                    };
+                    let val = Tile::new(val)?;
                     if self.grid[posy as usize][posx as usize] == Tile::Block {
                         self.number_blocks -= 1;
                     }
@@ -363,7 +555,7 @@ pub mod day13 {
                      .collect::<String>() + "\n")
                 .collect();
 
-            s.extend(ss.chars());
+            s.push_str(&ss);
 
             write!(f, "{}", s)
         }
@@ -378,6 +570,19 @@ pub mod day13 {
         Ball,
     }
 
+    impl Tile {
+        fn new(id: src::VALUE) -> Result<Tile, DayError> {
+            match id {
+                0 => { Ok(Tile::Empty) },
+                1 => { Ok(Tile::Wall) },
+                2 => { Ok(Tile::Block) },
+                3 => { Ok(Tile::Paddle) },
+                4 => { Ok(Tile::Ball) },
+                _ => { Err(DayError::Unexpected(format!("Unknown tile {}", id))) },
+            }
+        }
+    }
+
     impl Display for Tile {
         fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
             write!(f, "{}", 
@@ -404,16 +609,18 @@ pub mod day13 {
         }
     }
 
-    pub fn day13a() -> src::VALUE {
-        let game = Game::new();
-        print!("{}", game);
-        return game.number_blocks as src::VALUE;
+    pub fn day13a(out: &mut dyn Write) -> Result<src::VALUE, DayError> {
+        let game = Game::new()?;
+        write!(out, "{}", game)?;
+        Ok(game.number_blocks as src::VALUE)
     }
 
-    pub fn day13b() -> src::VALUE {
-        let mut game = Game::new();
-        game.solve();
-        game.score
+    pub fn day13b(out: &mut dyn Write) -> Result<src::VALUE, DayError> {
+        let mut game = Game::new()?;
+        let score = game.solve()?;
+        write!(out, "{}", game)?;
+        writeln!(out, "FINAL SCORE: {}", score)?;
+        Ok(score)
     }
 
 
@@ -421,8 +628,8 @@ pub mod day13 {
 
 pub mod day11 {
     use super::*;
-    pub fn day11a() -> src::VALUE {
-        let data = string_to_code(include_str!("../data/day11.txt"));
+    pub fn day11a(out: &mut dyn Write) -> Result<src::VALUE, DayError> {
+        let data = loader::parse(include_str!("../data/day11.txt"))?;
 
         let mut pos: Complex<i64> = Complex::new(0, 0);
         let mut dir: Complex<i64> = Complex::new(-1, 0);
@@ -433,30 +640,30 @@ pub mod day11 {
         loop {
             let first = pc.step_loop();
             match first {
-                Err(src::InterpreterError::Terminated) => { break; },
-                Err(_) => { panic!() },
-                Ok(color) => { tiles.insert(pos, color != 0) },
+                Ok(RunResult::Halted) => { break; },
+                Ok(RunResult::Output(color)) => { tiles.insert(pos, color != 0) },
+                res => { return Err(unexpected(&pc, res, "a color")) },
             };
 
             // Only works as long as turn_direction is in {0, 1}
             let second = pc.step_loop();
             match second {
-                Err(src::InterpreterError::Terminated) => { break; },
-                Err(_) => { panic!() },
-                Ok(turn_direction) => { dir = dir * Complex::new(0, 1 - 2*turn_direction)},
+                Ok(RunResult::Halted) => { break; },
+                Ok(RunResult::Output(turn_direction)) => { dir *= Complex::new(0, 1 - 2*turn_direction)},
+                res => { return Err(unexpected(&pc, res, "a turn")) },
             };
 
             pos += dir;
             pc.input_buffer.push_back(*tiles.get(&pos).unwrap_or(&false) as i64);
         }
 
-        println!("Number of tiles painted at least once: {}", tiles.len());
-        tiles.len() as src::VALUE
+        writeln!(out, "Number of tiles painted at least once: {}", tiles.len())?;
+        Ok(tiles.len() as src::VALUE)
         // 2418, correct!
     }
 
-    pub fn day11b() -> src::VALUE {
-        let data = string_to_code(include_str!("../data/day11.txt"));
+    pub fn day11b(out: &mut dyn Write) -> Result<src::VALUE, DayError> {
+        let data = loader::parse(include_str!("../data/day11.txt"))?;
 
         let mut pos: Complex<i64> = Complex::new(0, 0);
         // Starting direction. Starting with -1, 0 results in grid extending in the nicest direction
@@ -468,23 +675,26 @@ pub mod day11 {
         loop {
             let first = pc.step_loop();
             match first {
-                Err(src::InterpreterError::Terminated) => { break; },
-                Err(_) => { panic!() },
-                Ok(color) => { tiles.insert(pos, color != 0) },
+                Ok(RunResult::Halted) => { break; },
+                Ok(RunResult::Output(color)) => { tiles.insert(pos, color != 0) },
+                res => { return Err(unexpected(&pc, res, "a color")) },
             };
 
             // Only works as long as turn_direction is in {0, 1}
             let second = pc.step_loop();
             match second {
-                Err(src::InterpreterError::Terminated) => { break; },
-                Err(_) => { panic!() },
-                Ok(turn_direction) => { dir = dir * Complex::new(0, 1 - 2*turn_direction)},
+                Ok(RunResult::Halted) => { break; },
+                Ok(RunResult::Output(turn_direction)) => { dir *= Complex::new(0, 1 - 2*turn_direction)},
+                res => { return Err(unexpected(&pc, res, "a turn")) },
             };
 
             pos += dir;
             pc.input_buffer.push_back(*tiles.get(&pos).unwrap_or(&false) as i64);
         }
 
+        if tiles.is_empty() {
+            return Err(DayError::Unexpected("Nothing painted".to_owned()));
+        }
         let minx = tiles.keys().map(|&x| x.re as i32).min().unwrap();
         let miny = tiles.keys().map(|&x| x.im as i32).min().unwrap();
         let maxx = tiles.keys().map(|&x| x.re as i32).max().unwrap();
@@ -497,182 +707,114 @@ pub mod day11 {
             grid[z.re as usize][z.im as usize] = paint;
         }
 
-        println!("{}, {}", grid.len(), grid[0].len());
+        writeln!(out, "{}, {}", grid.len(), grid[0].len())?;
         for row in grid {
             for x in row {
-                print!("{}", x);
+                write!(out, "{}", x)?;
             }
-            println!();
+            writeln!(out)?;
         }
-        -1
+        Ok(-1)
     }
 }
 
 pub mod day9 {
     use super::*;
 
-    pub fn day9a() -> src::VALUE {
-        let result = execute(string_to_code(include_str!("../data/day9.txt")), vec![1].into()).unwrap()[0];
+    pub fn day9a(out: &mut dyn Write) -> Result<src::VALUE, DayError> {
+        let result = last_output(include_str!("../data/day9.txt"), 1)?;
 
-        println!("Result / BOOST Keycode: {}", result);
-        result
+        writeln!(out, "Result / BOOST Keycode: {}", result)?;
+        Ok(result)
     }
 
-    pub fn day9b() -> src::VALUE {
-        let result = execute(string_to_code(include_str!("../data/day9.txt")), vec![2].into()).unwrap()[0];
+    pub fn day9b(out: &mut dyn Write) -> Result<src::VALUE, DayError> {
+        let result = last_output(include_str!("../data/day9.txt"), 2)?;
 
-        println!("Result / Coordinates of distress signal: {}", result);
-        result
+        writeln!(out, "Result / Coordinates of distress signal: {}", result)?;
+        Ok(result)
     }
 }
 
 pub mod day7 {
     use super::*;
-    use itertools::Itertools;
-
-    pub fn day7a() -> src::VALUE {
-        let data7: Vec<_> = string_to_code(include_str!("../data/day7.txt"));
-
-        let mut signal_strength = 0;
+    use crate::amplifier::{self, Topology};
 
-        for inputs in (0..=4).permutations(5) {
-            let mut a1 = Interpreter::new(data7.to_owned(), vec![inputs[0], 0].into());
-            let res1 = a1.step_loop().unwrap();
+    fn highest_signal(out: &mut dyn Write, phases: &[src::VALUE], topology: Topology) -> Result<src::VALUE, DayError> {
+        let code = loader::parse(include_str!("../data/day7.txt"))?;
+        let (signal, _) = amplifier::search(&code, phases, topology)?
+            .ok_or_else(|| DayError::Unexpected("No signal".to_owned()))?;
 
-            let mut a2 = Interpreter::new(data7.to_owned(), vec![inputs[1], res1].into());
-            let res2 = a2.step_loop().unwrap();
-
-            let mut a3 = Interpreter::new(data7.to_owned(), vec![inputs[2], res2].into());
-            let res3 = a3.step_loop().unwrap();
-
-            let mut a4 = Interpreter::new(data7.to_owned(), vec![inputs[3], res3].into());
-            let res4 = a4.step_loop().unwrap();
-
-            let mut a5 = Interpreter::new(data7.to_owned(), vec![inputs[4], res4].into());
-            let res5 = a5.step_loop().unwrap();
-
-            signal_strength = signal_strength.max(res5);
-        }
-
-        println!("Result / Highest signal: {}", signal_strength);
-        signal_strength
+        writeln!(out, "Result / Highest signal: {}", signal)?;
+        Ok(signal)
     }
 
-    pub fn day7b() -> src::VALUE {
-        let data7: Vec<_> = string_to_code(include_str!("../data/day7.txt"));
-
-        let mut signal_strength = 0;
-
-        for inputs in (5..=9).permutations(5) {
-            let mut a1 = Interpreter::new(data7.to_owned(), vec![inputs[0], 0].into());
-            let mut a2 = Interpreter::new(data7.to_owned(), vec![inputs[1]].into());
-            let mut a3 = Interpreter::new(data7.to_owned(), vec![inputs[2]].into());
-            let mut a4 = Interpreter::new(data7.to_owned(), vec![inputs[3]].into());
-            let mut a5 = Interpreter::new(data7.to_owned(), vec![inputs[4]].into());
-
-
-            loop {
-                let res1 = match a1.step_loop() {
-                    Ok(res) => { res },
-                    Err(src::InterpreterError::Terminated) => { 
-                        // Once the first amplifier terminates, all the amplifiers will terminate
-                        a2.step_loop().unwrap_err();
-                        a3.step_loop().unwrap_err();
-                        a4.step_loop().unwrap_err();
-                        signal_strength = signal_strength.max(a5.last_output.unwrap());
-                        a5.step_loop().unwrap_err();
-                        break
-                    },
-                    _ => { panic!() },
-                };
+    pub fn day7a(out: &mut dyn Write) -> Result<src::VALUE, DayError> {
+        highest_signal(out, &[0, 1, 2, 3, 4], Topology::Chain)
+    }
 
-                a2.input_buffer.push_back(res1);
-                let res2 = a2.step_loop().unwrap();
-                a3.input_buffer.push_back(res2);
-                let res3 = a3.step_loop().unwrap();
-                a4.input_buffer.push_back(res3);
-                let res4 = a4.step_loop().unwrap();
-                a5.input_buffer.push_back(res4);
-                let res5 = a5.step_loop().unwrap();
-                a1.input_buffer.push_back(res5);
-            }
-        }
-        println!("Result / Highest signal: {}", signal_strength);
-        signal_strength
+    pub fn day7b(out: &mut dyn Write) -> Result<src::VALUE, DayError> {
+        highest_signal(out, &[5, 6, 7, 8, 9], Topology::Ring)
     }
 }
 
 pub mod day5 {
     use super::*;
 
-    pub fn day5a() -> src::VALUE {
-        let result = execute(string_to_code(include_str!("../data/day5.txt")), vec![1].into()).unwrap()[9];
+    pub fn day5a(out: &mut dyn Write) -> Result<src::VALUE, DayError> {
+        let result = last_output(include_str!("../data/day5.txt"), 1)?;
 
-        println!("Result: {}", result);
-        result
+        writeln!(out, "Result: {}", result)?;
+        Ok(result)
     }
 
-    pub fn day5b() -> src::VALUE {
-        let result = execute(string_to_code(include_str!("../data/day5.txt")), vec![5].into()).unwrap()[0];
+    pub fn day5b(out: &mut dyn Write) -> Result<src::VALUE, DayError> {
+        let result = last_output(include_str!("../data/day5.txt"), 5)?;
 
-        println!("Result: {}", result);
-        result
+        writeln!(out, "Result: {}", result)?;
+        Ok(result)
     }
 }
 
 pub mod day2 {
-    //use super::*;
-
-    // First AoC Intcode challenge. Requires Interpreter.code to be public, hence doesn't compile now. Otherwise passes.
-    //#[test]
-    //fn day2a() {
-        //let mut data2: Vec<_> = string_to_code(include_str!("../data/day2.txt"));
-
-        //data2[1] = 12;
-        //data2[2] = 2;
-
-        //let mut pc = Interpreter::new(data2, vec![].into());
-
-        //let res = pc.step_loop();
-
-        //if let Err(src::InterpreterError::Terminated) = res {
-        //} else {
-            //assert!(false);
-        //}
-
-        //assert_eq!(pc.code[0], DAY2A_RESULT);
-    //}
+    use super::*;
 
-    // Second AoC Intcode challenge. Requires Interpreter.code to be public, hence doesn't compile now. Otherwise passes.
-    //#[test]
-    //fn day2b() {
-        //let data2: Vec<_> = string_to_code(include_str!("../data/day2.txt"));
+    const TARGET: src::VALUE = 19690720;
 
-        //let target = 19690720;
+    /// Patches noun and verb into the program, runs it, and returns the value at address 0.
+    fn run(data: &[src::VALUE], noun: src::VALUE, verb: src::VALUE) -> Result<src::VALUE, DayError> {
+        let mut pc = Interpreter::new(data.to_owned(), vec![].into());
+        pc.write(1, noun)?;
+        pc.write(2, verb)?;
 
-        //for noun in 0..=99 {
-            //for verb in 0..=99 {
-                //let mut data = data2.to_owned();
-                //data[1] = noun;
-                //data[2] = verb;
+        match pc.step_loop() {
+            Ok(RunResult::Halted) => {},
+            res => return Err(unexpected(&pc, res, "the program to halt")),
+        }
 
-                //let mut pc = Interpreter::new(data, vec![].into());
+        Ok(pc.read(0)?)
+    }
 
-                //let res = pc.step_loop();
+    pub fn day2a(out: &mut dyn Write) -> Result<src::VALUE, DayError> {
+        let data = loader::parse(include_str!("../data/day2.txt"))?;
+        let result = run(&data, 12, 2)?;
 
-                //if let Err(src::InterpreterError::Terminated) = res {
-                //} else {
-                    //assert!(false);
-                //}
+        writeln!(out, "Result / Value at position 0: {}", result)?;
+        Ok(result)
+    }
 
-                //if pc.code[0] == target {
-                    //let result = 100*noun + verb;
-                    //assert_eq!(result, DAY2B_RESULT);
-                    //return
-                //}
-            //}
-        //}
-        //unreachable!()
-    //}
+    pub fn day2b(out: &mut dyn Write) -> Result<src::VALUE, DayError> {
+        let data = loader::parse(include_str!("../data/day2.txt"))?;
 
+        for noun in 0..=99 {
+            for verb in 0..=99 {
+                if run(&data, noun, verb)? == TARGET {
+                    let result = 100*noun + verb;
+                    writeln!(out, "Result / 100 * noun + verb: {}", result)?;
+                    return Ok(result);
+                }
+            }
+        }
+        Err(DayError::Unexpected(format!("No noun and verb produce {}", TARGET)))
+    }
 }
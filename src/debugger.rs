@@ -0,0 +1,230 @@
+//! An interactive debugger around `Interpreter::step`, driven by commands read line by line.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, BufRead, Write};
+
+use crate::disassembler;
+use crate::src::{self, ErrorKind, Interpreter, InterpreterError, RunResult, VALUE};
+
+const HELP: &str = "\
+Commands:
+  break <addr>        b   Set a breakpoint at an address
+  delete <addr>       d   Remove the breakpoint at an address
+  watch <addr>        w   Stop whenever the value of a cell changes
+  unwatch <addr>          Remove the watchpoint on a cell
+  continue            c   Run until a breakpoint, watchpoint, input request or halt
+  step [n]            s   Execute n instructions, default 1
+  next                n   Step over the current instruction, eg. a call
+  input <values...>   i   Append values to the input buffer
+  regs                r   Print ip, relative base, input buffer and last output
+  mem <start> [end]   x   Dump memory, end defaults to start + 1
+  list                l   Disassemble the current instruction
+  help                h   Print this help
+  quit                q   Leave the debugger";
+
+/// Why execution stopped.
+enum Stop {
+    Step,
+    Breakpoint(usize),
+    Watchpoint { address: usize, old: VALUE, new: VALUE },
+    NeedsInput,
+    Halted,
+    Error(InterpreterError),
+}
+
+pub struct Debugger {
+    pub pc: Interpreter,
+    breakpoints: BTreeSet<usize>,
+    /// Watched cells and their last seen value.
+    watchpoints: BTreeMap<usize, VALUE>,
+    last_output: Option<VALUE>,
+}
+
+impl Debugger {
+    pub fn new(pc: Interpreter) -> Debugger {
+        Debugger {
+            pc,
+            breakpoints: BTreeSet::new(),
+            watchpoints: BTreeMap::new(),
+            last_output: None,
+        }
+    }
+
+    /// Reads commands from `input` until it is exhausted or `quit` is entered.
+    pub fn run(&mut self, input: impl BufRead, mut out: impl Write) -> io::Result<()> {
+        writeln!(out, "Intcode debugger, type `help` for a list of commands.")?;
+        self.list(&mut out)?;
+        write!(out, "(icdb) ")?;
+        out.flush()?;
+
+        for line in input.lines() {
+            let line = line?;
+            let mut words = line.split_whitespace();
+
+            let result = match words.next() {
+                None => Ok(()),
+                Some("quit" | "q") => return Ok(()),
+                Some(command) => self.command(command, &words.collect::<Vec<_>>(), &mut out),
+            };
+
+            if let Err(e) = result {
+                writeln!(out, "Error: {}", e)?;
+            }
+            write!(out, "(icdb) ")?;
+            out.flush()?;
+        }
+        writeln!(out)
+    }
+
+    fn command(&mut self, command: &str, args: &[&str], out: &mut impl Write) -> Result<(), String> {
+        let parse = |k: usize| -> Result<VALUE, String> {
+            let arg = args.get(k).ok_or(format!("`{}` expects an argument", command))?;
+            arg.parse().map_err(|_| format!("Expected a number, found `{}`", arg))
+        };
+        let parse_address = |k: usize| -> Result<usize, String> {
+            let address = parse(k)?;
+            usize::try_from(address).map_err(|_| format!("Invalid address {}", address))
+        };
+
+        match command {
+            "break" | "b" => {
+                let address = parse_address(0)?;
+                self.breakpoints.insert(address);
+                writeln!(out, "Breakpoint at {}", address).map_err(|e| e.to_string())
+            },
+            "delete" | "d" => {
+                let address = parse_address(0)?;
+                if !self.breakpoints.remove(&address) {
+                    return Err(format!("No breakpoint at {}", address));
+                }
+                Ok(())
+            },
+            "watch" | "w" => {
+                let address = parse_address(0)?;
+                let val = self.pc.read(address).map_err(|e| e.to_string())?;
+                self.watchpoints.insert(address, val);
+                writeln!(out, "Watching {} (currently {})", address, val).map_err(|e| e.to_string())
+            },
+            "unwatch" => {
+                let address = parse_address(0)?;
+                if self.watchpoints.remove(&address).is_none() {
+                    return Err(format!("No watchpoint at {}", address));
+                }
+                Ok(())
+            },
+            "continue" | "c" => {
+                let stop = self.resume(usize::MAX, None, out).map_err(|e| e.to_string())?;
+                self.report(stop, out).map_err(|e| e.to_string())
+            },
+            "step" | "s" => {
+                let n = if args.is_empty() { 1 } else { parse_address(0)? };
+                let stop = self.resume(n, None, out).map_err(|e| e.to_string())?;
+                self.report(stop, out).map_err(|e| e.to_string())
+            },
+            "next" | "n" => {
+                let next = disassembler::decode_with(|k| self.pc.memory().get(k), self.pc.ip())
+                    .map_or(self.pc.ip() + 1, |decoded| decoded.address + decoded.size());
+                let stop = self.resume(usize::MAX, Some(next), out).map_err(|e| e.to_string())?;
+                self.report(stop, out).map_err(|e| e.to_string())
+            },
+            "input" | "i" => {
+                for k in 0..args.len() {
+                    self.pc.input_buffer.push_back(parse(k)?);
+                }
+                Ok(())
+            },
+            "regs" | "r" => self.regs(out).map_err(|e| e.to_string()),
+            "mem" | "x" => {
+                let start = parse_address(0)?;
+                let end = if args.len() > 1 { parse_address(1)? } else { start + 1 };
+                self.dump(start, end, out).map_err(|e| e.to_string())
+            },
+            "list" | "l" => self.list(out).map_err(|e| e.to_string()),
+            "help" | "h" => writeln!(out, "{}", HELP).map_err(|e| e.to_string()),
+            _ => Err(format!("Unknown command `{}`, type `help` for a list of commands", command)),
+        }
+    }
+
+    /// Executes at most `steps` instructions, stopping early at breakpoints, watchpoints,
+    /// input requests, the end of the program, or once `ip` reaches `until`.
+    fn resume(&mut self, steps: usize, until: Option<usize>, out: &mut impl Write) -> io::Result<Stop> {
+        for k in 0..steps {
+            if k > 0 && self.breakpoints.contains(&self.pc.ip()) {
+                return Ok(Stop::Breakpoint(self.pc.ip()));
+            }
+
+            match self.pc.step() {
+                Ok(None) => {},
+                Ok(Some(RunResult::Output(val))) => {
+                    self.last_output = Some(val);
+                    writeln!(out, "Output: {}", val)?;
+                },
+                Ok(Some(RunResult::NeedsInput)) => return Ok(Stop::NeedsInput),
+                Ok(Some(RunResult::Halted)) => return Ok(Stop::Halted),
+                Ok(Some(RunResult::BudgetExhausted(limit))) => return Ok(Stop::Error(self.pc.fault(ErrorKind::BudgetExhausted(limit), None))),
+                Err(e) => return Ok(Stop::Error(e)),
+            }
+
+            for (&address, old) in self.watchpoints.iter_mut() {
+                let new = self.pc.memory().read(address);
+                if new != *old {
+                    let stop = Stop::Watchpoint { address, old: *old, new };
+                    *old = new;
+                    return Ok(stop);
+                }
+            }
+
+            if until == Some(self.pc.ip()) {
+                break;
+            }
+        }
+        Ok(Stop::Step)
+    }
+
+    fn report(&self, stop: Stop, out: &mut impl Write) -> io::Result<()> {
+        match stop {
+            Stop::Step => {},
+            Stop::Breakpoint(address) => writeln!(out, "Breakpoint at {}", address)?,
+            Stop::Watchpoint { address, old, new } => writeln!(out, "Watchpoint {}: {} -> {}", address, old, new)?,
+            Stop::NeedsInput => writeln!(out, "Waiting for input, use `input <values>`")?,
+            Stop::Halted => writeln!(out, "Program halted")?,
+            Stop::Error(e) => writeln!(out, "{}", e)?,
+        }
+        self.list(out)
+    }
+
+    fn regs(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "ip: {}, relative base: {}, input buffer: {:?}, last output: {:?}",
+                 self.pc.ip(), self.pc.relative_base(), self.pc.input_buffer, self.last_output)
+    }
+
+    fn dump(&self, start: usize, end: usize, out: &mut impl Write) -> io::Result<()> {
+        for (k, address) in (start..end).enumerate() {
+            if k % 8 == 0 {
+                if k > 0 {
+                    writeln!(out)?;
+                }
+                write!(out, "{:>6}:", address)?;
+            }
+            match self.pc.memory().get(address) {
+                Some(val) => write!(out, " {}", val)?,
+                None => write!(out, " -")?,
+            }
+        }
+        writeln!(out)
+    }
+
+    fn list(&self, out: &mut impl Write) -> io::Result<()> {
+        let ip = self.pc.ip();
+        match disassembler::decode_with(|k| self.pc.memory().get(k), ip) {
+            Some(decoded) => writeln!(out, "{}", disassembler::Line::Instruction(decoded)),
+            None => writeln!(out, "{:>6}: .data {}", ip, self.pc.memory().get(ip).map_or("-".to_owned(), |val| val.to_string())),
+        }
+    }
+}
+
+/// Runs the debugger on `code`, reading commands from stdin and answering to `out`.
+pub fn debug(code: Vec<src::VALUE>, out: impl Write) -> io::Result<()> {
+    let mut debugger = Debugger::new(Interpreter::new(code, vec![].into()));
+    debugger.run(io::stdin().lock(), out)
+}
@@ -0,0 +1,868 @@
+//! Decompiles programs into C-like pseudocode, on top of `cfg` and `functions`.
+//!
+//! Every instruction is lifted into a statement over expressions. Cells at a fixed address are
+//! `mem[a]`, and the cells of the stack frame of a function are named after their offset from
+//! the relative base on entry: `arg1`, `arg2`, ... for the arguments, the locals after them, and
+//! `out1`, `out2`, ... past the frame, which hold the arguments of the calls it makes, and their
+//! results afterwards. The main program starts with a relative base of 0, so its relative cells
+//! are plain `mem[a]`. Operands the program writes to, eg. to index a table, are read from memory.
+//!
+//! Loops are recovered from the back edges of the control flow graph, if/else from where its
+//! branches join again, and whatever does not fit is left to `goto`.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+
+use crate::cfg::{Block, Cfg, Edge};
+use crate::disassembler::{self, Decoded, Mode};
+use crate::functions::{self, CallSite, Function};
+use crate::src::VALUE;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Mul,
+    Less,
+    Equals,
+}
+
+impl BinOp {
+    fn apply(self, a: VALUE, b: VALUE) -> Option<VALUE> {
+        match self {
+            BinOp::Add => a.checked_add(b),
+            BinOp::Mul => a.checked_mul(b),
+            BinOp::Less => Some((a < b) as VALUE),
+            BinOp::Equals => Some((a == b) as VALUE),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr {
+    Const(VALUE),
+    /// The cell at an address.
+    Mem(Box<Expr>),
+    /// A cell of the stack frame, at an offset from the relative base on entry of the function.
+    Slot(VALUE),
+    /// The relative base, where its offset from the entry is not known.
+    Base,
+    Neg(Box<Expr>),
+    /// The negated condition.
+    Not(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// `a op b`, folding constants and dropping neutral operands.
+    pub fn binary(op: BinOp, a: Expr, b: Expr) -> Expr {
+        if let (Expr::Const(x), Expr::Const(y)) = (&a, &b) {
+            if let Some(val) = op.apply(*x, *y) {
+                return Expr::Const(val);
+            }
+        }
+        // Constants go to the right of commutative operators
+        let (a, b) = match a {
+            Expr::Const(_) if op != BinOp::Less => (b, a),
+            _ => (a, b),
+        };
+        match (op, &b) {
+            (BinOp::Add, Expr::Const(0)) | (BinOp::Mul, Expr::Const(1)) => a,
+            (BinOp::Mul, Expr::Const(0)) => Expr::Const(0),
+            (BinOp::Mul, Expr::Const(-1)) => a.negate(),
+            _ => Expr::Binary(op, Box::new(a), Box::new(b)),
+        }
+    }
+
+    pub fn negate(self) -> Expr {
+        match self {
+            Expr::Const(val) if val != VALUE::MIN => Expr::Const(-val),
+            Expr::Neg(inner) => *inner,
+            _ => Expr::Neg(Box::new(self)),
+        }
+    }
+
+    /// The negation of `self` as a condition.
+    pub fn invert(self) -> Expr {
+        match self {
+            Expr::Const(val) => Expr::Const((val == 0) as VALUE),
+            Expr::Not(inner) => *inner,
+            _ => Expr::Not(Box::new(self)),
+        }
+    }
+
+    /// Whether `other` is a part of `self`.
+    fn contains(&self, other: &Expr) -> bool {
+        self == other || match self {
+            Expr::Mem(inner) | Expr::Neg(inner) | Expr::Not(inner) => inner.contains(other),
+            Expr::Binary(_, a, b) => a.contains(other) || b.contains(other),
+            Expr::Const(_) | Expr::Slot(_) | Expr::Base => false,
+        }
+    }
+
+    fn slots(&self, slots: &mut BTreeSet<VALUE>) {
+        match self {
+            Expr::Slot(offset) => { slots.insert(*offset); },
+            Expr::Mem(inner) | Expr::Neg(inner) | Expr::Not(inner) => inner.slots(slots),
+            Expr::Binary(_, a, b) => {
+                a.slots(slots);
+                b.slots(slots);
+            },
+            Expr::Const(_) | Expr::Base => {},
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Stmt {
+    Assign(Expr, Expr),
+    Input(Expr),
+    Output(Expr),
+    /// Moves the relative base, other than by the prologue and epilogue of a function.
+    AdjustBase(Expr),
+    /// Calls the function at the address, with the arguments.
+    Call(Expr, Vec<Expr>),
+    If(Expr, Vec<Stmt>, Vec<Stmt>),
+    While(Expr, Vec<Stmt>),
+    Break,
+    Continue,
+    Return,
+    Halt,
+    /// Continues at the label of the block at the address.
+    Goto(usize),
+    Label(usize),
+    /// Continues at a computed address, or one which is not code.
+    Jump(Expr),
+}
+
+impl Stmt {
+    fn slots(&self, slots: &mut BTreeSet<VALUE>) {
+        match self {
+            Stmt::Assign(place, val) => {
+                place.slots(slots);
+                val.slots(slots);
+            },
+            Stmt::Input(expr) | Stmt::Output(expr) | Stmt::AdjustBase(expr) | Stmt::Jump(expr) => expr.slots(slots),
+            Stmt::Call(target, args) => {
+                target.slots(slots);
+                args.iter().for_each(|arg| arg.slots(slots));
+            },
+            Stmt::If(condition, then, otherwise) => {
+                condition.slots(slots);
+                then.iter().chain(otherwise).for_each(|statement| statement.slots(slots));
+            },
+            Stmt::While(condition, body) => {
+                condition.slots(slots);
+                body.iter().for_each(|statement| statement.slots(slots));
+            },
+            Stmt::Break | Stmt::Continue | Stmt::Return | Stmt::Halt | Stmt::Goto(_) | Stmt::Label(_) => {},
+        }
+    }
+}
+
+/// Whether execution never continues after the statements.
+fn jumps_away(statements: &[Stmt]) -> bool {
+    match statements.last() {
+        Some(Stmt::Break | Stmt::Continue | Stmt::Return | Stmt::Halt | Stmt::Goto(_) | Stmt::Jump(_)) => true,
+        Some(Stmt::If(_, then, otherwise)) => jumps_away(then) && jumps_away(otherwise),
+        _ => false,
+    }
+}
+
+/// The main program, or a function.
+pub struct Procedure {
+    /// `None` for the main program at address 0.
+    pub function: Option<Function>,
+    /// The number of arguments, the most any call passes.
+    pub parameters: usize,
+    pub body: Vec<Stmt>,
+}
+
+impl Procedure {
+    pub fn name(&self) -> String {
+        self.function.as_ref().map_or("main".to_owned(), Function::name)
+    }
+
+    /// The name of the stack slot at `offset` from the relative base on entry.
+    fn slot(&self, offset: VALUE) -> String {
+        let frame = self.function.as_ref().and_then(|function| function.frame_size);
+        match frame {
+            _ if offset == 0 => "ret".to_owned(),
+            _ if offset < 0 => format!("caller{}", offset.unsigned_abs()),
+            _ if offset as usize <= self.parameters => format!("arg{}", offset),
+            Some(size) if offset >= size => format!("out{}", offset - size),
+            _ => format!("local{}", offset),
+        }
+    }
+
+    /// `expr` and its precedence, which is higher for operators binding tighter.
+    fn expr(&self, expr: &Expr) -> (String, u8) {
+        let operand = |expr: &Expr, min: u8| match self.expr(expr) {
+            (text, precedence) if precedence < min => format!("({})", text),
+            (text, _) => text,
+        };
+        match expr {
+            Expr::Const(val) => (val.to_string(), 6),
+            Expr::Mem(address) => (format!("mem[{}]", operand(address, 0)), 6),
+            Expr::Slot(offset) => (self.slot(*offset), 6),
+            Expr::Base => ("rb".to_owned(), 6),
+            Expr::Neg(inner) => (format!("-{}", operand(inner, 5)), 5),
+            Expr::Not(inner) => match &**inner {
+                Expr::Binary(BinOp::Less, a, b) => (format!("{} >= {}", operand(a, 3), operand(b, 3)), 2),
+                Expr::Binary(BinOp::Equals, a, b) => (format!("{} != {}", operand(a, 2), operand(b, 2)), 1),
+                _ => (format!("!{}", operand(inner, 5)), 5),
+            },
+            Expr::Binary(op, a, b) => match (op, &**b) {
+                (BinOp::Add, Expr::Const(val)) if *val < 0 && *val != VALUE::MIN => (format!("{} - {}", operand(a, 3), -val), 3),
+                (BinOp::Add, Expr::Neg(b)) => (format!("{} - {}", operand(a, 3), operand(b, 4)), 3),
+                (BinOp::Add, _) => (format!("{} + {}", operand(a, 3), operand(b, 4)), 3),
+                (BinOp::Mul, _) => (format!("{} * {}", operand(a, 4), operand(b, 5)), 4),
+                (BinOp::Less, _) => (format!("{} < {}", operand(a, 3), operand(b, 3)), 2),
+                (BinOp::Equals, _) => (format!("{} == {}", operand(a, 2), operand(b, 2)), 1),
+            },
+        }
+    }
+
+    fn statements(&self, out: &mut std::fmt::Formatter<'_>, statements: &[Stmt], depth: usize) -> std::fmt::Result {
+        let indent = "    ".repeat(depth);
+        let expr = |expr: &Expr| self.expr(expr).0;
+        for statement in statements {
+            match statement {
+                Stmt::Assign(place, val) => writeln!(out, "{}{} = {};", indent, expr(place), expr(val))?,
+                Stmt::Input(place) => writeln!(out, "{}{} = input();", indent, expr(place))?,
+                Stmt::Output(val) => writeln!(out, "{}output({});", indent, expr(val))?,
+                Stmt::AdjustBase(val) => writeln!(out, "{}rb += {};", indent, expr(val))?,
+                Stmt::Call(target, args) => {
+                    let target = match target {
+                        Expr::Const(entry) => format!("function_{}", entry),
+                        _ => format!("(*{})", self.expr(target).0),
+                    };
+                    let args: Vec<String> = args.iter().map(expr).collect();
+                    writeln!(out, "{}{}({});", indent, target, args.join(", "))?;
+                },
+                Stmt::If(condition, then, otherwise) => {
+                    writeln!(out, "{}if ({}) {{", indent, expr(condition))?;
+                    self.statements(out, then, depth + 1)?;
+                    let mut otherwise = otherwise;
+                    // An if as the only statement of the else branch continues the chain
+                    while let [Stmt::If(condition, then, rest)] = &otherwise[..] {
+                        writeln!(out, "{}}} else if ({}) {{", indent, expr(condition))?;
+                        self.statements(out, then, depth + 1)?;
+                        otherwise = rest;
+                    }
+                    if !otherwise.is_empty() {
+                        writeln!(out, "{}}} else {{", indent)?;
+                        self.statements(out, otherwise, depth + 1)?;
+                    }
+                    writeln!(out, "{}}}", indent)?;
+                },
+                Stmt::While(condition, body) => {
+                    writeln!(out, "{}while ({}) {{", indent, expr(condition))?;
+                    self.statements(out, body, depth + 1)?;
+                    writeln!(out, "{}}}", indent)?;
+                },
+                Stmt::Break => writeln!(out, "{}break;", indent)?,
+                Stmt::Continue => writeln!(out, "{}continue;", indent)?,
+                Stmt::Return => writeln!(out, "{}return;", indent)?,
+                Stmt::Halt => writeln!(out, "{}halt();", indent)?,
+                Stmt::Goto(address) => writeln!(out, "{}goto label_{};", indent, address)?,
+                Stmt::Label(address) => writeln!(out, "{}label_{}:", indent, address)?,
+                Stmt::Jump(target) => writeln!(out, "{}goto *{};", indent, expr(target))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for Procedure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(function) = &self.function {
+            let callers: Vec<String> = function.callers.iter().map(|caller| caller.to_string()).collect();
+            match function.frame_size {
+                Some(size) => writeln!(f, "// frame of {} cells, called from {}", size, callers.join(", "))?,
+                None => writeln!(f, "// no frame, called from {}", callers.join(", "))?,
+            }
+        }
+        let parameters: Vec<String> = (1..=self.parameters).map(|k| format!("int arg{}", k)).collect();
+        writeln!(f, "void {}({}) {{", self.name(), parameters.join(", "))?;
+
+        let mut slots = BTreeSet::new();
+        self.body.iter().for_each(|statement| statement.slots(&mut slots));
+        let locals: Vec<String> = slots
+            .into_iter()
+            .filter(|&offset| offset <= 0 || offset as usize > self.parameters)
+            .map(|offset| self.slot(offset))
+            .collect();
+        if !locals.is_empty() {
+            writeln!(f, "    int {};", locals.join(", "))?;
+        }
+
+        self.statements(f, &self.body, 1)?;
+        write!(f, "}}")
+    }
+}
+
+/// Decompiles the main program at address 0, and every function `functions::analyze` finds.
+pub fn decompile(code: &[VALUE]) -> Vec<Procedure> {
+    let analysis = functions::analyze(code);
+    let decompiler = Decompiler::new(code, &analysis.calls, &analysis.instructions);
+
+    let mut arguments = BTreeMap::new();
+    let mut procedures = vec![];
+    if decompiler.cfg.blocks.contains_key(&0) {
+        let body = decompiler.procedure(None, &mut arguments);
+        procedures.push(Procedure { function: None, parameters: 0, body });
+    }
+    for function in analysis.functions.values() {
+        let body = decompiler.procedure(Some(function), &mut arguments);
+        procedures.push(Procedure { function: Some(function.clone()), parameters: 0, body });
+    }
+
+    for procedure in &mut procedures {
+        if let Some(function) = &procedure.function {
+            procedure.parameters = arguments.get(&function.entry).copied().unwrap_or(0);
+        }
+    }
+    procedures
+}
+
+/// How a block is left.
+#[derive(Clone)]
+enum Exit {
+    Goto(usize),
+    /// Continues at the second address if the condition holds, else at the first one.
+    Branch(Expr, usize, usize),
+    Stop(Stmt),
+}
+
+/// A block lifted into statements.
+struct Node {
+    statements: Vec<Stmt>,
+    exit: Exit,
+}
+
+struct Decompiler<'a> {
+    cfg: Cfg,
+    /// The calls by the address of their jump.
+    calls: BTreeMap<usize, &'a CallSite>,
+    /// The operand cells which instructions with a fixed target write to.
+    patched: BTreeSet<usize>,
+}
+
+impl<'a> Decompiler<'a> {
+    fn new(code: &[VALUE], calls: &'a [CallSite], instructions: &BTreeSet<usize>) -> Decompiler<'a> {
+        let decoded: Vec<Decoded> = instructions.iter().filter_map(|&address| disassembler::decode(code, address)).collect();
+        let operands: BTreeSet<usize> = decoded.iter().flat_map(|instruction| instruction.address + 1..instruction.address + instruction.size()).collect();
+        let patched = decoded
+            .iter()
+            .filter_map(|instruction| instruction.operands.get(instruction.instruction.writes?))
+            .filter(|target| target.mode == Mode::Position)
+            .filter_map(|target| usize::try_from(target.value).ok())
+            .filter(|address| operands.contains(address))
+            .collect();
+
+        Decompiler {
+            cfg: Cfg::new(code),
+            calls: calls.iter().map(|call| (call.address, call)).collect(),
+            patched,
+        }
+    }
+
+    /// The statements of the main program if `function` is `None`. Counts the arguments passed
+    /// to each function in `arguments`.
+    fn procedure(&self, function: Option<&Function>, arguments: &mut BTreeMap<usize, usize>) -> Vec<Stmt> {
+        let entry = function.map_or(0, |function| function.entry);
+        let nodes = self
+            .offsets(entry)
+            .into_iter()
+            .filter_map(|(start, offset)| Some((start, self.node(function, self.cfg.blocks.get(&start)?, offset, arguments))))
+            .collect();
+        Structurer::new(Graph { entry, nodes }).run()
+    }
+
+    /// The blocks following `block` within its procedure, where calls return to.
+    fn successors(&self, block: &Block) -> Vec<usize> {
+        if let Some(call) = block.instructions.last().and_then(|jump| self.calls.get(&jump.address)) {
+            return vec![call.return_address];
+        }
+        block.edges.iter().filter_map(|edge| match *edge {
+            Edge::Next(target) | Edge::Jump(target) => Some(target),
+            Edge::Indirect => None,
+        }).collect()
+    }
+
+    /// The offset of the relative base from its value on entry at the start of each block of
+    /// the procedure at `entry`, `None` where it differs between paths or is not known.
+    fn offsets(&self, entry: usize) -> BTreeMap<usize, Option<VALUE>> {
+        let mut offsets = BTreeMap::from([(entry, Some(0))]);
+        let mut queue = vec![entry];
+        while let Some(start) = queue.pop() {
+            let Some(block) = self.cfg.blocks.get(&start) else {
+                continue;
+            };
+            let end = block.instructions.iter().fold(offsets[&start], |offset, instruction| self.advance(offset, instruction));
+            for next in self.successors(block) {
+                let merged = match offsets.get(&next) {
+                    None => end,
+                    Some(&offset) if offset == end || offset.is_none() => continue,
+                    Some(_) => None,
+                };
+                offsets.insert(next, merged);
+                queue.push(next);
+            }
+        }
+        offsets
+    }
+
+    /// The offset of the relative base after `instruction`.
+    fn advance(&self, offset: Option<VALUE>, instruction: &Decoded) -> Option<VALUE> {
+        if instruction.instruction.opcode != 9 {
+            return offset;
+        }
+        match instruction.operands[0] {
+            operand if operand.mode == Mode::Immediate && !self.patched.contains(&(instruction.address + 1)) => offset?.checked_add(operand.value),
+            _ => None,
+        }
+    }
+
+    /// Lifts `block`, which starts with the relative base at `offset`.
+    fn node(&self, function: Option<&Function>, block: &Block, offset: Option<VALUE>, arguments: &mut BTreeMap<usize, usize>) -> Node {
+        let last = block.instructions.last().unwrap();
+        let terminator = matches!(last.instruction.opcode, 5 | 6 | 99);
+        let body = &block.instructions[..block.instructions.len() - terminator as usize];
+        let frame = function.and_then(|function| function.frame_size);
+        let returns = function.is_some_and(|function| function.returns.iter().any(|ret| ret.address == last.address));
+
+        let mut offset = offset;
+        let mut statements = vec![];
+        for (k, instruction) in body.iter().enumerate() {
+            let moves = |size: Option<VALUE>| {
+                let operand = instruction.operands.first();
+                instruction.instruction.opcode == 9 && operand.is_some_and(|operand| operand.mode == Mode::Immediate && Some(operand.value) == size)
+            };
+            let prologue = function.is_some_and(|function| function.entry == block.start) && k == 0 && moves(frame);
+            let epilogue = returns && k + 1 == body.len() && moves(frame.map(|size| -size));
+            if !prologue && !epilogue {
+                statements.extend(self.statement(function, instruction, offset));
+            }
+            offset = self.advance(offset, instruction);
+        }
+
+        if let Some(call) = self.calls.get(&last.address) {
+            let target = self.operand(function, last, 1, offset);
+            let args = Decompiler::arguments(&mut statements, function, offset, call.return_address);
+            if let Some(entry) = call.target {
+                let count = arguments.entry(entry).or_default();
+                *count = args.len().max(*count);
+            }
+            statements.push(Stmt::Call(target, args));
+            return Node { statements, exit: Exit::Goto(call.return_address) };
+        }
+        if !terminator {
+            return Node { statements, exit: Exit::Goto(block.end) };
+        }
+        if last.instruction.opcode == 99 {
+            return Node { statements, exit: Exit::Stop(Stmt::Halt) };
+        }
+
+        let condition = self.operand(function, last, 0, offset);
+        let condition = if last.instruction.opcode == 5 { condition } else { condition.invert() };
+        let stop = if returns { Stmt::Return } else { Stmt::Jump(self.operand(function, last, 1, offset)) };
+        let exit = match block.edges[..] {
+            [Edge::Next(next), Edge::Indirect] => {
+                statements.push(Stmt::If(condition, vec![stop], vec![]));
+                Exit::Goto(next)
+            },
+            [Edge::Next(next), Edge::Jump(target)] => Exit::Branch(condition, next, target),
+            [Edge::Next(next)] | [Edge::Jump(next)] => Exit::Goto(next),
+            // Jumps which are always taken, to a computed address or out of memory
+            _ => Exit::Stop(stop),
+        };
+        Node { statements, exit }
+    }
+
+    /// The statement `instruction` is lifted into, none for jumps and halts, or copying a cell
+    /// into itself.
+    fn statement(&self, function: Option<&Function>, instruction: &Decoded, offset: Option<VALUE>) -> Option<Stmt> {
+        let operand = |k| self.operand(function, instruction, k, offset);
+        let op = match instruction.instruction.opcode {
+            1 => BinOp::Add,
+            2 => BinOp::Mul,
+            7 => BinOp::Less,
+            8 => BinOp::Equals,
+            3 => return Some(Stmt::Input(operand(0))),
+            4 => return Some(Stmt::Output(operand(0))),
+            9 => return Some(Stmt::AdjustBase(operand(0))),
+            _ => return None,
+        };
+        let (place, val) = (operand(2), Expr::binary(op, operand(0), operand(1)));
+        (place != val).then_some(Stmt::Assign(place, val))
+    }
+
+    /// The operand `k` of `instruction`, executed with the relative base at `offset`.
+    fn operand(&self, function: Option<&Function>, instruction: &Decoded, k: usize, offset: Option<VALUE>) -> Expr {
+        let operand = instruction.operands[k];
+        let cell = instruction.address + 1 + k;
+        let val = if self.patched.contains(&cell) { Expr::Mem(Box::new(Expr::Const(cell as VALUE))) } else { Expr::Const(operand.value) };
+        match (operand.mode, val) {
+            (Mode::Immediate, val) => val,
+            (Mode::Position, val) => Expr::Mem(Box::new(val)),
+            (Mode::Relative, Expr::Const(k)) => slot(function, offset, k),
+            (Mode::Relative, val) => Expr::Mem(Box::new(Expr::binary(BinOp::Add, Expr::Base, val))),
+        }
+    }
+
+    /// Takes the push of the return address, and the arguments stored to `@1`, `@2`, ... right
+    /// before a call out of `statements`. Returns the arguments, unless they are not stored in
+    /// one run.
+    fn arguments(statements: &mut Vec<Stmt>, function: Option<&Function>, offset: Option<VALUE>, return_address: usize) -> Vec<Expr> {
+        let push = Stmt::Assign(slot(function, offset, 0), Expr::Const(return_address as VALUE));
+        if let Some(k) = statements.iter().rposition(|statement| *statement == push) {
+            statements.remove(k);
+        }
+
+        let mut args = BTreeMap::new();
+        let mut taken = vec![];
+        while let Some(Stmt::Assign(place, val)) = statements.last() {
+            let Some(k) = (1..=statements.len() as VALUE).find(|&k| *place == slot(function, offset, k)) else {
+                break;
+            };
+            // The arguments are evaluated before the call, after all of them have been stored
+            if args.contains_key(&k) || args.values().any(|arg: &Expr| arg.contains(place)) {
+                break;
+            }
+            args.insert(k, val.clone());
+            taken.push(statements.pop().unwrap());
+        }
+
+        if args.keys().copied().eq(1..=args.len() as VALUE) {
+            args.into_values().collect()
+        } else {
+            statements.extend(taken.into_iter().rev());
+            vec![]
+        }
+    }
+}
+
+/// The cell at `k` from the relative base, which is at `offset` from its value on entry.
+fn slot(function: Option<&Function>, offset: Option<VALUE>, k: VALUE) -> Expr {
+    match (function, offset.and_then(|offset| offset.checked_add(k))) {
+        (Some(_), Some(offset)) => Expr::Slot(offset),
+        // The main program starts with a relative base of 0
+        (None, Some(address)) if address >= 0 => Expr::Mem(Box::new(Expr::Const(address))),
+        _ => Expr::Mem(Box::new(Expr::binary(BinOp::Add, Expr::Base, Expr::Const(k)))),
+    }
+}
+
+/// The lifted blocks of a procedure, by their address.
+struct Graph {
+    entry: usize,
+    nodes: BTreeMap<usize, Node>,
+}
+
+impl Graph {
+    /// The nodes following `node`, without addresses which are not code.
+    fn successors(&self, node: usize) -> Vec<usize> {
+        let successors = match self.nodes[&node].exit {
+            Exit::Goto(next) => vec![next],
+            Exit::Branch(_, next, target) => vec![next, target],
+            Exit::Stop(_) => vec![],
+        };
+        successors.into_iter().filter(|next| self.nodes.contains_key(next)).collect()
+    }
+
+    fn predecessors(&self) -> BTreeMap<usize, Vec<usize>> {
+        let mut predecessors: BTreeMap<usize, Vec<usize>> = self.nodes.keys().map(|&node| (node, vec![])).collect();
+        for &node in self.nodes.keys() {
+            for next in self.successors(node) {
+                predecessors.get_mut(&next).unwrap().push(node);
+            }
+        }
+        predecessors
+    }
+
+    fn is_stop(&self, node: usize) -> bool {
+        self.nodes.get(&node).is_some_and(|node| matches!(node.exit, Exit::Stop(_)))
+    }
+}
+
+/// Stands for the end of a procedure in its post-dominators.
+const SINK: usize = usize::MAX;
+
+/// The common elements of all `sets`, `None` if there are none.
+fn intersect<'s>(sets: impl IntoIterator<Item = &'s BTreeSet<usize>>) -> Option<BTreeSet<usize>> {
+    sets.into_iter().fold(None, |common, set| Some(match common {
+        None => set.clone(),
+        Some(common) => common.intersection(set).copied().collect(),
+    }))
+}
+
+/// The nodes every path from the entry to each node passes.
+fn dominators(graph: &Graph) -> BTreeMap<usize, BTreeSet<usize>> {
+    let predecessors = graph.predecessors();
+    let mut dominators = BTreeMap::from([(graph.entry, BTreeSet::from([graph.entry]))]);
+    loop {
+        let mut changed = false;
+        for &node in graph.nodes.keys().filter(|&&node| node != graph.entry) {
+            let common = intersect(predecessors[&node].iter().filter_map(|previous| dominators.get(previous)));
+            if let Some(mut common) = common {
+                common.insert(node);
+                if dominators.get(&node) != Some(&common) {
+                    dominators.insert(node, common);
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            return dominators;
+        }
+    }
+}
+
+/// The nodes every path from each node to an exit passes, without following `back` edges.
+/// `None` for nodes from which no exit is reached.
+fn postdominators(graph: &Graph, back: &BTreeSet<(usize, usize)>, exit: impl Fn(usize) -> bool) -> BTreeMap<usize, Option<BTreeSet<usize>>> {
+    let sink = BTreeSet::from([SINK]);
+    let mut postdominators: BTreeMap<usize, Option<BTreeSet<usize>>> = graph.nodes.keys().map(|&node| (node, None)).collect();
+    loop {
+        let mut changed = false;
+        for &node in graph.nodes.keys().rev() {
+            let mut sets: Vec<&BTreeSet<usize>> = graph
+                .successors(node)
+                .into_iter()
+                .filter(|&next| !back.contains(&(node, next)))
+                .filter_map(|next| postdominators[&next].as_ref())
+                .collect();
+            if exit(node) {
+                sets.push(&sink);
+            }
+            let common = intersect(sets).map(|mut common| {
+                common.insert(node);
+                common
+            });
+            if postdominators[&node] != common {
+                postdominators.insert(node, common);
+                changed = true;
+            }
+        }
+        if !changed {
+            return postdominators;
+        }
+    }
+}
+
+/// The closest node other than `node` which every path from `node` to an exit passes.
+fn immediate(postdominators: &BTreeMap<usize, Option<BTreeSet<usize>>>, node: usize) -> Option<usize> {
+    postdominators[&node]
+        .as_ref()?
+        .iter()
+        .copied()
+        .filter(|&other| other != node && other != SINK)
+        .max_by_key(|other| postdominators[other].as_ref().map_or(0, BTreeSet::len))
+}
+
+/// Where the structuring of a region continues.
+#[derive(Copy, Clone, Default)]
+struct Context {
+    /// Where the enclosing if/else joins again.
+    join: Option<usize>,
+    /// The header of the innermost loop, and where it exits to.
+    header: Option<usize>,
+    follow: Option<usize>,
+}
+
+/// Recovers loops and if/else from the graph of a procedure.
+struct Structurer {
+    graph: Graph,
+    /// The nodes of each loop, by its header.
+    loops: BTreeMap<usize, BTreeSet<usize>>,
+    /// Where the branches of each conditional node join again.
+    joins: BTreeMap<usize, Option<usize>>,
+    emitted: BTreeSet<usize>,
+    gotos: BTreeSet<usize>,
+    /// Targets of gotos which have not been emitted.
+    pending: Vec<usize>,
+}
+
+impl Structurer {
+    fn new(graph: Graph) -> Structurer {
+        let dominators = dominators(&graph);
+        let back: BTreeSet<(usize, usize)> = graph
+            .nodes
+            .keys()
+            .flat_map(|&node| graph.successors(node).into_iter().map(move |next| (node, next)))
+            .filter(|(node, next)| dominators[node].contains(next))
+            .collect();
+
+        let predecessors = graph.predecessors();
+        let mut loops: BTreeMap<usize, BTreeSet<usize>> = BTreeMap::new();
+        for &(latch, header) in &back {
+            let body = loops.entry(header).or_insert_with(|| BTreeSet::from([header]));
+            let mut queue = vec![latch];
+            while let Some(node) = queue.pop() {
+                if body.insert(node) {
+                    queue.extend(&predecessors[&node]);
+                }
+            }
+        }
+
+        // Branches which halt are ignored when looking for a join, unless all of them do
+        let latches: BTreeSet<usize> = back.iter().map(|&(latch, _)| latch).collect();
+        let normal = postdominators(&graph, &back, |node| {
+            latches.contains(&node) || matches!(graph.nodes[&node].exit, Exit::Stop(Stmt::Return | Stmt::Jump(_)))
+        });
+        let all = postdominators(&graph, &back, |node| {
+            latches.contains(&node) || graph.successors(node).iter().all(|&next| back.contains(&(node, next)))
+        });
+        let joins = graph
+            .nodes
+            .iter()
+            .filter(|(_, node)| matches!(node.exit, Exit::Branch(..)))
+            .map(|(&node, _)| (node, if normal[&node].is_some() { immediate(&normal, node) } else { immediate(&all, node) }))
+            .collect();
+
+        Structurer { graph, loops, joins, emitted: BTreeSet::new(), gotos: BTreeSet::new(), pending: vec![] }
+    }
+
+    fn run(mut self) -> Vec<Stmt> {
+        let mut body = vec![];
+        self.region(self.graph.entry, Context::default(), &mut body);
+        // Code only reached by gotos out of loops goes last
+        while let Some(node) = self.pending.pop() {
+            if !self.emitted.contains(&node) {
+                self.region(node, Context::default(), &mut body);
+            }
+        }
+
+        let mut body = simplify(body, &self.gotos);
+        if body.last() == Some(&Stmt::Return) {
+            body.pop();
+        }
+        body
+    }
+
+    /// Emits the nodes from `node` on, until the region of `cx` is left.
+    fn region(&mut self, mut node: usize, cx: Context, out: &mut Vec<Stmt>) {
+        loop {
+            if Some(node) == cx.join {
+                return;
+            }
+            if Some(node) == cx.header {
+                out.push(Stmt::Continue);
+                return;
+            }
+            if Some(node) == cx.follow {
+                out.push(Stmt::Break);
+                return;
+            }
+
+            if !self.graph.nodes.contains_key(&node) {
+                out.push(Stmt::Jump(Expr::Const(node as VALUE)));
+                return;
+            }
+            // Blocks which stop are small, and repeated rather than jumped to
+            let outside = cx.header.is_some_and(|header| !self.loops[&header].contains(&node));
+            if !self.graph.is_stop(node) && (self.emitted.contains(&node) || outside) {
+                if !self.emitted.contains(&node) {
+                    self.pending.push(node);
+                }
+                self.gotos.insert(node);
+                out.push(Stmt::Goto(node));
+                return;
+            }
+            self.emitted.insert(node);
+            out.push(Stmt::Label(node));
+
+            let next = if self.loops.contains_key(&node) {
+                let inner = Context { join: None, header: Some(node), follow: self.follow(node) };
+                let mut body = vec![];
+                if let Some(next) = self.block(node, inner, &mut body) {
+                    self.region(next, inner, &mut body);
+                }
+                out.push(Stmt::While(Expr::Const(1), body));
+                inner.follow
+            } else {
+                self.block(node, cx, out)
+            };
+            match next {
+                Some(next) => node = next,
+                None => return,
+            }
+        }
+    }
+
+    /// Emits the statements of `node`, and if/else for its branches. Returns where execution
+    /// continues afterwards, if it does.
+    fn block(&mut self, node: usize, cx: Context, out: &mut Vec<Stmt>) -> Option<usize> {
+        let current = &self.graph.nodes[&node];
+        out.extend(current.statements.iter().cloned());
+        match current.exit.clone() {
+            Exit::Goto(next) => Some(next),
+            Exit::Stop(statement) => {
+                out.push(statement);
+                None
+            },
+            Exit::Branch(condition, next, target) => {
+                let join = self.joins[&node];
+                let inner = Context { join: join.or(cx.join), ..cx };
+                let (mut then, mut otherwise) = (vec![], vec![]);
+                self.region(next, inner, &mut then);
+                self.region(target, inner, &mut otherwise);
+                out.push(Stmt::If(condition.invert(), then, otherwise));
+                join
+            },
+        }
+    }
+
+    /// Where the loop at `header` exits to, the first node outside of it which does not stop,
+    /// if there is one.
+    fn follow(&self, header: usize) -> Option<usize> {
+        let body = &self.loops[&header];
+        let exits: BTreeSet<usize> = body.iter()
+            .flat_map(|&node| self.graph.successors(node))
+            .filter(|next| !body.contains(next))
+            .collect();
+        exits.iter().copied().find(|&exit| !self.graph.is_stop(exit)).or(exits.first().copied())
+    }
+}
+
+/// Drops the labels no goto jumps to, turns branches which jump away into plain ifs, and loops
+/// exiting at their start into `while` with a condition.
+fn simplify(statements: Vec<Stmt>, gotos: &BTreeSet<usize>) -> Vec<Stmt> {
+    let mut out = vec![];
+    for statement in statements {
+        match statement {
+            Stmt::Label(address) if !gotos.contains(&address) => {},
+            Stmt::If(condition, then, otherwise) => {
+                let (then, otherwise) = (simplify(then, gotos), simplify(otherwise, gotos));
+                if then.is_empty() && otherwise.is_empty() {
+                    continue;
+                }
+                if then.is_empty() || (jumps_away(&otherwise) && (!jumps_away(&then) || otherwise.len() < then.len())) {
+                    out.push(Stmt::If(condition.invert(), otherwise, vec![]));
+                    out.extend(then);
+                } else if jumps_away(&then) {
+                    out.push(Stmt::If(condition, then, vec![]));
+                    out.extend(otherwise);
+                } else {
+                    out.push(Stmt::If(condition, then, otherwise));
+                }
+            },
+            Stmt::While(condition, body) => {
+                let mut body = simplify(body, gotos);
+                if body.last() == Some(&Stmt::Continue) {
+                    body.pop();
+                }
+                match &body[..] {
+                    [Stmt::If(exit, then, otherwise), ..] if condition == Expr::Const(1) && then[..] == [Stmt::Break] && otherwise.is_empty() => {
+                        let condition = exit.clone().invert();
+                        body.remove(0);
+                        out.push(Stmt::While(condition, body));
+                    },
+                    _ => out.push(Stmt::While(condition, body)),
+                }
+            },
+            statement => out.push(statement),
+        }
+    }
+    out
+}
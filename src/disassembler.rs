@@ -0,0 +1,255 @@
+//! Renders a program back into the mnemonics of the assembler.
+//!
+//! Only instructions reachable from address 0 are decoded. Control flow is followed through
+//! jumps with immediate targets and into the functions `functions::analyze` recognizes, which
+//! are labelled. Everything else is rendered as `.data`, so that values embedded between
+//! instructions are not mistaken for code.
+
+use std::collections::BTreeSet;
+use std::fmt::Display;
+
+use crate::functions::{self, Function};
+use crate::src::{Instruction, OPCODES, VALUE};
+
+/// Number of values rendered per `.data` line.
+const DATA_PER_LINE: usize = 8;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Mode {
+    Position,
+    Immediate,
+    Relative,
+}
+
+impl Mode {
+    fn from_digit(digit: VALUE) -> Option<Mode> {
+        match digit {
+            0 => Some(Mode::Position),
+            1 => Some(Mode::Immediate),
+            2 => Some(Mode::Relative),
+            _ => None,
+        }
+    }
+
+    /// The prefix of an operand in this mode, as used by the assembler.
+    pub fn sigil(&self) -> &'static str {
+        match self {
+            Mode::Position => "",
+            Mode::Immediate => "#",
+            Mode::Relative => "@",
+        }
+    }
+
+    fn short_name(&self) -> &'static str {
+        match self {
+            Mode::Position => "pos",
+            Mode::Immediate => "imm",
+            Mode::Relative => "rel",
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Operand {
+    pub mode: Mode,
+    pub value: VALUE,
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.mode.sigil(), self.value)
+    }
+}
+
+/// A single valid instruction.
+pub struct Decoded {
+    pub address: usize,
+    pub word: VALUE,
+    pub instruction: &'static Instruction,
+    pub operands: Vec<Operand>,
+}
+
+impl Decoded {
+    /// Number of cells the instruction occupies.
+    pub fn size(&self) -> usize {
+        1 + self.operands.len()
+    }
+
+    /// The addresses execution may continue at. Jumps with targets which are not immediate
+    /// can not be resolved statically and only contribute their fall through.
+    pub fn successors(&self) -> Vec<usize> {
+        let next = self.address + self.size();
+        match self.instruction.opcode {
+            99 => vec![],
+            5 | 6 => {
+                let condition = self.operands[0];
+                let target = self.operands[1];
+                let mut successors = vec![];
+
+                let jumps_always = condition.mode == Mode::Immediate && ((condition.value != 0) == (self.instruction.opcode == 5));
+                if !jumps_always {
+                    successors.push(next);
+                }
+                if target.mode == Mode::Immediate && target.value >= 0 {
+                    let never_jumps = condition.mode == Mode::Immediate && !jumps_always;
+                    if !never_jumps {
+                        successors.push(target.value as usize);
+                    }
+                }
+                successors
+            },
+            _ => vec![next],
+        }
+    }
+}
+
+/// Decodes the instruction at `address`, if it is a valid one.
+pub fn decode(code: &[VALUE], address: usize) -> Option<Decoded> {
+    decode_with(|k| code.get(k).copied(), address)
+}
+
+/// Like `decode`, but reads memory through `fetch`, eg. from a running `Interpreter`.
+pub fn decode_with(fetch: impl Fn(usize) -> Option<VALUE>, address: usize) -> Option<Decoded> {
+    let word = fetch(address)?;
+    if word <= 0 {
+        return None;
+    }
+
+    let instruction = OPCODES.iter().find(|instruction| instruction.opcode as VALUE == word % 100)?;
+
+    if word / 10i64.pow(2 + instruction.number_parameters as u32) != 0 {
+        return None;
+    }
+
+    let operands = (0..instruction.number_parameters)
+        .map(|k| Some(Operand {
+            mode: Mode::from_digit((word / 10i64.pow(2 + k as u32)) % 10)?,
+            value: fetch(address + 1 + k)?,
+        }))
+        .collect::<Option<Vec<_>>>()?;
+
+    if let Some(parameter) = instruction.writes {
+        if operands[parameter].mode == Mode::Immediate {
+            return None;
+        }
+    }
+
+    Some(Decoded { address, word, instruction, operands })
+}
+
+/// A line of the listing, either an instruction, a run of data, or the label of a function.
+pub enum Line {
+    Instruction(Decoded),
+    Data { address: usize, values: Vec<VALUE> },
+    Function(Function),
+}
+
+impl Line {
+    pub fn address(&self) -> usize {
+        match self {
+            Line::Instruction(decoded) => decoded.address,
+            Line::Data { address, .. } => *address,
+            Line::Function(function) => function.entry,
+        }
+    }
+}
+
+impl Display for Line {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Line::Instruction(decoded) => {
+                let operands = decoded.operands
+                    .iter()
+                    .map(|operand| operand.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let modes = decoded.operands
+                    .iter()
+                    .map(|operand| operand.mode.short_name())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let statement = format!("{:<20} {}", decoded.instruction.mnemonic(), operands);
+                write!(f, "{:>6}: {:<44} ; {} [{}]", decoded.address, statement, decoded.word, modes)
+            },
+            Line::Data { address, values } => {
+                let values = values
+                    .iter()
+                    .map(|val| val.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "{:>6}: {:<20} {}", address, ".data", values)
+            },
+            Line::Function(function) => {
+                let frame = match function.frame_size {
+                    Some(size) => format!("frame of {} cells", size),
+                    None => "no frame".to_owned(),
+                };
+                let join = |addresses: &mut dyn Iterator<Item = usize>| addresses.map(|x| x.to_string()).collect::<Vec<_>>().join(", ");
+                write!(f, "{:>6}: {:<44} ; {}, called from {}", function.entry, function.name() + ":", frame, join(&mut function.callers.iter().copied()))?;
+                if !function.returns.is_empty() {
+                    write!(f, ", returns at {}", join(&mut function.returns.iter().map(|ret| ret.address)))?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+/// Addresses of all instructions reachable from address 0.
+pub fn reachable(code: &[VALUE]) -> BTreeSet<usize> {
+    reachable_from(code, [0])
+}
+
+/// Addresses of all instructions reachable from any of `roots`.
+pub fn reachable_from(code: &[VALUE], roots: impl IntoIterator<Item = usize>) -> BTreeSet<usize> {
+    let mut starts = BTreeSet::new();
+    let mut covered = vec![false; code.len()];
+    let mut queue: Vec<usize> = roots.into_iter().collect();
+
+    while let Some(address) = queue.pop() {
+        if address >= code.len() || starts.contains(&address) || covered[address] {
+            continue;
+        }
+        let decoded = match decode(code, address) {
+            Some(decoded) => decoded,
+            None => continue,
+        };
+        if covered[address..address + decoded.size()].iter().any(|&x| x) {
+            continue;
+        }
+
+        covered[address..address + decoded.size()].iter_mut().for_each(|x| *x = true);
+        starts.insert(address);
+        queue.extend(decoded.successors());
+    }
+
+    starts
+}
+
+pub fn disassemble(code: &[VALUE]) -> Vec<Line> {
+    let mut analysis = functions::analyze(code);
+    let starts = analysis.instructions;
+    let mut lines = vec![];
+    let mut address = 0;
+
+    while address < code.len() {
+        if let Some(function) = analysis.functions.remove(&address) {
+            lines.push(Line::Function(function));
+        }
+        if starts.contains(&address) {
+            let decoded = decode(code, address).unwrap();
+            address += decoded.size();
+            lines.push(Line::Instruction(decoded));
+            continue;
+        }
+
+        let end = (address..code.len())
+            .take(DATA_PER_LINE)
+            .find(|k| starts.contains(k))
+            .unwrap_or(usize::min(address + DATA_PER_LINE, code.len()));
+        lines.push(Line::Data { address, values: code[address..end].to_vec() });
+        address = end;
+    }
+
+    lines
+}
@@ -0,0 +1,182 @@
+//! Recognizes functions following the calling convention of Intcode compilers.
+//!
+//! The relative base serves as stack pointer. A caller stores the arguments at `@1`, `@2`, ...,
+//! pushes the return address to `@0` and jumps to the function:
+//!
+//! ```text
+//!         add     #7, #0, @1          ; argument
+//!         add     #ret, #0, @0        ; return address
+//!         jf      #0, #function
+//! ret:    ...
+//! function:
+//!         arb     #3                  ; prologue, reserves a frame of 3 cells
+//!         ...                         ; the arguments at @-2 and @-1
+//!         arb     #-3                 ; epilogue
+//!         jt      #1, @0              ; return
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::cfg::{Block, Cfg, Edge};
+use crate::disassembler::{Decoded, Mode};
+use crate::src::VALUE;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CallSite {
+    /// The address of the jump into the function.
+    pub address: usize,
+    /// The called function, or `None` if the jump has no immediate target.
+    pub target: Option<usize>,
+    /// Where the function returns to, the address after the jump.
+    pub return_address: usize,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Return {
+    /// The address of the jump.
+    pub address: usize,
+    /// The cells the epilogue right before the jump releases, if there is one.
+    pub released: Option<VALUE>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Function {
+    pub entry: usize,
+    /// The cells the prologue reserves by moving the relative base, if there is a prologue.
+    pub frame_size: Option<VALUE>,
+    /// The addresses of the calls of the function.
+    pub callers: Vec<usize>,
+    pub returns: Vec<Return>,
+    /// The addresses of the instructions of the function. Code shared with other functions
+    /// belongs to all of them.
+    pub instructions: BTreeSet<usize>,
+}
+
+impl Function {
+    /// The label of the function in listings.
+    pub fn name(&self) -> String {
+        format!("function_{}", self.entry)
+    }
+
+    /// Whether the function has a prologue, and every return releases its frame.
+    pub fn is_balanced(&self) -> bool {
+        self.frame_size.is_some() && self.returns.iter().all(|ret| ret.released == self.frame_size)
+    }
+}
+
+pub struct Analysis {
+    /// The functions by their entry address, which is the target of a call.
+    pub functions: BTreeMap<usize, Function>,
+    pub calls: Vec<CallSite>,
+    /// The addresses of all instructions of the program and its functions, starting at 0.
+    pub instructions: BTreeSet<usize>,
+}
+
+/// Finds the call sites and functions of `code`, and the instructions of each function.
+pub fn analyze(code: &[VALUE]) -> Analysis {
+    let cfg = Cfg::new(code);
+    let calls: Vec<CallSite> = cfg.blocks.values().filter_map(call_site).collect();
+
+    let mut functions: BTreeMap<usize, Function> = BTreeMap::new();
+    for call in &calls {
+        let Some(entry) = call.target.filter(|target| cfg.blocks.contains_key(target)) else {
+            continue;
+        };
+        let function = functions.entry(entry).or_insert_with(|| {
+            let (instructions, returns) = body(&cfg, entry);
+            let frame_size = cfg.blocks[&entry].instructions.first()
+                .filter(|first| first.instruction.opcode == 9 && first.operands[0].mode == Mode::Immediate && first.operands[0].value > 0)
+                .map(|first| first.operands[0].value);
+            Function { entry, frame_size, callers: vec![], returns, instructions }
+        });
+        function.callers.push(call.address);
+    }
+
+    let mut instructions = BTreeSet::new();
+    if cfg.blocks.contains_key(&0) {
+        instructions = body(&cfg, 0).0;
+    }
+    for function in functions.values() {
+        instructions.extend(&function.instructions);
+    }
+
+    Analysis { functions, calls, instructions }
+}
+
+/// The call made by the jump ending `block`, if it is an unconditional jump after pushing
+/// the address following it to a relative cell.
+fn call_site(block: &Block) -> Option<CallSite> {
+    let jump = block.instructions.last()?;
+    let unconditional = !block.edges.is_empty() && block.edges.iter().all(|edge| !matches!(edge, Edge::Next(_)));
+    if !matches!(jump.instruction.opcode, 5 | 6) || !unconditional {
+        return None;
+    }
+    if !block.instructions.iter().any(|instruction| pushes(instruction, block.end)) {
+        return None;
+    }
+    let target = jump.operands[1];
+    Some(CallSite {
+        address: jump.address,
+        target: usize::try_from(target.value).ok().filter(|_| target.mode == Mode::Immediate),
+        return_address: block.end,
+    })
+}
+
+/// Whether `instruction` copies the immediate `val` to a relative cell, by adding 0 to it or
+/// multiplying it with 1.
+fn pushes(instruction: &Decoded, val: usize) -> bool {
+    let neutral = match instruction.instruction.opcode {
+        1 => 0,
+        2 => 1,
+        _ => return false,
+    };
+    match &instruction.operands[..] {
+        [a, b, target] if target.mode == Mode::Relative && a.mode == Mode::Immediate && b.mode == Mode::Immediate => {
+            (a.value == val as VALUE && b.value == neutral) || (a.value == neutral && b.value == val as VALUE)
+        },
+        _ => false,
+    }
+}
+
+/// The instructions reachable from `entry` without entering called functions, and the
+/// returns among them.
+fn body(cfg: &Cfg, entry: usize) -> (BTreeSet<usize>, Vec<Return>) {
+    let mut visited = BTreeSet::new();
+    let mut returns = vec![];
+    let mut queue = vec![entry];
+    while let Some(start) = queue.pop() {
+        if !visited.insert(start) {
+            continue;
+        }
+        let Some(block) = cfg.blocks.get(&start) else {
+            continue;
+        };
+
+        if let Some(call) = call_site(block) {
+            queue.push(call.return_address);
+            continue;
+        }
+        for edge in &block.edges {
+            match *edge {
+                Edge::Next(target) | Edge::Jump(target) => queue.push(target),
+                Edge::Indirect => {
+                    let (jump, rest) = block.instructions.split_last().unwrap();
+                    if jump.operands[1].mode == Mode::Relative {
+                        let released = rest.last()
+                            .filter(|epilogue| epilogue.instruction.opcode == 9 && epilogue.operands[0].mode == Mode::Immediate)
+                            .and_then(|epilogue| epilogue.operands[0].value.checked_neg());
+                        returns.push(Return { address: jump.address, released });
+                    }
+                },
+            }
+        }
+    }
+
+    let instructions = visited
+        .iter()
+        .filter_map(|start| cfg.blocks.get(start))
+        .flat_map(|block| block.instructions.iter().map(|instruction| instruction.address))
+        .collect();
+    returns.sort_unstable_by_key(|ret| ret.address);
+    (instructions, returns)
+}
@@ -0,0 +1,130 @@
+//! Sources of input and sinks for output of an `Interpreter`, see `Interpreter::with_io`.
+
+use std::collections::VecDeque;
+use std::io::{BufRead, Write};
+use std::sync::mpsc::{Receiver, Sender, SyncSender};
+
+use crate::src::VALUE;
+
+/// Provides the values read by the `input` instruction.
+pub trait InputSource {
+    /// The next input value, or `None` if there is none yet. The machine then returns
+    /// `RunResult::NeedsInput`, and asks again once it is resumed.
+    fn next_input(&mut self) -> Option<VALUE>;
+}
+
+/// Receives the values written by the `output` instruction. The machine returns every value as
+/// `RunResult::Output` as well.
+pub trait OutputSink {
+    fn emit(&mut self, val: VALUE);
+}
+
+impl InputSource for VecDeque<VALUE> {
+    fn next_input(&mut self) -> Option<VALUE> {
+        self.pop_front()
+    }
+}
+
+impl OutputSink for VecDeque<VALUE> {
+    fn emit(&mut self, val: VALUE) {
+        self.push_back(val);
+    }
+}
+
+impl OutputSink for Vec<VALUE> {
+    fn emit(&mut self, val: VALUE) {
+        self.push(val);
+    }
+}
+
+/// Discards every value, the default sink.
+impl OutputSink for () {
+    fn emit(&mut self, _: VALUE) {}
+}
+
+/// Calls the closure for every input value.
+impl<F: FnMut() -> Option<VALUE>> InputSource for F {
+    fn next_input(&mut self) -> Option<VALUE> {
+        self()
+    }
+}
+
+/// Calls the closure with every output value.
+impl<F: FnMut(VALUE)> OutputSink for F {
+    fn emit(&mut self, val: VALUE) {
+        self(val)
+    }
+}
+
+/// Takes the input from an iterator, eg. `FromIter([1, 2].into_iter())`.
+pub struct FromIter<T>(pub T);
+
+impl<T: Iterator<Item = VALUE>> InputSource for FromIter<T> {
+    fn next_input(&mut self) -> Option<VALUE> {
+        self.0.next()
+    }
+}
+
+/// Blocks until a value is received. Once all senders are gone, there is no further input.
+impl InputSource for Receiver<VALUE> {
+    fn next_input(&mut self) -> Option<VALUE> {
+        self.recv().ok()
+    }
+}
+
+impl OutputSink for Sender<VALUE> {
+    fn emit(&mut self, val: VALUE) {
+        // A machine keeps running even if nobody listens anymore
+        let _ = self.send(val);
+    }
+}
+
+impl OutputSink for SyncSender<VALUE> {
+    fn emit(&mut self, val: VALUE) {
+        let _ = self.send(val);
+    }
+}
+
+/// Parses input values from a stream, separated by commas or whitespace. Lines are only read
+/// once all values of the previous ones have been consumed. Input ends at the end of the stream,
+/// or at the first value which is not a number.
+pub struct ReadSource<R: BufRead> {
+    reader: R,
+    pending: VecDeque<VALUE>,
+    done: bool,
+}
+
+impl<R: BufRead> ReadSource<R> {
+    pub fn new(reader: R) -> ReadSource<R> {
+        ReadSource { reader, pending: VecDeque::new(), done: false }
+    }
+}
+
+impl<R: BufRead> InputSource for ReadSource<R> {
+    fn next_input(&mut self) -> Option<VALUE> {
+        while self.pending.is_empty() && !self.done {
+            let mut line = String::new();
+            match self.reader.read_line(&mut line) {
+                Ok(0) | Err(_) => self.done = true,
+                Ok(_) => {
+                    for token in line.split(|c: char| c == ',' || c.is_whitespace()).filter(|x| !x.is_empty()) {
+                        match token.parse() {
+                            Ok(val) => self.pending.push_back(val),
+                            Err(_) => { self.done = true; break; },
+                        }
+                    }
+                },
+            }
+        }
+        self.pending.pop_front()
+    }
+}
+
+/// Writes one value per line to a stream.
+pub struct WriteSink<W: Write>(pub W);
+
+impl<W: Write> OutputSink for WriteSink<W> {
+    fn emit(&mut self, val: VALUE) {
+        let _ = writeln!(self.0, "{}", val);
+    }
+}
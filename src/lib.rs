@@ -0,0 +1,31 @@
+use src::Interpreter;
+use std::collections::VecDeque;
+
+pub mod src;
+pub mod memory;
+pub mod blocks;
+pub mod budget;
+pub mod io;
+pub mod loader;
+pub mod amplifier;
+pub mod asynchronous;
+pub mod threaded;
+pub mod network;
+pub mod ascii;
+pub mod assembler;
+pub mod disassembler;
+pub mod cfg;
+pub mod functions;
+pub mod decompiler;
+pub mod debugger;
+pub mod trace;
+pub mod snapshot;
+pub mod aot;
+
+pub mod cli;
+pub mod days;
+
+/// Runs the program until it halts and returns its outputs.
+pub fn execute(data: Vec<src::VALUE>, input_buffer: VecDeque<src::VALUE>) -> Result<Vec<src::VALUE>, src::InterpreterError> {
+    Interpreter::new(data, input_buffer).outputs().collect()
+}
@@ -0,0 +1,194 @@
+//! Reads and writes programs in several formats.
+//!
+//! Text programs consist of values separated by commas, whitespace or both, on any number of
+//! lines. Comments start with `#` or `;` and run to the end of the line. A trailing comma is
+//! allowed, empty entries like `1,,2` are not.
+//!
+//! Binary programs start with `MAGIC`, followed by every value as a zigzag encoded LEB128
+//! varint, so small values of either sign take a single byte.
+
+use std::error::Error;
+use std::fmt::Display;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::src::VALUE;
+
+/// The start of every binary program. Never valid text, as it starts with a NUL byte.
+pub const MAGIC: &[u8; 4] = b"\0IC\x01";
+
+/// Values per line of `Format::Dump`.
+const DUMP_WIDTH: usize = 10;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// A single line of comma separated values, like the puzzle inputs.
+    Commas,
+    /// A single line of space separated values.
+    Whitespace,
+    /// `DUMP_WIDTH` values per line, each line commented with the address of its first value.
+    Dump,
+    /// The compact binary encoding.
+    Binary,
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    /// A text entry which is not a number, with its (1-based) line and column.
+    InvalidValue { line: usize, column: usize, value: String },
+    /// A comma without a value before it.
+    EmptyValue { line: usize, column: usize },
+    /// Text which is not UTF-8, with the offset of the first invalid byte.
+    InvalidUtf8 { offset: usize },
+    /// A binary program ending in the middle of a value.
+    Truncated { offset: usize },
+    /// A binary value which does not fit into a `VALUE`.
+    Overflow { offset: usize },
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "{}", e),
+            LoadError::InvalidValue { line, column, value } => write!(f, "line {}, column {}: Invalid value `{}`", line, column, value),
+            LoadError::EmptyValue { line, column } => write!(f, "line {}, column {}: Expected a value before `,`", line, column),
+            LoadError::InvalidUtf8 { offset } => write!(f, "byte {}: Neither text nor a binary program", offset),
+            LoadError::Truncated { offset } => write!(f, "byte {}: Program ends in the middle of a value", offset),
+            LoadError::Overflow { offset } => write!(f, "byte {}: Value does not fit into an Intcode value", offset),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> LoadError {
+        LoadError::Io(e)
+    }
+}
+
+/// Parses a text program.
+pub fn parse(text: &str) -> Result<Vec<VALUE>, LoadError> {
+    let mut code = vec![];
+    // Whether the last token was a value, as opposed to a comma or the start
+    let mut after_value = false;
+
+    for (k, line) in text.lines().enumerate() {
+        let line = &line[..line.find(['#', ';']).unwrap_or(line.len())];
+        let mut chars = line.char_indices().peekable();
+        let mut column = 0;
+
+        while let Some((start, c)) = chars.next() {
+            column += 1;
+            if c == ',' {
+                if !after_value {
+                    return Err(LoadError::EmptyValue { line: k + 1, column });
+                }
+                after_value = false;
+            } else if !c.is_whitespace() {
+                let start_column = column;
+                let mut end = start + c.len_utf8();
+                while let Some(&(i, c)) = chars.peek() {
+                    if c == ',' || c.is_whitespace() {
+                        break;
+                    }
+                    chars.next();
+                    column += 1;
+                    end = i + c.len_utf8();
+                }
+                let value = &line[start..end];
+                code.push(value.parse().map_err(|_| LoadError::InvalidValue { line: k + 1, column: start_column, value: value.to_owned() })?);
+                after_value = true;
+            }
+        }
+    }
+    Ok(code)
+}
+
+/// Decodes a binary program, including `MAGIC`.
+pub fn decode(bytes: &[u8]) -> Result<Vec<VALUE>, LoadError> {
+    let mut code = vec![];
+    let mut offset = MAGIC.len();
+
+    while offset < bytes.len() {
+        let start = offset;
+        let mut val: u64 = 0;
+        let mut shift = 0;
+        loop {
+            let byte = *bytes.get(offset).ok_or(LoadError::Truncated { offset: start })?;
+            offset += 1;
+            if shift == 63 && byte > 1 || shift > 63 {
+                return Err(LoadError::Overflow { offset: start });
+            }
+            val |= ((byte & 0x7f) as u64) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        code.push((val >> 1) as VALUE ^ -((val & 1) as VALUE));
+    }
+    Ok(code)
+}
+
+/// Loads a program in any format, telling binary programs apart by `MAGIC`.
+pub fn load(bytes: &[u8]) -> Result<Vec<VALUE>, LoadError> {
+    if bytes.starts_with(MAGIC) {
+        return decode(bytes);
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => parse(text),
+        Err(e) => Err(LoadError::InvalidUtf8 { offset: e.valid_up_to() }),
+    }
+}
+
+pub fn load_file(path: impl AsRef<Path>) -> Result<Vec<VALUE>, LoadError> {
+    load(&fs::read(path)?)
+}
+
+fn join(values: &[VALUE], separator: &str) -> String {
+    values.iter().map(|val| val.to_string()).collect::<Vec<_>>().join(separator)
+}
+
+/// Writes `code` in the given format, which `load` reads back unchanged.
+pub fn write(code: &[VALUE], format: Format, mut w: impl Write) -> io::Result<()> {
+    match format {
+        Format::Commas => writeln!(w, "{}", join(code, ","))?,
+        Format::Whitespace => writeln!(w, "{}", join(code, " "))?,
+        Format::Dump => {
+            let lines = code.chunks(DUMP_WIDTH).count();
+            for (k, values) in code.chunks(DUMP_WIDTH).enumerate() {
+                let separator = if k + 1 < lines { "," } else { "" };
+                writeln!(w, "{}{:<2} # {}", join(values, ", "), separator, k * DUMP_WIDTH)?;
+            }
+        },
+        Format::Binary => {
+            w.write_all(MAGIC)?;
+            for &val in code {
+                let mut zigzag = ((val << 1) ^ (val >> 63)) as u64;
+                loop {
+                    let byte = (zigzag & 0x7f) as u8;
+                    zigzag >>= 7;
+                    if zigzag == 0 {
+                        w.write_all(&[byte])?;
+                        break;
+                    }
+                    w.write_all(&[byte | 0x80])?;
+                }
+            }
+        },
+    }
+    w.flush()
+}
+
+pub fn to_bytes(code: &[VALUE], format: Format) -> Vec<u8> {
+    let mut bytes = vec![];
+    write(code, format, &mut bytes).expect("Writing to a Vec never fails");
+    bytes
+}
+
+pub fn save(code: &[VALUE], format: Format, path: impl AsRef<Path>) -> io::Result<()> {
+    write(code, format, io::BufWriter::new(fs::File::create(path)?))
+}
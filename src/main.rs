@@ -1,105 +1,55 @@
-use src::Interpreter;
-use std::collections::VecDeque;
+use intcode::*;
 
-mod src;
-
-pub mod days;
+#[cfg(test)]
+mod compiled;
 
 fn main() {
-    let code = vec![3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99];
-
-    let mut pc = src::Interpreter::new(code.to_owned(), vec![].into());
-
-    loop {
-        let res = pc.step_loop();
-        match res {
-            Ok(val) => println!("{}", val),
-            Err(src::InterpreterError::NoInputError) => { pc.input_buffer.push_back(read_input()) },
-            Err(src::InterpreterError::Terminated) => { break; },
-            Err(e) => {
-                println!("Error! {:?}", e);
-                println!("{:?}", pc);
-                break;
-            }
-        }
-    }
-
-    //days::day17::camera();
-    
-    //days::day15::day15b();
-    //days::day15::day15a();
-
-    //days::day11::day11a();
-    //days::day11::day11b();
-
-    //days::day13::day13a();
-    //days::day13::day13b();
-
-    //days::day9::day9a();
-    //days::day9::day9b();
-
-    //days::day7::day7a();
-    //days::day7::day7b();
-
-    //days::day5::day5a();
-    //days::day5::day5b();
-}
-
-fn read_input() -> src::VALUE {
-    loop {
-        println!("Input buffer empty. Use stdin. Waiting for input: ");
-
-        let mut input = String::new();
-        if let Err(e) = std::io::stdin().read_line(&mut input) {
-            println!("Error: Interpreter failed to read input: {}", e);
-            panic!()
-        } 
-
-        if let Ok(num) = input.trim().parse::<src::VALUE>() {
-            return num
-        }
-    }
-}
-
-fn execute(data: Vec<src::VALUE>, input_buffer: VecDeque<src::VALUE>) -> Result<Vec<src::VALUE>, src::InterpreterError> {
-    let mut pc = Interpreter::new(data.to_owned(), input_buffer);
-    let mut output = vec![];
-
-    loop {
-        let res = pc.step_loop();
-
-        match res {
-            Err(src::InterpreterError::Terminated) => {break},
-            Err(e) => { return Err(e); }, 
-            Ok(val) => { output.push(val); },
-        }
-    }
-
-    Ok(output)
-}
-
-fn string_to_code(code_str: &str) -> Vec<src::VALUE> {
-    code_str
-        .trim()
-        .split(',')
-        .map(|x| str::parse(x).unwrap())
-        .collect()
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    std::process::exit(cli::main(&args, &mut std::io::stdout().lock(), &mut std::io::stderr()));
 }
 
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use src::{Interpreter, InterpreterError};
+    use src::{ErrorKind, Interpreter, InterpreterError, RunResult};
+    use memory::MemoryModel;
 
-    //const DAY2A_RESULT: src::VALUE = 6087827;
-    //const DAY2B_RESULT: src::VALUE = 5379;
+    const DAY2A_RESULT: src::VALUE = 6087827;
+    const DAY2B_RESULT: src::VALUE = 5379;
     const DAY5A_RESULT: src::VALUE = 5182797;
     const DAY5B_RESULT: src::VALUE = 12077198;
     const DAY7A_RESULT: src::VALUE = 77500;
     const DAY7B_RESULT: src::VALUE = 22476942;
     const DAY9A_RESULT: src::VALUE = 2406950601;
     const DAY9B_RESULT: src::VALUE = 83239;
+    const DAY11A_RESULT: src::VALUE = 2418;
+    const DAY13A_RESULT: src::VALUE = 324;
+    const DAY13B_RESULT: src::VALUE = 15957;
+    const DAY15A_RESULT: src::VALUE = 294;
+    const DAY15B_RESULT: src::VALUE = 388;
+    const DAY17A_RESULT: src::VALUE = 3888;
+    const DAY17B_RESULT: src::VALUE = 927809;
+
+    /// The answer of a solution, discarding what it prints.
+    fn solve(solution: fn(&mut dyn std::io::Write) -> Result<src::VALUE, days::DayError>) -> src::VALUE {
+        solution(&mut std::io::sink()).unwrap()
+    }
+
+    /// The parts of a fault which identify it, without the machine context.
+    fn summary(e: &InterpreterError) -> (ErrorKind, usize, src::VALUE, Option<usize>) {
+        (e.kind, e.ip, e.opcode, e.parameter)
+    }
+
+    #[test]
+    fn day2a() {
+        assert_eq!(DAY2A_RESULT, solve(days::day2::day2a));
+    }
+
+    #[test]
+    fn day2b() {
+        assert_eq!(DAY2B_RESULT, solve(days::day2::day2b));
+    }
 
     #[test]
     fn day5a() {
@@ -107,23 +57,23 @@ mod tests {
         wanted[9] = DAY5A_RESULT;
         let wanted = wanted;
 
-        let given = execute(string_to_code(include_str!("../data/day5.txt")), vec![1].into()).unwrap();
+        let given = execute(loader::parse(include_str!("../data/day5.txt")).unwrap(), vec![1].into()).unwrap();
 
         assert_eq!(wanted, given);
     }
 
     #[test]
     fn day5b() {
-        assert_eq!(DAY5B_RESULT, days::day5::day5b());
+        assert_eq!(DAY5B_RESULT, solve(days::day5::day5b));
     }
 
     #[test]
     fn day5misc() {
         // Misc official tests. 
-        let position_equal_code = string_to_code("3,9,8,9,10,9,4,9,99,-1,8");
-        let position_smaller_code = string_to_code("3,9,7,9,10,9,4,9,99,-1,8");
-        let immediate_equal_code = string_to_code("3,3,1108,-1,8,3,4,3,99");
-        let immediate_smaller_code = string_to_code("3,3,1107,-1,8,3,4,3,99");
+        let position_equal_code = loader::parse("3,9,8,9,10,9,4,9,99,-1,8").unwrap();
+        let position_smaller_code = loader::parse("3,9,7,9,10,9,4,9,99,-1,8").unwrap();
+        let immediate_equal_code = loader::parse("3,3,1108,-1,8,3,4,3,99").unwrap();
+        let immediate_smaller_code = loader::parse("3,3,1107,-1,8,3,4,3,99").unwrap();
 
 
         let given = execute(position_equal_code.to_owned(), vec![8].into()).unwrap();
@@ -147,8 +97,8 @@ mod tests {
         assert_eq!(given, vec![0]);
 
 
-        let position_jump = string_to_code("3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9");
-        let immediate_jump = string_to_code("3,3,1105,-1,9,1101,0,0,12,4,12,99,1");
+        let position_jump = loader::parse("3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9").unwrap();
+        let immediate_jump = loader::parse("3,3,1105,-1,9,1101,0,0,12,4,12,99,1").unwrap();
 
         let given = execute(position_jump.to_owned(), vec![1].into()).unwrap();
         assert_eq!(given, vec![1]);
@@ -161,7 +111,7 @@ mod tests {
         let given = execute(immediate_jump, vec![0].into()).unwrap();
         assert_eq!(given, vec![0]);
 
-        let larger_example = string_to_code("3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99");
+        let larger_example = loader::parse("3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99").unwrap();
 
         let given = execute(larger_example.to_owned(), vec![7].into()).unwrap();
         assert_eq!(given, vec![999]);
@@ -172,26 +122,416 @@ mod tests {
 
     }
 
+    #[test]
+    fn assembler() {
+        // The larger example of day 5
+        let source = "
+            ; Compares the input to 8
+                    input   value
+                    equals  value, #8, flag
+                    jump-if-true flag, #equal
+                    less-than #8, value, flag
+                    jump-if-false flag, #less
+                    jump-if-false #0, #greater
+                    .data   98
+            flag:   .data   0
+            value:  .data   0
+            equal:  multiply value, #125, flag      ; 8 * 125 = 1000
+                    output  flag
+                    jt      #1, #end
+            less:   out     #999
+                    jt      #1, #end
+            greater:
+                    add     #1000, #1, flag
+                    output  flag
+                    jump-if-true #1, #end
+                    .data   98
+            end:    halt
+        ";
+        let code = assembler::assemble(source).unwrap();
+
+        assert_eq!(code, loader::parse("3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99").unwrap());
+        assert_eq!(execute(code, vec![9].into()).unwrap(), vec![1001]);
+
+        let code = assembler::assemble("arb #text+1\nout @-1\nhlt\ntext: .ascii \"Hi; \\\"x\\\"\\n\"").unwrap();
+        assert_eq!(code, vec![109, 6, 204, -1, 99, 'H' as i64, 'i' as i64, ';' as i64, ' ' as i64, '"' as i64, 'x' as i64, '"' as i64, '\n' as i64]);
+        assert_eq!(execute(code, vec![].into()).unwrap(), vec!['H' as i64]);
+
+        let code = assembler::assemble("out #-9223372036854775808\nhlt").unwrap();
+        assert_eq!(code, vec![104, i64::MIN, 99]);
+
+        let error = |source| assembler::assemble(source).unwrap_err();
+        assert_eq!(error("halt\n  jt #1, #nowhere").to_string(), "line 2: Undefined label `nowhere`");
+        assert_eq!(error("add #1, #2, #3").kind, assembler::AsmErrorKind::ImmediateWrite { mnemonic: "add".to_owned(), parameter: 2 });
+        assert_eq!(error("\n\nx: halt\nx: halt").line, 4);
+        assert_eq!(error("jt #1").line, 1);
+        assert_eq!(error("add 1,,2").kind, assembler::AsmErrorKind::InvalidOperand("".to_owned()));
+        assert_eq!(error(".byte 1").kind, assembler::AsmErrorKind::UnknownDirective(".byte".to_owned()));
+    }
+
+    #[test]
+    fn disassembler() {
+        let code = loader::parse("3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99").unwrap();
+        let lines = disassembler::disassemble(&code);
+        let listing: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+
+        assert_eq!(listing[1], "     2: equals               21, #8, 20              ; 1008 [pos imm pos]");
+        assert_eq!(listing[6], "    19: .data                98, 0, 0");
+        assert_eq!(listing[7].split(';').next().unwrap().trim_end(), "    22: multiply             21, #125, 20");
+        assert_eq!(listing[15], "    45: .data                98");
+        assert_eq!(listing[16].split(';').next().unwrap().trim_end(), "    46: halt");
+
+        // Without addresses and comments, the listing assembles back into the program.
+        let source: String = listing
+            .iter()
+            .map(|line| line.split_once(": ").unwrap().1.split(';').next().unwrap().to_owned() + "\n")
+            .collect();
+        assert_eq!(assembler::assemble(&source).unwrap(), code);
+
+        for program in [include_str!("../data/day9.txt"), include_str!("../data/day15.txt")] {
+            let code = loader::parse(program).unwrap();
+            let source: String = disassembler::disassemble(&code)
+                .iter()
+                .map(|line| line.to_string().split_once(": ").unwrap().1.split(';').next().unwrap().to_owned() + "\n")
+                .collect();
+            assert_eq!(assembler::assemble(&source).unwrap(), code);
+        }
+    }
+
+    #[test]
+    fn cfg() {
+        use cfg::{Cfg, Edge};
+
+        // Counts down from the input, jumping back while it is not zero, then returns through
+        // the address at 30
+        let mut code = loader::parse("3,29,1001,29,-1,29,4,29,1005,29,2,106,0,30,99").unwrap();
+        code.resize(31, 0);
+        code[30] = 14;
+        let graph = Cfg::new(&code);
+        let edges: Vec<(usize, usize, &[Edge])> = graph.blocks.values().map(|block| (block.start, block.end, &block.edges[..])).collect();
+        assert_eq!(edges, [
+            (0, 2, &[Edge::Next(2)][..]),
+            (2, 11, &[Edge::Next(11), Edge::Jump(2)]),
+            (11, 14, &[Edge::Indirect]),
+            (14, 15, &[]),
+        ]);
+        assert_eq!(graph.to_dot(), "\
+digraph cfg {
+    node [shape=box, fontname=\"monospace\"];
+    b0 [label=\"0: input 29\\l\"];
+    b0 -> b2;
+    b2 [label=\"2: add 29, #-1, 29\\l6: output 29\\l8: jump-if-true 29, #2\\l\"];
+    b2 -> b11;
+    b2 -> b2 [label=\"jump\"];
+    b11 [label=\"11: jump-if-false #0, 30\\l\"];
+    b11 -> indirect [style=dashed];
+    b14 [label=\"14: halt \\l\"];
+    indirect [label=\"indirect\", shape=ellipse, style=dashed];
+}
+");
+
+        // Every instruction of the programs of `data` is in exactly one block
+        // Along with the number of indirect jumps, mostly returns from functions
+        for (day, indirect) in [(2, 0), (5, 0), (7, 1), (9, 5), (11, 5), (13, 5), (15, 0), (17, 6)] {
+            let code = loader::load_file(format!("data/day{}.txt", day)).unwrap();
+            let graph = Cfg::new(&code);
+            let addresses: Vec<usize> = graph.blocks.values().flat_map(|block| block.instructions.iter().map(|x| x.address)).collect();
+            assert_eq!(addresses, aot::entry_points(&code).into_iter().collect::<Vec<_>>());
+            for block in graph.blocks.values() {
+                let last = block.instructions.last().unwrap();
+                assert_eq!(block.end, last.address + last.size());
+            }
+            assert_eq!(graph.to_dot().matches("-> indirect [style=dashed]").count(), indirect, "day {}", day);
+        }
+    }
+
+    #[test]
+    fn functions() {
+        use functions::{Function, Return};
+
+        let code = loader::load_file("data/day9.txt").unwrap();
+        let analysis = functions::analyze(&code);
+        assert_eq!(analysis.functions.values().cloned().map(|function| Function { instructions: Default::default(), ..function }).collect::<Vec<_>>(), [Function {
+            entry: 920,
+            frame_size: Some(3),
+            callers: vec![910, 937, 952],
+            returns: vec![Return { address: 968, released: Some(3) }],
+            instructions: Default::default(),
+        }]);
+        assert_eq!(analysis.functions[&920].instructions.range(..).next_back(), Some(&968));
+        assert!(analysis.instructions.contains(&913) && !disassembler::reachable(&code).contains(&913));
+
+        // The number of functions and of calls through function pointers, all functions
+        // releasing their frame on return
+        for (day, count, indirect) in [(2, 0, 0), (5, 0, 0), (7, 0, 0), (9, 1, 0), (11, 3, 1), (13, 5, 0), (15, 0, 0), (17, 4, 0)] {
+            let code = loader::load_file(format!("data/day{}.txt", day)).unwrap();
+            let analysis = functions::analyze(&code);
+            assert_eq!(analysis.functions.len(), count, "day {}", day);
+            assert_eq!(analysis.calls.iter().filter(|call| call.target.is_none()).count(), indirect, "day {}", day);
+            assert!(analysis.functions.values().all(Function::is_balanced), "day {}", day);
+
+            // The labelled listing still assembles back into the program
+            let listing: Vec<String> = disassembler::disassemble(&code).iter().map(|line| line.to_string()).collect();
+            assert_eq!(listing.iter().filter(|line| line.contains(": function_")).count(), count);
+            let source: String = listing
+                .iter()
+                .map(|line| line.split_once(": ").unwrap().1.split(';').next().unwrap().to_owned() + "\n")
+                .collect();
+            assert_eq!(assembler::assemble(&source).unwrap(), code, "day {}", day);
+        }
+
+        // An epilogue releasing `i64::MIN` cells does not match a frame
+        let code = loader::parse("21101,7,0,0,1106,0,8,99,109,-9223372036854775808,2105,1,0").unwrap();
+        let analysis = functions::analyze(&code);
+        assert_eq!(analysis.functions[&8].returns, [Return { address: 10, released: None }]);
+        assert_eq!(disassembler::disassemble(&code).len(), 6);
+        assert_eq!(decompiler::decompile(&code).len(), 2);
+    }
+
+    #[test]
+    fn decompiler() {
+        let source = "
+                    arb     #stack
+                    in      value
+                    add     value, #0, @1
+                    add     #back, #0, @0
+                    jf      #0, #sum
+            back:   out     @1
+                    hlt
+            sum:    arb     #3
+                    add     #0, #0, @-1
+            loop:   jf      @-2, #done
+                    eq      @-2, #3, flag
+                    jt      flag, #skip
+                    add     @-1, @-2, @-1
+                    jf      #0, #next
+            skip:   mul     @-1, #2, @-1
+            next:   add     @-2, #-1, @-2
+                    jt      #1, #loop
+            done:   add     @-1, #0, @-2
+                    arb     #-3
+                    jt      #1, @0
+            flag:   .data   0
+            value:  .data   0
+            stack:  .data   0
+        ";
+        let code = assembler::assemble(source).unwrap();
+        assert_eq!(execute(code.clone(), vec![5].into()).unwrap(), vec![(5 + 4) * 2 + 2 + 1]);
+
+        let procedures: Vec<String> = decompiler::decompile(&code).iter().map(|procedure| procedure.to_string()).collect();
+        assert_eq!(procedures, [
+            "void main() {\n    rb += 63;\n    mem[62] = input();\n    function_18(mem[62]);\n    output(mem[64]);\n    halt();\n}",
+            "// frame of 3 cells, called from 12\nvoid function_18(int arg1) {\n    int local2;\n    local2 = 0;\n    while (arg1) {\n        \
+             mem[61] = arg1 == 3;\n        if (!mem[61]) {\n            local2 = local2 + arg1;\n        } else {\n            \
+             local2 = local2 * 2;\n        }\n        arg1 = arg1 - 1;\n    }\n    arg1 = local2;\n}",
+        ]);
+
+        // The recursive function of day 9 passes its argument and result in the same cell
+        let procedures = decompiler::decompile(&loader::load_file("data/day9.txt").unwrap());
+        assert_eq!(procedures[1].to_string(), "// frame of 3 cells, called from 910, 937, 952\nvoid function_920(int arg1) {\n    \
+            int local2, out1;\n    mem[63] = arg1 < 3;\n    if (!mem[63]) {\n        function_920(arg1 - 1);\n        \
+            local2 = out1;\n        function_920(arg1 - 3);\n        arg1 = out1 + local2;\n    }\n}");
+
+        // The maze of day 15 is read from a table, by patching an operand
+        let pseudocode = decompiler::decompile(&loader::load_file("data/day15.txt").unwrap())[0].to_string();
+        assert!(pseudocode.contains("mem[211] = mem[1032] + 252;\n") && pseudocode.contains("mem[1044] = mem[mem[211]] < 74;\n"));
+
+        // Slots far below the frame are named by their distance
+        let code = loader::parse("21101,7,0,0,1106,0,8,99,204,-9223372036854775808,109,2,109,-2,2105,1,0").unwrap();
+        assert!(decompiler::decompile(&code)[1].to_string().contains("    output(caller9223372036854775808);\n"));
+
+        // The number of procedures, loops and gotos
+        for (day, count, loops, gotos) in [(2, 1, 0, 0), (5, 1, 0, 0), (7, 1, 0, 0), (9, 2, 0, 0), (11, 4, 5, 5), (13, 6, 7, 3), (15, 1, 1, 5), (17, 5, 10, 13)] {
+            let procedures = decompiler::decompile(&loader::load_file(format!("data/day{}.txt", day)).unwrap());
+            let pseudocode: String = procedures.iter().map(|procedure| procedure.to_string()).collect();
+            assert_eq!((procedures.len(), pseudocode.matches("while (").count(), pseudocode.matches("goto label_").count()), (count, loops, gotos), "day {}", day);
+        }
+    }
+
+    #[test]
+    fn debugger() {
+        let code = loader::parse("3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99").unwrap();
+        let commands = "\
+            break 22
+            watch 20
+            continue
+            input 8
+            continue
+            regs
+            continue
+            step
+            unwatch 20
+            step 1
+            regs
+            mem 19 22
+            next
+            bogus
+        ";
+
+        let mut debugger = debugger::Debugger::new(Interpreter::new(code, vec![].into()));
+        let mut out = vec![];
+        debugger.run(commands.as_bytes(), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        let expected = [
+            "Breakpoint at 22",
+            "Watching 20 (currently 0)",
+            "Waiting for input, use `input <values>`",
+            "Watchpoint 20: 0 -> 1",
+            "ip: 6, relative base: 0, input buffer: [], last output: None",
+            "Breakpoint at 22",
+            "Watchpoint 20: 1 -> 1000",
+            "Output: 1000",
+            "ip: 28, relative base: 0, input buffer: [], last output: Some(1000)",
+            "    19: 98 1000 8",
+            "Program halted",
+            "Error: Unknown command `bogus`",
+        ];
+        let mut rest = out.as_str();
+        for line in expected {
+            let k = rest.find(line).unwrap_or_else(|| panic!("Missing `{}` in:\n{}", line, out));
+            rest = &rest[k + line.len()..];
+        }
+    }
+
+    #[test]
+    fn trace() {
+        let code = loader::parse("3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99").unwrap();
+
+        let run = |input: src::VALUE| {
+            let (sender, receiver) = std::sync::mpsc::channel();
+            let mut pc = Interpreter::new(code.to_owned(), vec![input].into());
+            pc.set_tracer(Some(Box::new(sender)));
+            while pc.step_loop() != Ok(RunResult::Halted) {}
+            receiver.try_iter().collect::<Vec<_>>()
+        };
+
+        let (smaller, equal) = (run(7), run(8));
+        assert_eq!(smaller.len(), 8);
+        assert_eq!(equal.len(), 7);
+        // Both runs take the same path until the first jump depending on the input
+        let ips = |records: &[trace::TraceRecord]| records.iter().map(|record| record.ip).collect::<Vec<_>>();
+        assert_eq!(ips(&smaller), vec![0, 2, 6, 9, 13, 31, 33, 46]);
+        assert_eq!(ips(&equal), vec![0, 2, 6, 22, 26, 28, 46]);
+
+        assert_eq!(equal[1], trace::TraceRecord {
+            ip: 2,
+            opcode: 1008,
+            name: "equals",
+            param_indices: vec![21, 4, 20],
+            reads: vec![8, 8, 0],
+            write: Some((20, 1)),
+            relative_base: 0,
+        });
+        assert_eq!(equal[1].to_json(), r#"{"ip":2,"opcode":1008,"name":"equals","param_indices":[21,4,20],"reads":[8,8,0],"write":{"address":20,"value":1},"relative_base":0}"#);
+        assert_eq!(equal[6].to_json(), r#"{"ip":46,"opcode":99,"name":"halt","param_indices":[],"reads":[],"write":null,"relative_base":0}"#);
+        let quoted = trace::TraceRecord { name: "a \"b\"\\\n", ..equal[6].clone() };
+        assert!(quoted.to_json().contains(r#""name":"a \"b\"\\\n","#));
+        assert_eq!(equal[1].to_string(), "     2:   1008 equals               indices [21, 4, 20] reads [8, 8, 0] writes [20] = 1 rb 0");
+    }
+
     #[test]
     fn day7a() {
-        assert_eq!(days::day7::day7a(), DAY7A_RESULT);
+        assert_eq!(solve(days::day7::day7a), DAY7A_RESULT);
     }
 
     #[test]
     fn day7b() {
-        assert_eq!(days::day7::day7b(), DAY7B_RESULT);
+        assert_eq!(solve(days::day7::day7b), DAY7B_RESULT);
     }
 
     #[test]
     fn day9a() {
-        let result = days::day9::day9a();
+        let result = solve(days::day9::day9a);
         assert_eq!(DAY9A_RESULT, result);
     }
 
     #[test]
-    #[ignore = "This is more of a benchmark. Disabled to cut down on waiting time."]
     fn day9b() {
-        assert_eq!(DAY9B_RESULT, days::day9::day9b());
+        assert_eq!(DAY9B_RESULT, solve(days::day9::day9b));
+    }
+
+    #[test]
+    fn day11a() {
+        assert_eq!(DAY11A_RESULT, solve(days::day11::day11a));
+    }
+
+    #[test]
+    fn day13() {
+        assert_eq!(DAY13A_RESULT, solve(days::day13::day13a));
+        let mut out = vec![];
+        assert_eq!(days::day13::day13b(&mut out).unwrap(), DAY13B_RESULT);
+        assert!(String::from_utf8(out).unwrap().ends_with("FINAL SCORE: 15957\n"));
+    }
+
+    #[test]
+    fn day15a() {
+        assert_eq!(DAY15A_RESULT, solve(days::day15::day15a));
+    }
+
+    #[test]
+    fn day15b() {
+        assert_eq!(DAY15B_RESULT, solve(days::day15::day15b));
+    }
+
+    #[test]
+    fn snapshot() {
+        use snapshot::{Snapshot, SnapshotError};
+
+        let larger_example = loader::parse("3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99").unwrap();
+
+        for model in [MemoryModel::Dense, MemoryModel::Sparse, MemoryModel::Fixed] {
+            let mut pc = Interpreter::with_memory(larger_example.to_owned(), [].into(), model);
+            assert_eq!(pc.step_loop(), Ok(RunResult::NeedsInput));
+
+            // Forking the machine
+            let mut fork = pc.clone();
+            pc.input_buffer.push_back(7);
+            fork.input_buffer.push_back(9);
+            assert_eq!(pc.step_loop(), Ok(RunResult::Output(999)));
+            assert_eq!(fork.step_loop(), Ok(RunResult::Output(1001)));
+
+            // Saving and restoring it
+            if model != MemoryModel::Fixed {
+                pc.write(5000, 17).unwrap();
+            }
+            let mut bytes = vec![];
+            pc.snapshot().write_to(&mut bytes).unwrap();
+            let mut restored = Interpreter::from_snapshot(Snapshot::read_from(&bytes[..]).unwrap());
+
+            assert_eq!(restored.memory().model(), model);
+            assert_eq!(restored.memory().len(), pc.memory().len());
+            assert_eq!(restored.slice(0..pc.memory().len()), pc.slice(0..pc.memory().len()));
+            assert_eq!((restored.ip(), restored.relative_base(), restored.last_output), (pc.ip(), pc.relative_base(), pc.last_output));
+            assert_eq!(restored.step_loop(), Ok(RunResult::Halted));
+        }
+
+        let mut pc = Interpreter::new(vec![3, 0, 4, 0, 99], [].into());
+        pc.input_buffer.extend([5, -6]);
+        let mut bytes = vec![];
+        pc.snapshot().write_to(&mut bytes).unwrap();
+        assert_eq!(String::from_utf8(bytes.to_owned()).unwrap(), "\
+            intcode-snapshot 1\n\
+            model dense\n\
+            len 5\n\
+            ip 0\n\
+            relative-base 0\n\
+            finish false\n\
+            last-output none\n\
+            input 5,-6\n\
+            segment 0 3,0,4,0,99\n");
+
+        let error = |s: &str| Snapshot::read_from(s.as_bytes()).unwrap_err().to_string();
+        assert_eq!(error("intcode-snapshot 2\n"), "Unsupported snapshot version 2, expected 1");
+        assert_eq!(error("1,2,3\n"), "Not an intcode snapshot");
+        assert_eq!(error("intcode-snapshot 1\nmodel dense\nip x\n"), "Missing field `len`");
+        assert_eq!(error("intcode-snapshot 1\nmodel dense\nlen 5\nip -1\nrelative-base 0\nfinish false\nlast-output none\ninput \n"), "line 4: Invalid entry `ip -1`");
+        let fields = "finish false\nlast-output none\ninput \n";
+        assert_eq!(error(&format!("intcode-snapshot 1\nmodel dense\nlen 2\nip 0\nrelative-base 0\n{}segment 0 1,2,3\n", fields)), "line 3: Invalid entry `len 2`");
+        assert_eq!(error(&format!("intcode-snapshot 1\nmodel fixed\nlen 9000000000000000000\nip 0\nrelative-base 0\n{}", fields)), "line 3: Invalid entry `len 9000000000000000000`");
+        assert!(Snapshot::read_from(format!("intcode-snapshot 1\nmodel sparse\nlen 9000000000000000000\nip 0\nrelative-base 0\n{}", fields).as_bytes()).is_ok());
+        assert_eq!(error(&format!("intcode-snapshot 1\nmodel sparse\nlen 1\nip 0\nrelative-base 0\n{}segment 18446744073709551615 1,2\n", fields)), "line 9: Invalid entry `segment 18446744073709551615 1,2`");
+        assert!(matches!(Snapshot::load("data/missing.snapshot"), Err(SnapshotError::Io(_))));
     }
 
     #[test]
@@ -199,27 +539,745 @@ mod tests {
         let mut pc = Interpreter::new(vec![-1, 0, 99], [].into());
 
         let e = pc.step();
-        assert_eq!(e, Err(InterpreterError::InvalidOpCode));
+        assert_eq!(e.map_err(|e| summary(&e)), Err((ErrorKind::InvalidOpCode, 0, -1, None)));
 
-        for op in [11301, 13101, 31101, 1_11101, 700001, 10001] {
+        for (op, parameter) in [(11301, Some(0)), (13101, Some(1)), (31101, Some(2)), (1_11101, None), (700001, None), (10001, Some(2)), (103, Some(0))] {
             println!("{}", op);
             let mut pc = Interpreter::new(vec![op, 0, 99], [].into());
             let e = pc.step();
-            assert_eq!(e, Err(InterpreterError::InvalidParameters));
+            assert_eq!(e.map_err(|e| summary(&e)), Err((ErrorKind::InvalidParameters, 0, op, parameter)));
         }
 
-        let mut pc = Interpreter::new(vec![1101, 99, 99, 99], [].into());
+        let mut pc = Interpreter::with_memory(vec![1101, 99, 99, 99], [].into(), MemoryModel::Fixed);
         let e = pc.step();
-        assert_eq!(e, Err(InterpreterError::InvalidParameters));
+        assert_eq!(e.map_err(|e| summary(&e)), Err((ErrorKind::InvalidParameters, 0, 1101, Some(2))));
+
+        let mut pc = Interpreter::new(vec![1101, 1, 2, 7, 1106, 0, -1, 0], [].into());
+        assert_eq!(pc.step(), Ok(None));
+        let e = pc.step();
+        assert_eq!(e.map_err(|e| summary(&e)), Err((ErrorKind::JumpOutOfBounds, 4, 1106, Some(1))));
+
+        let mut pc = Interpreter::new(vec![1102, src::VALUE::MAX, 2, 0, 99], [].into());
+        assert_eq!(pc.step().unwrap_err().kind, ErrorKind::Overflow);
+        // Faults do not advance the machine
+        assert_eq!(pc.step().unwrap_err().kind, ErrorKind::Overflow);
+
+        let mut pc = Interpreter::new(loader::parse("109,9223372036854775807,109,1,99").unwrap(), [].into());
+        assert_eq!(pc.step(), Ok(None));
+        let e = pc.step();
+        assert_eq!(e.map_err(|e| summary(&e)), Err((ErrorKind::Overflow, 2, 109, None)));
+        assert_eq!(pc.relative_base(), isize::MAX);
+    }
+
+    #[test]
+    fn fault_diagnostics() {
+        let mut pc = Interpreter::new(vec![1, 0, 0, 3, 21101, 1, 2, -60, 99], [].into());
+        assert_eq!(pc.step(), Ok(None));
+        let e = pc.step().unwrap_err();
+        assert_eq!(e.context.name, Some("add"));
+        assert_eq!(e.context.modes, vec![1, 1, 2]);
+        assert_eq!(e.context.address, Some(-60));
+        assert_eq!((e.context.excerpt_start, e.context.excerpt.len()), (0, 9));
+        assert_eq!(e.to_string(), "\
+error: Invalid parameters
+ --> ip 4: instruction 21101 (add), parameter 2
+  |
+0 | 1 0 0 2 21101 1 2 -60 99
+  |         ^^^^^     ~~~
+  = modes: [1, 1, 2]
+  = address: -60");
+
+        let mut pc = Interpreter::new(vec![1106, 0, -7], [].into());
+        let e = pc.step().unwrap_err();
+        assert_eq!((e.kind, e.context.address), (ErrorKind::JumpOutOfBounds, Some(-7)));
+
+        // Superfluous mode digits are reported, and unknown opcodes have no name
+        let e = Interpreter::new(vec![1_11101, 0, 0, 0], [].into()).step().unwrap_err();
+        assert_eq!(e.context.modes, vec![1, 1, 1, 1]);
+        let e = Interpreter::new(vec![1042, 0], [].into()).step().unwrap_err();
+        assert_eq!((e.context.name, e.context.modes.clone()), (None, vec![0, 1]));
+
+        // The excerpt is a window around ip
+        let mut code = [1101, 0, 0, 0].repeat(10);
+        code.push(-1);
+        let mut pc = Interpreter::new(code, [].into());
+        let e = pc.step_loop().unwrap_err();
+        assert_eq!((e.ip, e.context.excerpt_start, e.context.excerpt), (40, 36, vec![1101, 0, 0, 0, -1]));
+    }
+
+    #[test]
+    fn instruction_set() {
+        use src::{InstructionSet, RegisterError};
+        use std::sync::{Arc, Mutex};
+
+        let mut instructions = InstructionSet::default();
+        assert_eq!(instructions.iter().count(), 10);
+
+        // 10 a b c: c = a^b, 11 a: a host call which records a value, 12 a: jump to a
+        instructions.register(10, "power", 3, Some(2), |pc| {
+            let (base, exponent) = (pc.read(pc.parameter(0))?, pc.read(pc.parameter(1))?);
+            let val = base.checked_pow(exponent as u32).ok_or_else(|| pc.fault(ErrorKind::Overflow, None))?;
+            pc.write(pc.parameter(2), val)?;
+            pc.advance();
+            Ok(None)
+        }).unwrap();
+        let calls = Arc::new(Mutex::new(vec![]));
+        let log = Arc::clone(&calls);
+        instructions.register(11, "host", 1, None, move |pc| {
+            log.lock().unwrap().push(pc.read(pc.parameter(0))?);
+            pc.advance();
+            Ok(None)
+        }).unwrap();
+        instructions.register(12, "goto", 1, None, |pc| {
+            pc.set_ip(pc.read(pc.parameter(0))? as usize);
+            Ok(None)
+        }).unwrap();
+
+        assert_eq!(instructions.register(1, "add", 3, Some(2), |_| Ok(None)), Err(RegisterError::Occupied(1)));
+        assert_eq!(instructions.register(0, "nop", 0, None, |_| Ok(None)), Err(RegisterError::InvalidOpcode(0)));
+        assert_eq!(instructions.register(20, "wide", 17, None, |_| Ok(None)), Err(RegisterError::TooManyParameters(17)));
+        assert_eq!(instructions.register(20, "nop", 1, Some(1), |_| Ok(None)), Err(RegisterError::InvalidWrite(1)));
+
+        let code = vec![1110, 2, 10, 16, 111, 7, 112, 11, 104, -1, 99, 4, 16, 99, 0, 0, 0];
+        let mut pc = Interpreter::new(code.to_owned(), [].into());
+        pc.set_instructions(instructions.clone());
+        assert_eq!(pc.step_loop(), Ok(RunResult::Output(1024)));
+        assert_eq!(pc.last_output, Some(1024));
+        assert_eq!(pc.step_loop(), Ok(RunResult::Halted));
+        assert_eq!(*calls.lock().unwrap(), vec![7]);
+
+        // Clones share the instruction set, the standard set does not know the new opcodes
+        let mut fork = pc.clone();
+        assert_eq!(fork.instructions().iter().count(), 13);
+        let e = Interpreter::new(code.to_owned(), [].into()).step().unwrap_err();
+        assert_eq!((e.kind, e.context.name), (ErrorKind::InvalidOpCode, None));
+
+        // Custom faults carry the name of the instruction, standard instructions can be replaced
+        let mut pc = Interpreter::new(vec![1110, 10, 30, 0, 99], [].into());
+        pc.set_instructions(instructions.clone());
+        let e = pc.step().unwrap_err();
+        assert_eq!((e.kind, e.context.name), (ErrorKind::Overflow, Some("power")));
+
+        let mut instructions = InstructionSet::default();
+        assert_eq!(instructions.remove(4).map(|x| x.name), Some("output"));
+        instructions.register(4, "output twice", 1, None, |pc| {
+            let val = pc.read(pc.parameter(0))?;
+            pc.advance();
+            Ok(Some(RunResult::Output(2 * val)))
+        }).unwrap();
+        let mut pc = Interpreter::new(vec![104, 21, 99], [].into());
+        pc.set_instructions(instructions);
+        assert_eq!(pc.step_loop(), Ok(RunResult::Output(42)));
+        assert_eq!(fork.step_loop(), Ok(RunResult::Halted));
+    }
+
+    #[test]
+    fn aot() {
+        use aot::{AotError, Compiled, Policy};
+        use std::collections::VecDeque;
+
+        type Machine = fn(VecDeque<src::VALUE>) -> Compiled;
+        // Program, compiled module and inputs which are cycled through
+        let programs: [(&str, &str, Machine, &[src::VALUE]); 2] = [
+            ("data/day9.txt", include_str!("compiled/day9.rs"), compiled::day9::machine, &[1]),
+            ("data/day9.txt", include_str!("compiled/day9.rs"), compiled::day9::machine, &[2]),
+        ];
+
+        for (path, module, machine, inputs) in programs {
+            let code = loader::load_file(path).unwrap();
+            assert_eq!(aot::translate(&code, path, Policy::Refuse).unwrap(), module, "{} is outdated", path);
+
+            let mut pc = Interpreter::new(code, [].into());
+            let mut compiled = machine([].into());
+            let mut inputs = inputs.iter().cycle();
+            loop {
+                let expected = pc.step_loop();
+                assert_eq!(compiled.step_loop(), expected, "{}", path);
+                match expected {
+                    Ok(RunResult::NeedsInput) => {
+                        let val = *inputs.next().unwrap();
+                        pc.input_buffer.push_back(val);
+                        compiled.push_input(val);
+                    },
+                    Ok(RunResult::Output(_)) => {},
+                    _ => break,
+                }
+            }
+            assert_eq!(compiled.read(0), pc.read(0).unwrap(), "{}", path);
+            // Only programs which never hand over to the interpreter are checked in
+            assert!(compiled.is_native(), "{}", path);
+        }
+
+        // The others write into their own code, so they are translated but not checked in
+        for path in ["data/day2.txt", "data/day5.txt", "data/day7.txt", "data/day11.txt", "data/day13.txt", "data/day15.txt", "data/day17.txt"] {
+            let code = loader::load_file(path).unwrap();
+            assert!(aot::translate(&code, path, Policy::Fallback).is_ok(), "{}", path);
+        }
+        let day2 = loader::load_file("data/day2.txt").unwrap();
+        assert_eq!(aot::translate(&day2, "day2", Policy::Refuse), Err(AotError::SelfModifying { ip: 0, address: 3 }));
+    }
+
+    #[test]
+    fn blocks() {
+        use blocks::Engine;
+        use budget::Budget;
+
+        /// Runs `code` on both engines, cycling through `inputs`, and asserts that they agree.
+        fn compare(code: &[src::VALUE], inputs: &[src::VALUE], budget: Budget) {
+            let [mut pc, mut cached] = [Engine::Step, Engine::Blocks].map(|engine| {
+                let mut pc = Interpreter::new(code.to_owned(), [].into()).with_engine(engine);
+                pc.set_budget(budget);
+                pc
+            });
+            let mut inputs = inputs.iter().cycle();
+            for _ in 0..5000 {
+                let expected = pc.step_loop().map_err(|e| summary(&e));
+                assert_eq!(cached.step_loop().map_err(|e| summary(&e)), expected, "{:?}", code);
+                assert_eq!((cached.ip(), cached.relative_base(), cached.usage()), (pc.ip(), pc.relative_base(), pc.usage()));
+                match expected {
+                    Ok(RunResult::NeedsInput) => {
+                        let val = *inputs.next().unwrap();
+                        pc.input_buffer.push_back(val);
+                        cached.input_buffer.push_back(val);
+                    },
+                    Ok(RunResult::Output(_)) => {},
+                    _ => break,
+                }
+            }
+            assert_eq!(cached.memory().segments(), pc.memory().segments());
+        }
+
+        let programs: [(&str, &[src::VALUE]); 10] = [
+            ("data/day2.txt", &[]),
+            ("data/day5.txt", &[1]),
+            ("data/day5.txt", &[5]),
+            ("data/day7.txt", &[3, 0]),
+            ("data/day9.txt", &[1]),
+            ("data/day9.txt", &[2]),
+            ("data/day11.txt", &[0, 1, 1]),
+            ("data/day13.txt", &[]),
+            ("data/day15.txt", &[1, 4, 2, 3]),
+            ("data/day17.txt", &[]),
+        ];
+        for (path, inputs) in programs {
+            compare(&loader::load_file(path).unwrap(), inputs, Budget::unlimited());
+        }
+
+        // Faults are left to `step`, also in the middle of a block
+        for code in [vec![-1, 0, 99], vec![11301, 0, 99], vec![1101, 1, 2, 7, 1106, 0, -1, 0], vec![1101, 0, 0, 0, 1102, src::VALUE::MAX, 2, 0, 99],
+                     vec![1, 0, 0, 3, 21101, 1, 2, -60, 99], vec![3, 0, 3, 0, 99], loader::parse("109,1000000,21101,3,4,0,204,0,99").unwrap(),
+                     loader::parse("109,9223372036854775807,109,1,99").unwrap(),
+                     // Blocks far past the allocated memory are not cached
+                     loader::parse("109,4000000000000000000,21101,99,0,0,1105,1,4000000000000000000").unwrap()] {
+            compare(&code, &[1], Budget::unlimited());
+        }
+        compare(&[1105, 1, 0], &[], Budget::unlimited().with_instructions(100));
+        compare(&[104, 1, 1105, 1, 0], &[], Budget::unlimited().with_outputs(3));
+        compare(&[1101, 7, 0, 100000, 99], &[], Budget::unlimited().with_memory(1000));
+
+        // Writes into the running block evict it, here the operand of the output
+        let mut pc = Interpreter::new(vec![1101, 7, 0, 5, 104, 0, 99], [].into()).with_engine(Engine::Blocks);
+        assert_eq!(pc.step_loop(), Ok(RunResult::Output(7)));
+        // As do writes from outside, and changes of the instruction set
+        let mut pc = Interpreter::new(vec![104, 1, 1105, 1, 0], [].into()).with_engine(Engine::Blocks);
+        assert_eq!(pc.step_loop(), Ok(RunResult::Output(1)));
+        pc.write(1, 2).unwrap();
+        assert_eq!(pc.step_loop(), Ok(RunResult::Output(2)));
+        let mut instructions = src::InstructionSet::default();
+        instructions.remove(4);
+        instructions.register(4, "output twice", 1, None, |pc| {
+            let val = pc.read(pc.parameter(0))?;
+            pc.advance();
+            Ok(Some(RunResult::Output(2 * val)))
+        }).unwrap();
+        pc.set_instructions(instructions);
+        assert_eq!(pc.step_loop(), Ok(RunResult::Output(4)));
+        assert_eq!(pc.engine(), Engine::Blocks);
+    }
+
+    #[test]
+    fn budget() {
+        use budget::{Budget, Limit};
+
+        // Jumps to itself forever
+        let mut pc = Interpreter::new(vec![1105, 1, 0], [].into());
+        pc.set_budget(Budget::unlimited().with_instructions(100));
+        assert_eq!(pc.step_loop(), Ok(RunResult::BudgetExhausted(Limit::Instructions)));
+        assert_eq!(pc.usage().instructions, 100);
+        pc.set_budget(pc.budget().with_instructions(250));
+        assert_eq!(pc.run(), Ok(RunResult::BudgetExhausted(Limit::Instructions)));
+        assert_eq!(pc.usage().instructions, 250);
+
+        pc.set_budget(Budget::unlimited().with_timeout(std::time::Duration::from_millis(10)));
+        assert_eq!(pc.step_loop(), Ok(RunResult::BudgetExhausted(Limit::Deadline)));
+
+        // Outputs 1 forever
+        let mut pc = Interpreter::new(vec![104, 1, 1105, 1, 0], [].into());
+        pc.set_budget(Budget::unlimited().with_outputs(3));
+        let outputs: Vec<_> = pc.outputs().collect();
+        assert_eq!(outputs[..3], [Ok(1), Ok(1), Ok(1)]);
+        assert_eq!(outputs[3].as_ref().unwrap_err().kind, ErrorKind::BudgetExhausted(Limit::Outputs));
+        assert_eq!(outputs.len(), 4);
+
+        // Outputting exactly the limit and halting is no exhausted budget
+        for engine in [blocks::Engine::Step, blocks::Engine::Blocks] {
+            let mut pc = Interpreter::new(vec![104, 1, 104, 2, 1101, 0, 0, 0, 99], [].into()).with_engine(engine);
+            pc.set_budget(Budget::unlimited().with_outputs(2));
+            assert_eq!(pc.outputs().collect::<Vec<_>>(), vec![Ok(1), Ok(2)]);
+            assert_eq!(pc.step_loop(), Ok(RunResult::Halted));
+        }
+
+        // The write far beyond the code is stopped before it happens, and can be resumed
+        let mut pc = Interpreter::new(vec![1101, 7, 0, 100000, 99], [].into());
+        pc.set_budget(Budget::unlimited().with_memory(1000));
+        assert_eq!(pc.step_loop(), Ok(RunResult::BudgetExhausted(Limit::Memory)));
+        assert_eq!((pc.ip(), pc.memory().cells()), (0, 5));
+        pc.set_budget(Budget::unlimited());
+        assert_eq!(pc.step_loop(), Ok(RunResult::Halted));
+        assert_eq!(pc.read(100000), Ok(7));
+
+        let mut pc = Interpreter::with_memory(vec![1101, 7, 0, 100000, 99], [].into(), MemoryModel::Sparse);
+        pc.set_budget(Budget::unlimited().with_memory(2048));
+        assert_eq!(pc.step_loop(), Ok(RunResult::Halted));
+        assert_eq!(pc.memory().cells(), 2048);
+
+        let mut amplifiers = amplifier::Amplifiers::ring(&[3, 7, 4, 7, 1105, 1, 0, 0], &[1, 2]);
+        amplifiers.machines[1].set_budget(Budget::unlimited().with_instructions(10));
+        assert_eq!(amplifiers.run(0).unwrap_err().kind, ErrorKind::BudgetExhausted(Limit::Instructions));
+    }
+
+    #[test]
+    fn loader() {
+        use loader::{Format, LoadError};
+
+        let code = loader::parse(include_str!("../data/day9.txt")).unwrap();
+        let extremes = vec![0, -1, 1, 63, -64, 64, src::VALUE::MAX, src::VALUE::MIN];
+        for format in [Format::Commas, Format::Whitespace, Format::Dump, Format::Binary] {
+            for code in [&code, &extremes, &vec![]] {
+                assert_eq!(loader::load(&loader::to_bytes(code, format)).unwrap(), *code, "{:?}", format);
+            }
+        }
+        assert_eq!(loader::to_bytes(&extremes[..5], Format::Binary), b"\0IC\x01\x00\x01\x02\x7e\x7f");
+
+        let text = "# Adds two numbers\n1101, 2 3,\n\t5 ; comment\r\n99,\n";
+        assert_eq!(loader::parse(text).unwrap(), vec![1101, 2, 3, 5, 99]);
+
+        let position = |text: &str| match loader::parse(text).unwrap_err() {
+            LoadError::InvalidValue { line, column, .. } | LoadError::EmptyValue { line, column } => (line, column),
+            e => panic!("Unexpected error {:?}", e),
+        };
+        assert_eq!(position("1,2,\n3,,4"), (2, 3));
+        assert_eq!(position(",1"), (1, 1));
+        assert_eq!(position("1,2\n  99 x4"), (2, 6));
+        assert_eq!(loader::parse("1, 2x").unwrap_err().to_string(), "line 1, column 4: Invalid value `2x`");
+
+        assert!(matches!(loader::load(b"\0IC\x01\x80"), Err(LoadError::Truncated { offset: 4 })));
+        assert!(matches!(loader::load(b"\0IC\x01\x00\xff\xff\xff\xff\xff\xff\xff\xff\xff\x7f"), Err(LoadError::Overflow { offset: 5 })));
+        assert!(matches!(loader::load(b"1,2\xff"), Err(LoadError::InvalidUtf8 { offset: 3 })));
+        assert!(matches!(loader::load_file("data/missing.txt"), Err(LoadError::Io(_))));
+    }
+
+    #[test]
+    fn io() {
+        use io::{FromIter, ReadSource, WriteSink};
+        use std::sync::mpsc;
+
+        // Doubles every input until it reads 0
+        let code = loader::parse("3,20,1006,20,14,1002,20,2,20,4,20,1105,1,0,99").unwrap();
+
+        let mut pc = Interpreter::with_io(code.to_owned(), MemoryModel::Dense, FromIter([1, 2, 3, 0].into_iter()), vec![]);
+        assert_eq!(pc.run(), Ok(RunResult::Halted));
+        assert_eq!(pc.output, vec![2, 4, 6]);
+
+        let mut next = 0;
+        let mut sum = 0;
+        let mut pc = Interpreter::with_io(code.to_owned(), MemoryModel::Dense, || { next += 1; Some(next % 4) }, |val| sum += val);
+        assert_eq!(pc.run(), Ok(RunResult::Halted));
+        drop(pc);
+        assert_eq!(sum, 12);
+
+        let input = ReadSource::new("5, 6\n\n7 0 x".as_bytes());
+        let mut pc = Interpreter::with_io(code.to_owned(), MemoryModel::Dense, input, WriteSink(vec![]));
+        assert_eq!(pc.outputs().collect::<Result<Vec<_>, _>>(), Ok(vec![10, 12, 14]));
+        assert_eq!(String::from_utf8(pc.output.0).unwrap(), "10\n12\n14\n");
+
+        // Input which runs out is a fault when iterating the outputs
+        let input = ReadSource::new("1 x 2".as_bytes());
+        let mut pc = Interpreter::with_io(code.to_owned(), MemoryModel::Dense, input, ());
+        let outputs: Vec<_> = pc.outputs().collect();
+        assert_eq!(outputs[0], Ok(2));
+        assert_eq!(outputs[1].as_ref().unwrap_err().kind, ErrorKind::NoInput);
+        assert_eq!(outputs.len(), 2);
+
+        // Channels connect machines running on different threads
+        let (input_tx, input_rx) = mpsc::channel();
+        let (output_tx, output_rx) = mpsc::channel();
+        let machine = std::thread::spawn(move || {
+            Interpreter::with_io(code, MemoryModel::Dense, input_rx, output_tx).run()
+        });
+        for val in [4, 5] {
+            input_tx.send(val).unwrap();
+            assert_eq!(output_rx.recv(), Ok(2 * val));
+        }
+        drop(input_tx);
+        assert_eq!(machine.join().unwrap(), Ok(RunResult::NeedsInput));
+
+        assert_eq!(execute(loader::parse("104,1,104,2,99").unwrap(), [].into()), Ok(vec![1, 2]));
+    }
+
+    #[test]
+    fn amplifiers() {
+        use amplifier::{Amplifiers, Topology};
+
+        let code = loader::parse("3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0").unwrap();
+        assert_eq!(Amplifiers::chain(&code, &[4, 3, 2, 1, 0]).run(0), Ok(Some(43210)));
+        assert_eq!(amplifier::search(&code, &[0, 1, 2, 3, 4], Topology::Chain), Ok(Some((43210, vec![4, 3, 2, 1, 0]))));
+
+        let code = loader::parse("3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5").unwrap();
+        assert_eq!(Amplifiers::ring(&code, &[9, 8, 7, 6, 5]).run(0), Ok(Some(139629729)));
+        assert_eq!(amplifier::search(&code, &[5, 6, 7, 8, 9], Topology::Ring), Ok(Some((139629729, vec![9, 8, 7, 6, 5]))));
+
+        // Prefixes may hold any number of inputs. Here the second machine waits for a third value.
+        let add = loader::parse("3,0,3,1,1,0,1,0,4,0,99").unwrap();
+        let mut amplifiers = Amplifiers::new(&add, vec![vec![1], vec![]], Topology::Chain);
+        let e = amplifiers.run(2).unwrap_err();
+        assert_eq!((e.kind, e.ip), (ErrorKind::NoInput, 2));
+        let mut amplifiers = Amplifiers::new(&add, vec![vec![1], vec![10]], Topology::Chain);
+        assert_eq!(amplifiers.run(2), Ok(Some(13)));
+    }
+
+    #[test]
+    fn asynchronous() {
+        use asynchronous::Executor;
+        use budget::{Budget, Limit};
+        use itertools::Itertools;
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        // Every phase setting of day 7 at once, 600 machines on a single thread
+        let code = loader::load_file("data/day7.txt").unwrap();
+        let mut executor = Executor::new();
+        let rings: Vec<_> = (5..10).permutations(5).map(|phases| asynchronous::spawn_ring(&mut executor, &code, &phases, 0)).collect();
+        assert_eq!(executor.run(), 0);
+        let signals = rings.iter().map(|ring| ring.last().unwrap().take().unwrap().unwrap().unwrap());
+        assert_eq!(signals.max(), Some(DAY7B_RESULT));
+
+        // Machines waiting for each other are left pending
+        let mut executor = Executor::new();
+        let ring = asynchronous::spawn_ring(&mut executor, &[3, 0, 3, 0, 3, 0, 99], &[1, 2], 0);
+        assert_eq!(executor.run(), 2);
+        assert!(ring.iter().all(|machine| !machine.is_finished()));
+
+        // A full sink suspends the machine until its values are taken
+        let code = loader::load_file("data/day17.txt").unwrap();
+        let expected = execute(code.to_owned(), [].into()).unwrap();
+        let mut executor = Executor::new();
+        let (_input, rx) = asynchronous::channel();
+        let (tx, mut output) = asynchronous::bounded(2);
+        let machine = executor.spawn(asynchronous::drive(Interpreter::new(code, [].into()), rx, tx));
+        let received = executor.spawn(async move {
+            let mut received = vec![];
+            while let Some(val) = output.recv().await {
+                received.push(val);
+            }
+            received
+        });
+        assert_eq!(executor.run(), 0);
+        assert_eq!(machine.take().unwrap(), Ok(expected.last().copied()));
+        assert_eq!(received.take().unwrap(), expected);
+
+        // Machines which never wait take turns, within their own budget
+        let finished = Rc::new(RefCell::new(vec![]));
+        let mut executor = Executor::new();
+        let mut looping = Interpreter::new(vec![1105, 1, 0], [].into());
+        looping.set_budget(Budget::unlimited().with_instructions(10 * asynchronous::SLICE));
+        for (name, pc) in [("loop", looping), ("echo", Interpreter::new(vec![3, 0, 4, 0, 99], [7].into()))] {
+            let (_input, rx) = asynchronous::channel();
+            let (tx, _output) = asynchronous::channel();
+            let finished = Rc::clone(&finished);
+            executor.spawn(async move {
+                let res = asynchronous::drive(pc, rx, tx).await;
+                finished.borrow_mut().push((name, res.map_err(|e| e.kind)));
+            });
+        }
+        assert_eq!(executor.run(), 0);
+        assert_eq!(finished.borrow()[..], [("echo", Ok(Some(7))), ("loop", Err(ErrorKind::BudgetExhausted(Limit::Instructions)))]);
+    }
+
+    #[test]
+    fn threaded() {
+        use itertools::Itertools;
+        use threaded::{Graph, GraphError};
+
+        let code = loader::load_file("data/day7.txt").unwrap();
+        let signals = (5..10).permutations(5).map(|phases| *Graph::ring(&code, &phases, 0).run().unwrap()[4].last().unwrap());
+        assert_eq!(signals.max(), Some(DAY7B_RESULT));
+
+        // Doubles its input, the output of the first machine going to both others
+        let double = loader::parse("3,0,1002,0,2,0,4,0,99").unwrap();
+        let mut graph = Graph::new();
+        let source = graph.machine(&double, &[5]);
+        let left = graph.machine(&double, &[]);
+        let right = graph.machine(&[3, 0, 3, 1, 1, 0, 1, 0, 4, 0, 99], &[]);
+        graph.connect(source, left);
+        graph.connect(source, right);
+        graph.connect(left, right);
+        assert_eq!(graph.run(), Ok(vec![vec![10], vec![20], vec![30]]));
+
+        // Machines in a ring waiting for each other, and machines waiting for a faulted one
+        assert_eq!(Graph::ring(&[3, 0, 3, 0, 3, 0, 99], &[1, 2], 0).run(), Err(GraphError::Deadlock { machines: vec![0, 1] }));
+        let mut graph = Graph::new();
+        let waiting = graph.machine(&double, &[]);
+        let faulty = graph.machine(&[1101, 1, 1, -1], &[]);
+        graph.connect(faulty, waiting);
+        let e = graph.run().unwrap_err();
+        assert!(matches!(e, GraphError::Fault { machine: 1, error: InterpreterError { kind: ErrorKind::InvalidParameters, .. } }), "{:?}", e);
+    }
+
+    #[test]
+    fn network() {
+        use network::{Event, Nat, Network, Packet};
+
+        // Machine 0 sends a packet to machine 1, every machine passes received packets on to
+        // the next address, with its own address as x and y incremented.
+        let code = assembler::assemble("
+                    in      addr
+                    jt      addr, #wait
+                    out     #1
+                    out     #0
+                    out     #100
+            wait:   in      x
+                    eq      x, #-1, tmp
+                    jt      tmp, #wait
+                    in      y
+                    add     addr, #1, dest
+                    add     y, #1, y
+                    out     dest
+                    out     addr
+                    out     y
+                    jt      #1, #wait
+            addr:   .data   0
+            x:      .data   0
+            y:      .data   0
+            tmp:    .data   0
+            dest:   .data   0
+        ").unwrap();
+
+        let mut network = Network::new(&code, 4);
+        let mut sent = vec![];
+        let res: Option<()> = network.run(|_, event| {
+            if let Event::Sent(packet) = event {
+                sent.push(packet);
+            }
+            None
+        }).unwrap();
+        assert_eq!(res, None);
+        assert!(network.is_idle());
+        assert_eq!(sent.len(), 4);
+        assert_eq!(sent[3], Packet { source: 3, destination: 4, x: 3, y: 103 });
+
+        // The NAT picks up the packets leaving the network, and restarts it whenever it is idle
+        let mut network = Network::new(&code, 4);
+        let mut nat = Nat::new(4);
+        let mut forwarded = vec![];
+        let res = network.run(|network, event| {
+            let packet = nat.handle(network, event)?;
+            forwarded.push(packet.y);
+            (forwarded.len() == 3).then_some(packet)
+        }).unwrap();
+        assert_eq!(forwarded, vec![103, 107, 111]);
+        assert_eq!(res, Some(Packet { source: 4, destination: 0, x: 3, y: 111 }));
+        assert!(!network.send(Packet { source: 0, destination: 4, x: 0, y: 0 }));
+    }
+
+    #[test]
+    fn day17a() {
+        assert_eq!(DAY17A_RESULT, solve(days::day17::day17a));
+    }
+
+    #[test]
+    fn day17b() {
+        assert_eq!(DAY17B_RESULT, solve(days::day17::day17b));
+    }
+
+    #[test]
+    fn ascii() {
+        use ascii::{Console, Event};
+
+        // Greets, then echoes a line of input and outputs 1000
+        let code = assembler::assemble("
+                    out     #72
+                    out     #105
+                    out     #10
+            loop:   in      c
+                    out     c
+                    eq      c, #10, t
+                    jf      t, #loop
+                    out     #1000
+                    hlt
+            c:      .data   0
+            t:      .data   0
+        ").unwrap();
+
+        let mut console = Console::new(Interpreter::new(code.to_owned(), [].into()));
+        assert_eq!(console.next_event(), Ok(Event::Line("Hi".to_owned())));
+        assert_eq!(console.next_event(), Ok(Event::NeedsInput));
+        console.send_line("échoé");
+        // Characters beyond ASCII are passed through as numbers
+        assert_eq!(console.next_event(), Ok(Event::Number('é' as src::VALUE)));
+        assert_eq!(console.next_event(), Ok(Event::Line("cho".to_owned())));
+        assert_eq!(console.next_event(), Ok(Event::Number('é' as src::VALUE)));
+        assert_eq!(console.next_event(), Ok(Event::Line("".to_owned())));
+        assert_eq!(console.next_event(), Ok(Event::Number(1000)));
+        assert_eq!(console.next_event(), Ok(Event::Halted));
+
+        // A partial line is flushed before input is requested
+        let mut console = Console::new(Interpreter::new(loader::parse("104,62,3,0,99").unwrap(), [].into()));
+        assert_eq!(console.next_event(), Ok(Event::Line(">".to_owned())));
+        assert_eq!(console.next_event(), Ok(Event::NeedsInput));
+
+        let mut console = Console::new(Interpreter::new(code.to_owned(), [].into()));
+        let mut transcript = vec![];
+        assert!(matches!(console.drive("hello\r\nunused\n".as_bytes(), &mut transcript, true), Ok(RunResult::Halted)));
+        assert_eq!(String::from_utf8(transcript).unwrap(), "Hi\nhello\nhello\n1000\n");
+
+        let mut console = Console::new(Interpreter::new(code, [].into()));
+        let mut transcript = vec![];
+        assert!(matches!(console.drive("".as_bytes(), &mut transcript, false), Ok(RunResult::NeedsInput)));
+        assert_eq!(String::from_utf8(transcript).unwrap(), "Hi\n");
+    }
+
+    #[test]
+    fn cli() {
+        // The exit code, stdout and stderr of a command
+        let output = |args: &[&str]| {
+            let (mut out, mut err) = (vec![], vec![]);
+            let code = cli::main(&args.iter().map(|x| x.to_string()).collect::<Vec<_>>(), &mut out, &mut err);
+            (code, String::from_utf8(out).unwrap(), String::from_utf8(err).unwrap())
+        };
+        let run = |args: &[&str]| output(args).0;
+
+        /// A program in a temporary file, which is deleted again on drop.
+        struct Program(String);
+        impl Drop for Program {
+            fn drop(&mut self) {
+                let _ = std::fs::remove_file(&self.0);
+            }
+        }
+        let program = |name: &str, code: &str| {
+            let path = std::env::temp_dir().join(format!("intcode-cli-{}-{}.txt", std::process::id(), name));
+            std::fs::write(&path, code).unwrap();
+            Program(path.to_str().unwrap().to_owned())
+        };
+
+        let (echo, looping) = (program("echo", "3,0,4,0,99"), program("loop", "1105,1,0"));
+        let echo = echo.0.as_str();
+        assert_eq!(output(&["run", echo, "--input", "5"]), (0, "5\n".to_owned(), "".to_owned()));
+        let (code, out, err) = output(&["run", echo, "--input", ""]);
+        assert_eq!((code, out.as_str()), (15, ""));
+        assert!(err.starts_with("error: No input\n --> ip 0: instruction 3 (input), parameter 0\n"));
+
+        let (code, out, err) = output(&["trace", echo, "--input", "5", "--json"]);
+        assert_eq!((code, out.as_str()), (0, "5\n"));
+        assert_eq!(err.lines().count(), 3);
+        assert!(err.starts_with(r#"{"ip":0,"opcode":3,"name":"input","param_indices":[0],"reads":[3],"write":{"address":0,"value":5},"relative_base":0}"#));
+        let (_, _, err) = output(&["trace", echo, "--input", "5"]);
+        assert!(err.ends_with("     4:     99 halt                 indices [] reads [] rb 0\n"));
+
+        assert_eq!(output(&["disasm", echo]).1.lines().nth(1), Some("     2: output               0                       ; 4 [pos]"));
+        assert!(output(&["cfg", echo]).1.contains("b0 [label=\"0: input 0\\l2: output 0\\l4: halt \\l\"];"));
+        assert_eq!(output(&["decompile", echo]).1, "void main() {\n    mem[0] = input();\n    output(mem[0]);\n    halt();\n}\n");
+        assert!(output(&["compile", echo]).1.contains("pub fn machine("));
+        assert!(output(&["help"]).1.starts_with("Usage: intcode <command> [options]"));
+        assert!(output(&["bench", echo, "--input", "5", "--runs", "2"]).1.starts_with("2 runs of 3 instructions\n"));
+        assert_eq!(run(&["bench", echo, "--input", "5", "--engine", "blocks"]), 0);
+        assert_eq!(run(&["run", &program("fault", "1101,1,1,-1").0]), 11);
+        assert_eq!(run(&["run", &program("opcode", "42").0]), 10);
+        let (code, _, err) = output(&["run", &program("invalid", "1,x,99").0]);
+        assert_eq!((code, err.contains("intcode-cli") && err.ends_with(": line 1, column 3: Invalid value `x`\n")), (2, true));
+        assert_eq!(run(&["run", &program("memory", "1101,1,1,50").0, "--memory", "fixed"]), 11);
+        assert_eq!(run(&["run", &looping.0, "--max-instructions", "1000"]), 16);
+        assert_eq!(run(&["run", &looping.0, "--max-instructions", "1000", "--engine", "blocks"]), 16);
+        assert_eq!(run(&["run", &looping.0, "--timeout", "10"]), 16);
+        let (code, _, err) = output(&["trace", &looping.0, "--max-instructions", "3"]);
+        assert_eq!((code, err.lines().filter(|line| line.starts_with("     0:   1105")).count()), (16, 3));
+        assert_eq!(run(&["run", echo, "--timeout", "soon"]), 1);
+
+        assert_eq!(output(&["day", "2", "a"]), (0, "Result / Value at position 0: 6087827\n".to_owned(), "".to_owned()));
+        assert_eq!(run(&["day", "3"]), 1);
+        assert_eq!(run(&["day", "2", "c"]), 1);
+        assert_eq!(run(&["run"]), 1);
+        assert_eq!(run(&["run", echo, "--bogus"]), 1);
+        assert_eq!(run(&["run", echo, "--memory", "tiny"]), 1);
+        assert_eq!(run(&["run", echo, "--engine", "jit"]), 1);
+        assert_eq!(run(&["frobnicate"]), 1);
+        assert_eq!(run(&["run", "data/missing.txt"]), 2);
+    }
+
+    #[test]
+    fn memory_access() {
+        let mut pc = Interpreter::with_memory(vec![1, 0, 0, 0, 99], [].into(), MemoryModel::Fixed);
+
+        pc.write(1, 4).unwrap();
+        assert_eq!(pc.slice(0..5), Ok(vec![1, 4, 0, 0, 99]));
+        assert_eq!(pc.step_loop(), Ok(RunResult::Halted));
+        assert_eq!(pc.read(0), Ok(100));
+        assert_eq!(pc.ip(), 4);
+        assert_eq!(pc.relative_base(), 0);
+
+        assert_eq!(pc.read(50).unwrap_err().kind, ErrorKind::OutOfMemory);
+        assert_eq!(pc.write(50, 1).unwrap_err().kind, ErrorKind::OutOfMemory);
+        assert_eq!(pc.slice(45..51).unwrap_err().kind, ErrorKind::OutOfMemory);
+
+        let mut pc = Interpreter::new(vec![99], [].into());
+        pc.write(50, 1).unwrap();
+        assert_eq!(pc.slice(49..52), Ok(vec![0, 1, 0]));
+    }
+
+    #[test]
+    fn memory_models() {
+        // Sets the relative base far beyond the code, writes 3 + 4 there and outputs it.
+        let code = loader::parse("109,1000000,21101,3,4,0,204,0,99").unwrap();
+
+        for model in [MemoryModel::Dense, MemoryModel::Sparse] {
+            let mut pc = Interpreter::with_memory(code.to_owned(), [].into(), model);
+            assert_eq!(pc.step_loop(), Ok(RunResult::Output(7)));
+            assert_eq!(pc.step_loop(), Ok(RunResult::Halted));
+        }
+
+        let mut pc = Interpreter::with_memory(code, [].into(), MemoryModel::Fixed);
+        assert_eq!(pc.step_loop().unwrap_err().kind, ErrorKind::InvalidParameters);
+
+        // Far beyond what a vector can hold, the dense model switches to pages
+        let code = loader::parse("109,4000000000000000000,21101,3,4,0,204,0,99").unwrap();
+        assert_eq!(execute(code.to_owned(), [].into()), Ok(vec![7]));
+        let mut pc = Interpreter::with_memory(code, [].into(), MemoryModel::Sparse);
+        assert_eq!(pc.step_loop(), Ok(RunResult::Output(7)));
+
+        // As it does for any write far beyond its end, while nearby writes grow it in place
+        let mut pc = Interpreter::new(vec![99; 100], [].into());
+        pc.write(150, 1).unwrap();
+        assert_eq!((pc.memory().model(), pc.memory().cells()), (MemoryModel::Dense, 151));
+        pc.write(memory::DENSE_GROWTH, 2).unwrap();
+        assert_eq!((pc.memory().model(), pc.memory().len()), (MemoryModel::Sparse, memory::DENSE_GROWTH + 1));
+        assert_eq!(pc.slice(149..152), Ok(vec![0, 1, 0]));
+        assert_eq!(pc.read(memory::DENSE_GROWTH), Ok(2));
+
+        let mut pc = Interpreter::new(vec![1101, 99, 99, 99, 99], [].into());
+        assert_eq!(pc.step(), Ok(None));
+
+        // Negative addresses are never valid
+        let mut pc = Interpreter::new(vec![1, -1, 0, 0, 99], [].into());
+        assert_eq!(pc.step().map_err(|e| summary(&e)), Err((ErrorKind::InvalidParameters, 0, 1, Some(0))));
     }
 
     #[test]
     fn errors() {
         let mut pc = Interpreter::new(vec![3, 0, 99], [].into());
 
-        let err = pc.step().unwrap_err();
+        let res = pc.step().unwrap();
 
-        assert_eq!(err, InterpreterError::NoInputError);
+        assert_eq!(res, Some(RunResult::NeedsInput));
 
         pc.input_buffer.push_back(-1);
 
@@ -227,9 +1285,15 @@ mod tests {
 
         assert_eq!(nothing, None);
 
-        let err = pc.step().unwrap_err();
+        let res = pc.step().unwrap();
+
+        assert_eq!(res, Some(RunResult::Halted));
+
+        // Halting is final
+        assert_eq!(pc.step_loop(), Ok(RunResult::Halted));
 
-        assert_eq!(err, InterpreterError::Terminated);
+        let err = execute(vec![3, 0, 3, 0, 99], vec![1].into()).unwrap_err();
+        assert_eq!(summary(&err), (ErrorKind::NoInput, 2, 3, Some(0)));
     }
 
 
@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::src::VALUE;
+
+/// Number of cells per page of the sparse memory model.
+const PAGE_SIZE: usize = 1024;
+/// Number of cells the dense memory model grows by at once, unless it at most doubles in size.
+/// Writes further out switch it to pages.
+pub const DENSE_GROWTH: usize = 1 << 16;
+
+/// Selects the memory backend of an `Interpreter` at construction.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum MemoryModel {
+    /// A `Vec` which grows on demand whenever a cell beyond its end is written. A write more
+    /// than `DENSE_GROWTH` cells and twice the length beyond its end switches the memory to the
+    /// pages of `Sparse`, which `Memory::model` reports from then on.
+    #[default]
+    Dense,
+    /// Pages of `PAGE_SIZE` cells, allocated on first write. Suited for programs which touch
+    /// a few cells at very large addresses. Clones share pages until either side writes to them.
+    Sparse,
+    /// The original model: the code padded with nine times its length of zeros. Any access
+    /// beyond that is an error.
+    Fixed,
+}
+
+/// The memory of an `Interpreter`. Every cell which has never been written reads as 0.
+#[derive(Clone, Debug)]
+pub enum Memory {
+    Dense(Vec<VALUE>),
+    Sparse {
+        pages: HashMap<usize, Arc<[VALUE; PAGE_SIZE]>>,
+        /// One past the highest address ever written.
+        len: usize,
+    },
+    Fixed(Vec<VALUE>),
+}
+
+impl Default for Memory {
+    fn default() -> Memory {
+        Memory::Dense(vec![])
+    }
+}
+
+impl Memory {
+    pub fn new(mut code: Vec<VALUE>, model: MemoryModel) -> Memory {
+        match model {
+            MemoryModel::Dense => Memory::Dense(code),
+            MemoryModel::Sparse => Memory::paged(&code),
+            MemoryModel::Fixed => {
+                code.extend(vec![0; 9*code.len()]); // The intcode specification does not specify
+                                                    // an exact size beyond "several times the
+                                                    // size of the starting memory"
+                Memory::Fixed(code)
+            },
+        }
+    }
+
+    /// A sparse memory holding `values` from address 0.
+    fn paged(values: &[VALUE]) -> Memory {
+        let mut memory = Memory::Sparse { pages: HashMap::new(), len: 0 };
+        for (address, &val) in values.iter().enumerate() {
+            memory.write(address, val);
+        }
+        memory
+    }
+
+    pub fn model(&self) -> MemoryModel {
+        match self {
+            Memory::Dense(_) => MemoryModel::Dense,
+            Memory::Sparse { .. } => MemoryModel::Sparse,
+            Memory::Fixed(_) => MemoryModel::Fixed,
+        }
+    }
+
+    /// One past the highest address in use. For the growable models this is the highest
+    /// address written so far, for the fixed model the size of the memory.
+    pub fn len(&self) -> usize {
+        match self {
+            Memory::Dense(code) | Memory::Fixed(code) => code.len(),
+            Memory::Sparse { len, .. } => *len,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of cells allocated, which for the sparse model is a multiple of the page size.
+    pub fn cells(&self) -> usize {
+        match self {
+            Memory::Dense(code) | Memory::Fixed(code) => code.len(),
+            Memory::Sparse { pages, .. } => pages.len() * PAGE_SIZE,
+        }
+    }
+
+    /// Number of cells allocated after writing to `address`.
+    pub fn cells_with(&self, address: usize) -> usize {
+        match self {
+            Memory::Dense(code) if grows_densely(code, address) => usize::max(code.len(), address + 1),
+            Memory::Dense(code) => code.len().div_ceil(PAGE_SIZE) * PAGE_SIZE + PAGE_SIZE,
+            Memory::Fixed(code) => code.len(),
+            Memory::Sparse { pages, .. } if pages.contains_key(&(address / PAGE_SIZE)) => self.cells(),
+            Memory::Sparse { .. } => self.cells() + PAGE_SIZE,
+        }
+    }
+
+    /// Whether `address` may be read and written. Only the fixed model has an upper bound.
+    #[inline]
+    pub fn contains(&self, address: usize) -> bool {
+        match self {
+            Memory::Fixed(code) => address < code.len(),
+            _ => true,
+        }
+    }
+
+    /// The value at `address`, or `None` if the address is not addressable.
+    #[inline]
+    pub fn get(&self, address: usize) -> Option<VALUE> {
+        match self {
+            Memory::Fixed(code) => code.get(address).copied(),
+            _ => Some(self.read(address)),
+        }
+    }
+
+    /// The value at `address`. Cells which are not addressable read as 0.
+    #[inline]
+    pub fn read(&self, address: usize) -> VALUE {
+        match self {
+            Memory::Dense(code) | Memory::Fixed(code) => code.get(address).copied().unwrap_or(0),
+            Memory::Sparse { pages, .. } => pages
+                .get(&(address / PAGE_SIZE))
+                .map_or(0, |page| page[address % PAGE_SIZE]),
+        }
+    }
+
+    /// The written parts of memory as pairs of start address and values, sorted by address.
+    /// Cells outside of these read as 0.
+    pub fn segments(&self) -> Vec<(usize, &[VALUE])> {
+        match self {
+            Memory::Dense(code) | Memory::Fixed(code) => vec![(0, &code[..])],
+            Memory::Sparse { pages, .. } => {
+                let mut segments: Vec<_> = pages
+                    .iter()
+                    .map(|(&page, values)| (page * PAGE_SIZE, &values[..]))
+                    .collect();
+                segments.sort_unstable_by_key(|&(address, _)| address);
+                segments
+            },
+        }
+    }
+
+    /// Restores a memory of the given model and `len` from `segments`.
+    pub fn from_segments(model: MemoryModel, len: usize, segments: &[(usize, Vec<VALUE>)]) -> Memory {
+        let mut memory = match model {
+            MemoryModel::Dense => Memory::Dense(vec![0; len]),
+            MemoryModel::Sparse => Memory::Sparse { pages: HashMap::new(), len },
+            MemoryModel::Fixed => Memory::Fixed(vec![0; len]),
+        };
+        for (start, values) in segments {
+            for (k, &val) in values.iter().enumerate() {
+                if val != 0 && memory.contains(start + k) {
+                    memory.write(start + k, val);
+                }
+            }
+        }
+        memory
+    }
+
+    /// Writes `val` to `address`, growing the memory if necessary.
+    ///
+    /// Panics if `address` is not addressable, check with `contains` first.
+    #[inline]
+    pub fn write(&mut self, address: usize, val: VALUE) {
+        match self {
+            Memory::Fixed(code) => code[address] = val,
+            Memory::Dense(code) if !grows_densely(code, address) => {
+                *self = Memory::paged(code);
+                self.write(address, val);
+            },
+            Memory::Dense(code) => {
+                if address >= code.len() {
+                    code.resize(address + 1, 0);
+                }
+                code[address] = val;
+            },
+            Memory::Sparse { pages, len } => {
+                let page = pages.entry(address / PAGE_SIZE).or_insert_with(|| Arc::new([0; PAGE_SIZE]));
+                Arc::make_mut(page)[address % PAGE_SIZE] = val;
+                *len = usize::max(*len, address.saturating_add(1));
+            },
+        }
+    }
+}
+
+/// Whether a dense memory holding `code` stays dense when `address` is written.
+#[inline]
+fn grows_densely(code: &[VALUE], address: usize) -> bool {
+    address < usize::max(2 * code.len(), DENSE_GROWTH)
+}
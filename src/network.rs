@@ -0,0 +1,148 @@
+//! Many machines exchanging packets, as in Advent of Code 2019 day 23.
+//!
+//! Every machine first reads its address. It sends a packet by outputting the destination
+//! address followed by the values `x` and `y`, and receives one by reading `x` and `y`. Reading
+//! from an empty queue yields -1.
+
+use crate::src::{ErrorKind, Interpreter, InterpreterError, RunResult, VALUE};
+
+/// Number of consecutive reads of an empty queue after which a machine counts as idle.
+const IDLE_READS: usize = 2;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Packet {
+    /// The address of the sender.
+    pub source: VALUE,
+    pub destination: VALUE,
+    pub x: VALUE,
+    pub y: VALUE,
+}
+
+/// Passed to the hook of `Network::run`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// A machine sent a packet. Packets to addresses without a machine are dropped after the
+    /// hook has seen them, eg. to let it act as a NAT.
+    Sent(Packet),
+    /// Every queue is empty, and every machine has been waiting for input.
+    Idle,
+}
+
+pub struct Network {
+    pub machines: Vec<Interpreter>,
+    /// The values of a packet each machine has output so far.
+    outbox: Vec<Vec<VALUE>>,
+    /// The number of consecutive reads of an empty queue, per machine.
+    idle_reads: Vec<usize>,
+}
+
+impl Network {
+    /// Starts `size` copies of `code`, with the addresses 0 to `size - 1`.
+    pub fn new(code: &[VALUE], size: usize) -> Network {
+        Network {
+            machines: (0..size).map(|address| Interpreter::new(code.to_owned(), vec![address as VALUE].into())).collect(),
+            outbox: vec![vec![]; size],
+            idle_reads: vec![0; size],
+        }
+    }
+
+    /// Queues `packet` at its destination. Returns false if there is no machine with that address.
+    pub fn send(&mut self, packet: Packet) -> bool {
+        let Some(k) = usize::try_from(packet.destination).ok().filter(|&k| k < self.machines.len()) else {
+            return false;
+        };
+        self.machines[k].input_buffer.extend([packet.x, packet.y]);
+        self.idle_reads[k] = 0;
+        true
+    }
+
+    /// Runs every machine in turn until it waits for input a second time, feeding it -1 the
+    /// first time its queue is empty. Returns all packets sent, in order, after delivering them.
+    pub fn step(&mut self) -> Result<Vec<Packet>, InterpreterError> {
+        let mut sent = vec![];
+        for k in 0..self.machines.len() {
+            let mut polled = false;
+            loop {
+                match self.machines[k].step_loop()? {
+                    RunResult::Output(val) => {
+                        self.idle_reads[k] = 0;
+                        self.outbox[k].push(val);
+                        if let [destination, x, y] = self.outbox[k][..] {
+                            self.outbox[k].clear();
+                            let packet = Packet { source: k as VALUE, destination, x, y };
+                            self.send(packet);
+                            sent.push(packet);
+                        }
+                    },
+                    RunResult::NeedsInput if !polled => {
+                        polled = true;
+                        self.idle_reads[k] += 1;
+                        self.machines[k].input_buffer.push_back(-1);
+                    },
+                    RunResult::NeedsInput | RunResult::Halted => break,
+                    RunResult::BudgetExhausted(limit) => return Err(self.machines[k].fault(ErrorKind::BudgetExhausted(limit), None)),
+                }
+            }
+        }
+        Ok(sent)
+    }
+
+    /// Whether all queues are empty and every running machine has been polling for a while.
+    pub fn is_idle(&self) -> bool {
+        self.machines
+            .iter()
+            .zip(&self.idle_reads)
+            .all(|(pc, &reads)| pc.finish || (pc.input_buffer.is_empty() && reads >= IDLE_READS))
+    }
+
+    /// Runs the network, passing every packet and every idle period to `hook`, until the hook
+    /// returns a result. Returns `None` if the network stays idle although the hook was told.
+    /// The hook may inject packets with `Network::send`.
+    pub fn run<T>(&mut self, mut hook: impl FnMut(&mut Network, Event) -> Option<T>) -> Result<Option<T>, InterpreterError> {
+        loop {
+            for packet in self.step()? {
+                if let Some(res) = hook(self, Event::Sent(packet)) {
+                    return Ok(Some(res));
+                }
+            }
+
+            if self.is_idle() {
+                if let Some(res) = hook(self, Event::Idle) {
+                    return Ok(Some(res));
+                }
+                if self.is_idle() {
+                    return Ok(None);
+                }
+            }
+        }
+    }
+}
+
+/// Keeps the last packet sent to its address, and forwards it to machine 0 whenever the
+/// network is idle.
+pub struct Nat {
+    pub address: VALUE,
+    pub last: Option<Packet>,
+}
+
+impl Nat {
+    pub fn new(address: VALUE) -> Nat {
+        Nat { address, last: None }
+    }
+
+    /// Handles an event of `Network::run`. Returns the packet forwarded to machine 0, if any.
+    pub fn handle(&mut self, network: &mut Network, event: Event) -> Option<Packet> {
+        match event {
+            Event::Sent(packet) if packet.destination == self.address => {
+                self.last = Some(packet);
+                None
+            },
+            Event::Idle => {
+                let packet = Packet { source: self.address, destination: 0, ..self.last? };
+                network.send(packet);
+                Some(packet)
+            },
+            Event::Sent(_) => None,
+        }
+    }
+}
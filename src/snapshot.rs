@@ -0,0 +1,187 @@
+//! Complete interpreter state, with a versioned text format to store it on disk.
+//!
+//! ```text
+//! intcode-snapshot 1
+//! model dense
+//! len 1000
+//! ip 12
+//! relative-base 0
+//! finish false
+//! last-output none
+//! input 1,2
+//! segment 0 3,21,1008,...
+//! ```
+//!
+//! Memory is stored as `segment <start address> <values>` lines, every cell outside of them
+//! is 0. Memory of the sparse model is stored as one segment per page.
+
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate::memory::{Memory, MemoryModel, DENSE_GROWTH};
+use crate::src::VALUE;
+
+/// Increased whenever the format changes incompatibly.
+pub const VERSION: u32 = 1;
+const MAGIC: &str = "intcode-snapshot";
+const FIELDS: [&str; 7] = ["model", "len", "ip", "relative-base", "finish", "last-output", "input"];
+
+/// Everything needed to resume an `Interpreter`, see `Interpreter::snapshot`.
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub memory: Memory,
+    pub ip: usize,
+    pub relative_base: isize,
+    pub finish: bool,
+    pub input_buffer: VecDeque<VALUE>,
+    pub last_output: Option<VALUE>,
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    /// The file is not a snapshot at all.
+    MissingHeader,
+    UnsupportedVersion(String),
+    /// A line which could not be parsed, with its (1-based) line number.
+    InvalidLine(usize, String),
+    MissingField(&'static str),
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::Io(e) => write!(f, "{}", e),
+            SnapshotError::MissingHeader => write!(f, "Not an intcode snapshot"),
+            SnapshotError::UnsupportedVersion(version) => write!(f, "Unsupported snapshot version {}, expected {}", version, VERSION),
+            SnapshotError::InvalidLine(line, s) => write!(f, "line {}: Invalid entry `{}`", line, s),
+            SnapshotError::MissingField(field) => write!(f, "Missing field `{}`", field),
+        }
+    }
+}
+
+impl Error for SnapshotError {}
+
+impl From<io::Error> for SnapshotError {
+    fn from(e: io::Error) -> SnapshotError {
+        SnapshotError::Io(e)
+    }
+}
+
+fn join(values: impl Iterator<Item = VALUE>) -> String {
+    values.map(|val| val.to_string()).collect::<Vec<_>>().join(",")
+}
+
+fn split(values: &str) -> Option<Vec<VALUE>> {
+    if values.is_empty() {
+        return Some(vec![]);
+    }
+    values.split(',').map(|val| val.parse().ok()).collect()
+}
+
+impl Snapshot {
+    pub fn write_to(&self, mut w: impl Write) -> io::Result<()> {
+        writeln!(w, "{} {}", MAGIC, VERSION)?;
+        writeln!(w, "model {}", match self.memory.model() {
+            MemoryModel::Dense => "dense",
+            MemoryModel::Sparse => "sparse",
+            MemoryModel::Fixed => "fixed",
+        })?;
+        writeln!(w, "len {}", self.memory.len())?;
+        writeln!(w, "ip {}", self.ip)?;
+        writeln!(w, "relative-base {}", self.relative_base)?;
+        writeln!(w, "finish {}", self.finish)?;
+        match self.last_output {
+            Some(val) => writeln!(w, "last-output {}", val)?,
+            None => writeln!(w, "last-output none")?,
+        }
+        writeln!(w, "input {}", join(self.input_buffer.iter().copied()))?;
+        for (start, values) in self.memory.segments() {
+            writeln!(w, "segment {} {}", start, join(values.iter().copied()))?;
+        }
+        w.flush()
+    }
+
+    pub fn read_from(r: impl BufRead) -> Result<Snapshot, SnapshotError> {
+        let mut lines = r.lines();
+
+        let header = lines.next().ok_or(SnapshotError::MissingHeader)??;
+        match header.split_once(' ') {
+            Some((MAGIC, version)) if version == VERSION.to_string() => {},
+            Some((MAGIC, version)) => return Err(SnapshotError::UnsupportedVersion(version.to_owned())),
+            _ => return Err(SnapshotError::MissingHeader),
+        }
+
+        // Field name to line number and value
+        let mut fields: HashMap<String, (usize, String)> = HashMap::new();
+        let mut segments = vec![];
+
+        for (k, line) in lines.enumerate() {
+            let line = line?;
+            let line_number = k + 2;
+            let invalid = || SnapshotError::InvalidLine(line_number, line.to_owned());
+            if line.is_empty() {
+                continue;
+            }
+
+            let (key, value) = line.split_once(' ').unwrap_or((&line, ""));
+            if key == "segment" {
+                let (start, values) = value.split_once(' ').unwrap_or((value, ""));
+                let start: usize = start.parse().map_err(|_| invalid())?;
+                let values = split(values).ok_or_else(invalid)?;
+                start.checked_add(values.len()).ok_or_else(invalid)?;
+                segments.push((start, values));
+            } else if FIELDS.contains(&key) {
+                fields.insert(key.to_owned(), (line_number, value.to_owned()));
+            } else {
+                return Err(invalid());
+            }
+        }
+
+        let field = |name: &'static str| fields.get(name).ok_or(SnapshotError::MissingField(name));
+        let invalid = |name: &'static str| match fields.get(name) {
+            Some((line_number, value)) => SnapshotError::InvalidLine(*line_number, format!("{} {}", name, value)),
+            None => SnapshotError::MissingField(name),
+        };
+        let parse = |name: &'static str| field(name)?.1.parse::<VALUE>().map_err(|_| invalid(name));
+
+        let model = match field("model")?.1.as_str() {
+            "dense" => MemoryModel::Dense,
+            "sparse" => MemoryModel::Sparse,
+            "fixed" => MemoryModel::Fixed,
+            _ => return Err(invalid("model")),
+        };
+
+        // The vector models have to hold every segment, yet allocate no more than the segments
+        // cover or the dense model grows by at once
+        let len = usize::try_from(parse("len")?).map_err(|_| invalid("len"))?;
+        let extent = segments.iter().map(|(start, values)| start + values.len()).max().unwrap_or(0);
+        if model != MemoryModel::Sparse && (len < extent || len > usize::max(extent, DENSE_GROWTH)) {
+            return Err(invalid("len"));
+        }
+
+        Ok(Snapshot {
+            memory: Memory::from_segments(model, len, &segments),
+            ip: usize::try_from(parse("ip")?).map_err(|_| invalid("ip"))?,
+            relative_base: isize::try_from(parse("relative-base")?).map_err(|_| invalid("relative-base"))?,
+            finish: field("finish")?.1.parse().map_err(|_| invalid("finish"))?,
+            input_buffer: split(&field("input")?.1).ok_or_else(|| invalid("input"))?.into(),
+            last_output: match field("last-output")?.1.as_str() {
+                "none" => None,
+                _ => Some(parse("last-output")?),
+            },
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write_to(BufWriter::new(File::create(path)?))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Snapshot, SnapshotError> {
+        Snapshot::read_from(BufReader::new(File::open(path)?))
+    }
+}
@@ -1,75 +1,230 @@
 use std::collections::VecDeque;
 use std::fmt::{Debug, Display};
+use std::ops::Range;
 use std::error::Error;
+use std::sync::Arc;
+
+use crate::blocks::{self, BlockCache, Engine, Flow, Op, Operand};
+use crate::budget::{Budget, Limit, Usage};
+use crate::io::{InputSource, OutputSink};
+use crate::memory::{self, Memory, MemoryModel};
+use crate::snapshot::Snapshot;
+use crate::trace::{TraceRecord, TraceSink};
 
 // TODO: Decide if want to keep this, or just make everything i64 from the get-go
+#[allow(clippy::upper_case_acronyms)]
 pub type VALUE = i64;
 
-#[derive(Copy, Clone, Debug, PartialEq)]
-pub enum InterpreterError {
-    Terminated,
-    NoInputError,
+/// Why `step_loop` returned control to the caller, in the absence of faults.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RunResult {
+    /// The program produced an output value.
+    Output(VALUE),
+    /// The program requests input, but the input buffer is empty. Once input has been
+    /// provided, the program can be resumed.
+    NeedsInput,
+    /// The program terminated normally.
+    Halted,
+    /// A limit of the budget has been reached before the next instruction. Once the limit
+    /// has been raised, the program can be resumed.
+    BudgetExhausted(Limit),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
     InvalidOpCode,
     InvalidParameters,
     Overflow,
     JumpOutOfBounds,
     OutOfMemory,
+    /// Never reported by `step`, which returns `RunResult::NeedsInput` instead. Reported by
+    /// `Interpreter::outputs` and drivers with a fixed input, once it ran out.
+    NoInput,
+    /// Never reported by `step`, which returns `RunResult::BudgetExhausted` instead. Reported
+    /// by `Interpreter::outputs` and drivers which do not resume the machine.
+    BudgetExhausted(Limit),
 }
 
-impl Display for InterpreterError {
-    // FIXME: Rewrite the fmt here.
+impl Display for ErrorKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match *self {
-            InterpreterError::Terminated => write!(f, "Terminated"),
-            InterpreterError::NoInputError => write!(f, "No input"),
-            InterpreterError::InvalidOpCode => write!(f, "Invalid OpCode"),
-            InterpreterError::Overflow => write!(f, "Overflow"),
-            InterpreterError::JumpOutOfBounds => write!(f, "Jump out of bounds"),
-            InterpreterError::OutOfMemory => write!(f, "Jump out of bounds"),
-            InterpreterError::InvalidParameters => write!(f, "Invalid parameters"),
+            ErrorKind::InvalidOpCode => write!(f, "Invalid OpCode"),
+            ErrorKind::InvalidParameters => write!(f, "Invalid parameters"),
+            ErrorKind::Overflow => write!(f, "Overflow"),
+            ErrorKind::JumpOutOfBounds => write!(f, "Jump out of bounds"),
+            ErrorKind::OutOfMemory => write!(f, "Out of memory"),
+            ErrorKind::NoInput => write!(f, "No input"),
+            ErrorKind::BudgetExhausted(limit) => write!(f, "Budget exhausted: {}", limit),
         }
     }
 }
 
-impl Error for InterpreterError {}
+/// Number of cells before `ip` in the memory excerpt of a fault.
+const EXCERPT_BEFORE: usize = 4;
+/// Number of cells starting at `ip` in the memory excerpt of a fault.
+const EXCERPT_AFTER: usize = 8;
+
+/// A fault of the program. The machine does not advance past the faulting instruction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InterpreterError {
+    pub kind: ErrorKind,
+    /// The instruction pointer of the faulting instruction.
+    pub ip: usize,
+    /// The raw instruction at `ip`, including its parameter modes.
+    pub opcode: VALUE,
+    /// The index of the offending parameter, if the fault is caused by a single one.
+    pub parameter: Option<usize>,
+    pub context: Box<FaultContext>,
+}
+
+/// The state of the machine at the time of a fault, as far as needed to diagnose it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FaultContext {
+    /// The name of the instruction, if the opcode decodes to one.
+    pub name: Option<&'static str>,
+    /// The parameter mode digits of the instruction, starting with the first parameter.
+    pub modes: Vec<u8>,
+    /// The address which could not be accessed or jumped to, if any.
+    pub address: Option<VALUE>,
+    /// The address of the first cell of `excerpt`.
+    pub excerpt_start: usize,
+    /// The memory around `ip` at the time of the fault.
+    pub excerpt: Vec<VALUE>,
+}
+
+impl InterpreterError {
+    /// Records the address which caused the fault.
+    pub fn at(mut self, address: VALUE) -> InterpreterError {
+        self.context.address = Some(address);
+        self
+    }
+}
+
+/// Formats the fault like a compiler error, eg.
+///
+/// ```text
+/// error: Invalid parameters
+///  --> ip 4: instruction 21101 (add), parameter 2
+///   |
+/// 0 | 1 0 0 2 21101 1 2 -60 99
+///   |         ^^^^^     ~~~
+///   = modes: [1, 1, 2]
+///   = address: -60
+/// ```
+impl Display for InterpreterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let FaultContext { name, modes, address, excerpt_start, excerpt } = &*self.context;
+        let gutter = " ".repeat((excerpt_start + excerpt.len()).to_string().len());
 
+        writeln!(f, "error: {}", self.kind)?;
+        write!(f, "{}--> ip {}: instruction {}", gutter, self.ip, self.opcode)?;
+        if let Some(name) = name {
+            write!(f, " ({})", name)?;
+        }
+        if let Some(parameter) = self.parameter {
+            write!(f, ", parameter {}", parameter)?;
+        }
+        writeln!(f)?;
 
+        if !excerpt.is_empty() {
+            let mut values = String::new();
+            let mut markers = String::new();
+            for (k, val) in excerpt.iter().enumerate() {
+                let val = val.to_string();
+                let address = excerpt_start + k;
+                let marker = if address == self.ip {
+                    '^'
+                } else if self.parameter.is_some_and(|p| address == self.ip + 1 + p) {
+                    '~'
+                } else {
+                    ' '
+                };
+                markers += &marker.to_string().repeat(val.len());
+                markers += " ";
+                values += &val;
+                values += " ";
+            }
+            writeln!(f, "{} |", gutter)?;
+            writeln!(f, "{:>width$} | {}", excerpt_start, values.trim_end(), width = gutter.len())?;
+            writeln!(f, "{} | {}", gutter, markers.trim_end())?;
+        }
 
-//#[derive(Debug)]
-pub struct Interpreter {
-    /// The underlying code of the program.
-    code: Vec<VALUE>,
+        write!(f, "{} = modes: {:?}", gutter, modes)?;
+        if let Some(address) = address {
+            write!(f, "\n{} = address: {}", gutter, address)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for InterpreterError {}
+
+/// An Intcode machine, reading input from `I` and writing output to `O`, see `crate::io`.
+#[derive(Default)]
+pub struct Interpreter<I = VecDeque<VALUE>, O = ()> {
+    /// The memory of the program, initially holding its code.
+    memory: Memory,
     /// The instruction pointer.
     ip: usize,
     /// Current relative base for relative base mode
     relative_base: isize,
-    /// The arguments for the current instruction.
-    param_indices: Vec<usize>,
+    /// The resolved addresses of the parameters of the current instruction, of which the
+    /// first `number_parameters` are in use.
+    param_indices: [usize; MAX_PARAMETERS],
+    number_parameters: usize,
     /// Indicates whether the program is finished.
     pub finish: bool,
-    /// Input buffer
-    pub input_buffer: VecDeque<VALUE>,
+    /// Input buffer, or any other source of input
+    pub input_buffer: I,
+    /// Receives every output value
+    pub output: O,
     /// The last valid output. 
     pub last_output: Option<VALUE>,
+    /// Receives a record of every executed instruction, if set.
+    tracer: Option<Box<dyn TraceSink + Send>>,
+    /// Limits of the execution, checked before every instruction.
+    budget: Budget,
+    /// What has been used of the budget.
+    usage: Usage,
+    /// The instructions understood by the machine, shared between clones.
+    instructions: Arc<InstructionSet<I, O>>,
+    /// The decoded blocks, if the machine runs on `Engine::Blocks`.
+    blocks: Option<BlockCache>,
 }
 
-impl Default for Interpreter {
-    fn default() -> Interpreter {
-        Interpreter { code: vec![], ip: 0, relative_base: 0, param_indices: vec![], finish: false, input_buffer: VecDeque::new(), last_output: None }
+/// Clones the complete state of the machine, except for the tracer.
+impl<I: Clone, O: Clone> Clone for Interpreter<I, O> {
+    fn clone(&self) -> Interpreter<I, O> {
+        Interpreter {
+            memory: self.memory.clone(),
+            ip: self.ip,
+            relative_base: self.relative_base,
+            param_indices: self.param_indices,
+            number_parameters: self.number_parameters,
+            finish: self.finish,
+            input_buffer: self.input_buffer.clone(),
+            output: self.output.clone(),
+            last_output: self.last_output,
+            tracer: None,
+            budget: self.budget,
+            usage: self.usage,
+            instructions: Arc::clone(&self.instructions),
+            blocks: self.blocks.clone(),
+        }
     }
 }
 
-impl Debug for Interpreter {
+impl<I: Debug, O> Debug for Interpreter<I, O> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut s: String = format!("IP: {}, Parameter Indices: {:?}, Input Buffer: {:?}, Last Output: {:?}\n", 
-                                    self.ip, self.param_indices, self.input_buffer, self.last_output);
+                                    self.ip, &self.param_indices[..self.number_parameters], self.input_buffer, self.last_output);
         
         let start = usize::max(usize::saturating_sub(self.ip, 12) , 0);
-        let end = usize::min(usize::saturating_add(self.ip, 12), self.code.len());
+        let end = usize::min(usize::saturating_add(self.ip, 12), self.memory.len());
 
-        let width = f64::log10(self.code[start..end]
-            .iter()
-            .fold(end as VALUE, |current, &x| VALUE::max(current, x)) as f64).floor() as usize + 1;
+        let width = f64::log10((start..end)
+            .map(|k| self.memory.read(k))
+            .fold(end as VALUE, VALUE::max) as f64).floor() as usize + 1;
         
 
         s += "[";
@@ -78,207 +233,793 @@ impl Debug for Interpreter {
         }
         s += "]\n[";
         for k in start..end {
-            s += &*format!("{:>width$},", self.code[k]);
+            s += &*format!("{:>width$},", self.memory.read(k));
         }
         s += "]\n";
         writeln!(f, "{}", s)
     }
 }
 
-impl Interpreter {
-    pub fn step(&mut self) -> Result<Option<VALUE>, InterpreterError> {
+impl<I: InputSource, O: OutputSink> Interpreter<I, O> {
+    /// Executes a single instruction. Returns `Ok(None)` if the program simply continues.
+    pub fn step(&mut self) -> Result<Option<RunResult>, InterpreterError> {
         if self.finish {
-            return Err(InterpreterError::Terminated);
+            return Ok(Some(RunResult::Halted));
         }
         self.last_output = None;
+        if let Some(limit) = self.budget.exhausted(&self.usage, self.memory.read(self.ip) % 100 == 4) {
+            return Ok(Some(RunResult::BudgetExhausted(limit)));
+        }
 
-        let next_code = match self.code.get(self.ip) {
-            None => return Err(InterpreterError::OutOfMemory),
+        let next_code = match self.memory.get(self.ip) {
+            None => return Err(self.fault(ErrorKind::OutOfMemory, None).at(self.ip as VALUE)),
             Some(val) => val,
         };
 
-        // Ensures that an opcode 0 is an error, distinguishes from 99
-        if next_code % 100 == 0 {
-            return Err(InterpreterError::InvalidOpCode);
+        let (instruction, custom) = match self.instructions.entry(next_code) {
+            None => return Err(self.fault(ErrorKind::InvalidOpCode, None)),
+            Some((instruction, Handler::Builtin)) => (*instruction, None),
+            Some((instruction, Handler::Custom(func))) => (*instruction, Some(Arc::clone(func))),
+        };
+        let Instruction { opcode, name, number_parameters, writes } = instruction;
+
+        // The first invalid parameter, and the address it refers to if there is one
+        let mut wrong_parameter = None;
+        let mut modes = next_code / 100;
+        let mut write_mode = 0;
+
+        for k in 0..number_parameters {
+            let mode = modes % 10;
+            modes /= 10;
+            if writes == Some(k) {
+                write_mode = mode;
+            }
+
+            let operand = self.memory.get(self.ip + 1 + k);
+            let address = match (mode, operand) {
+                (0, Some(operand)) => { Some(operand) },
+                (1, _) => { Some((self.ip + 1 + k) as VALUE) },
+                (2, Some(operand)) => { operand.checked_add(self.relative_base as VALUE) },
+                _ => { None }
+            };
+            self.param_indices[k] = match address {
+                Some(address) if address >= 0 => { address as usize },
+                _ => { wrong_parameter = wrong_parameter.or(Some((k, address))); 0 }
+            };
         }
+        self.number_parameters = number_parameters;
 
-        let next_instruction = match OPCODES.get(((next_code % 100) % 99) as usize) {
-            None => return Err(InterpreterError::InvalidOpCode),
-            Some(val) => val,
+        if let Some((k, address)) = wrong_parameter {
+            let fault = self.fault(ErrorKind::InvalidParameters, Some(k));
+            return Err(match address {
+                Some(address) => fault.at(address),
+                None => fault,
+            });
+        }
+
+        // Mode digits beyond the last parameter
+        if modes != 0 {
+            return Err(self.fault(ErrorKind::InvalidParameters, None));
+        }
+
+        // immediate mode, never to be used for writing
+        if write_mode == 1 {
+            return Err(self.fault(ErrorKind::InvalidParameters, writes));
+        }
+
+        if let Some(k) = self.params().iter().position(|&x| !self.memory.contains(x)) {
+            return Err(self.fault(ErrorKind::InvalidParameters, Some(k)).at(self.param_indices[k] as VALUE));
+        }
+
+        if let (Some(k), Some(max)) = (writes, self.budget.memory) {
+            let cells = self.memory.cells_with(self.param_indices[k]);
+            if cells > max && cells > self.memory.cells() {
+                return Ok(Some(RunResult::BudgetExhausted(Limit::Memory)));
+            }
+        }
+
+        let mut record = self.tracer.as_ref().map(|_| TraceRecord {
+            ip: self.ip,
+            opcode: next_code,
+            name,
+            param_indices: self.params().to_owned(),
+            reads: self.params().iter().map(|&x| self.memory.read(x)).collect(),
+            write: None,
+            relative_base: self.relative_base,
+        });
+
+        let res = match custom {
+            None => match opcode {
+                1 => op_add(self),
+                2 => op_mul(self),
+                3 => op_in(self),
+                4 => op_out(self),
+                5 => op_jit(self),
+                6 => op_jif(self),
+                7 => op_lt(self),
+                8 => op_eq(self),
+                9 => op_relb(self),
+                _ => op_halt(self),
+            },
+            Some(func) => func(self),
         };
+        if let Ok(Some(RunResult::Output(val))) = res {
+            self.last_output = Some(val);
+            self.usage.outputs += 1;
+            self.output.emit(val);
+        }
+        if let Ok(None | Some(RunResult::Output(_) | RunResult::Halted)) = res {
+            self.usage.instructions += 1;
+        }
 
-        let mut wrong_parameters = false;
+        if let (Some(tracer), Some(record)) = (self.tracer.as_mut(), record.as_mut()) {
+            if matches!(res, Ok(None | Some(RunResult::Output(_) | RunResult::Halted))) {
+                record.write = writes.map(|k| (self.param_indices[k], self.memory.read(self.param_indices[k])));
+                tracer.record(record);
+            }
+        }
 
-        self.param_indices = (0..next_instruction.number_parameters)
-            .map(|k| match (next_code / 10i64.pow(2+k as u32)) % 10 {
-                0 => { self.code[self.ip + 1 + k] as usize },
-                1 => { self.ip + 1 + k },
-                2 => { (self.relative_base + self.code[self.ip + 1 + k] as isize) as usize },
-                _ => { wrong_parameters = true; 0 }
-            })
-            .collect();
+        res
+    }
 
-        if wrong_parameters {
-            return Err(InterpreterError::InvalidParameters);
+    /// Steps through the program until it produces output, needs input, or halts.
+    pub fn step_loop(&mut self) -> Result<RunResult, InterpreterError> { 
+        loop {
+            if let Some(res) = self.run_blocks() {
+                return Ok(res);
+            }
+            if let Some(res) = self.step()? {
+                return Ok(res);
+            }
         }
+    }
 
-        if next_code / 10i64.pow(2+next_instruction.number_parameters as u32) != 0 {
-            return Err(InterpreterError::InvalidParameters);
+    /// Executes cached blocks until an instruction returns a result, or has to be left to
+    /// `step`. Returns `None` right away unless the machine runs on `Engine::Blocks`.
+    fn run_blocks(&mut self) -> Option<RunResult> {
+        if self.finish || self.tracer.is_some() {
+            return None;
         }
+        self.last_output = None;
+        loop {
+            let cache = self.blocks.as_mut()?;
+            let block = match cache.get(self.ip) {
+                Some(block) => Arc::clone(block),
+                None => {
+                    let limit = self.memory.cells().max(memory::DENSE_GROWTH);
+                    cache.insert(self.ip, blocks::decode(&self.memory, &self.instructions, self.ip), limit)?
+                },
+            };
+            let generation = cache.generation();
 
-        // immediate mode, never to be used for writing
-        if next_code / 10i64.pow(4) == 1 {
-            return Err(InterpreterError::InvalidParameters);
+            for op in &block.ops {
+                if let Some(limit) = self.budget.exhausted(&self.usage, op.opcode == 4) {
+                    return Some(RunResult::BudgetExhausted(limit));
+                }
+                match self.execute(op) {
+                    Flow::Continue => {},
+                    Flow::Stop(res) => return Some(res),
+                    Flow::Step => return None,
+                }
+                // A write into the block itself evicted it
+                if self.blocks.as_ref().is_some_and(|cache| cache.generation() != generation) {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Executes the cached instruction at `ip` like `step`, unless it would fault or exceed the
+    /// memory budget.
+    #[inline]
+    fn execute(&mut self, op: &Op) -> Flow {
+        let [a, b, c] = op.operands;
+        match op.opcode {
+            1 | 2 | 7 | 8 => {
+                let (Some(x), Some(y), Some(target)) = (self.load(a), self.load(b), self.writable(c)) else {
+                    return Flow::Step;
+                };
+                let val = match op.opcode {
+                    1 => x.checked_add(y),
+                    2 => x.checked_mul(y),
+                    7 => Some((x < y) as VALUE),
+                    _ => Some((x == y) as VALUE),
+                };
+                let Some(val) = val else {
+                    return Flow::Step;
+                };
+                self.store(target, val);
+            },
+            3 => {
+                let Some(target) = self.writable(a) else {
+                    return Flow::Step;
+                };
+                let Some(val) = self.input_buffer.next_input() else {
+                    return Flow::Stop(RunResult::NeedsInput);
+                };
+                self.store(target, val);
+            },
+            4 => {
+                let Some(val) = self.load(a) else {
+                    return Flow::Step;
+                };
+                self.ip += op.size;
+                self.usage.instructions += 1;
+                self.last_output = Some(val);
+                self.usage.outputs += 1;
+                self.output.emit(val);
+                return Flow::Stop(RunResult::Output(val));
+            },
+            5 | 6 => {
+                let (Some(condition), Some(target)) = (self.load(a), self.load(b)) else {
+                    return Flow::Step;
+                };
+                if (condition != 0) == (op.opcode == 5) {
+                    if target < 0 {
+                        return Flow::Step;
+                    }
+                    self.ip = target as usize;
+                    self.usage.instructions += 1;
+                    return Flow::Continue;
+                }
+            },
+            9 => {
+                // On overflow the step engine reports the fault
+                let Some(offset) = self.load(a) else {
+                    return Flow::Step;
+                };
+                if self.adjust_relative_base(offset).is_err() {
+                    return Flow::Step;
+                }
+            },
+            _ => {
+                self.finish = true;
+                self.usage.instructions += 1;
+                return Flow::Stop(RunResult::Halted);
+            },
         }
+        self.ip += op.size;
+        self.usage.instructions += 1;
+        Flow::Continue
+    }
 
-        if self.param_indices.iter().any(|x| !(0..self.code.len()).contains(x)) {
-            return Err(InterpreterError::InvalidParameters);
+    /// The address of a position or relative operand, if it is addressable.
+    #[inline]
+    fn resolve(&self, operand: Operand) -> Option<usize> {
+        let address = match operand {
+            Operand::Position(address) => address,
+            Operand::Relative(offset) => usize::try_from(offset.checked_add(self.relative_base as VALUE)?).ok()?,
+            Operand::Immediate(_) => return None,
+        };
+        self.memory.contains(address).then_some(address)
+    }
+
+    #[inline]
+    fn load(&self, operand: Operand) -> Option<VALUE> {
+        match operand {
+            Operand::Immediate(val) => Some(val),
+            operand => Some(self.memory.read(self.resolve(operand)?)),
         }
+    }
 
+    /// The address an operand writes to, if the write stays within the memory budget.
+    #[inline]
+    fn writable(&self, operand: Operand) -> Option<usize> {
+        let address = self.resolve(operand)?;
+        if let Some(max) = self.budget.memory {
+            let cells = self.memory.cells_with(address);
+            if cells > max && cells > self.memory.cells() {
+                return None;
+            }
+        }
+        Some(address)
+    }
 
-        if let Some(e) = (next_instruction.func)(self) {
-            Err(e)
-        } else {
-            Ok(self.last_output)
+    /// Writes to memory, evicting the cached blocks containing `address`.
+    #[inline]
+    fn store(&mut self, address: usize, val: VALUE) {
+        self.memory.write(address, val);
+        if let Some(cache) = self.blocks.as_mut() {
+            cache.invalidate(address);
         }
     }
 
-    pub fn step_loop(&mut self) -> Result<VALUE, InterpreterError> { 
+    /// Runs the program until it needs input, halts or exhausts its budget. Output only goes
+    /// to the sink.
+    pub fn run(&mut self) -> Result<RunResult, InterpreterError> {
         loop {
-            let res = self.step();
-            match res {
-                Err(e) => { return Err(e); },
-                Ok(Some(val)) => { return Ok(val); },
-                Ok(None) => {},
+            match self.step_loop()? {
+                RunResult::Output(_) => {},
+                res => return Ok(res),
             }
         }
     }
 
-    pub fn new<'a>(mut code: Vec<VALUE>, input_buffer: VecDeque<VALUE>) -> Interpreter {
-        code.extend(vec![0i64; 9*code.len()]); // Ensure starting memory is large enough. The
-                                               // intcode specification does not specify an exact
-                                               // size beyond "several times the size of the
-                                               // starting memory"
+    /// Lazily runs the program, yielding its outputs until it halts. A fault ends the iterator,
+    /// as do missing input and an exhausted budget, which are reported as `ErrorKind::NoInput`
+    /// and `ErrorKind::BudgetExhausted`.
+    pub fn outputs(&mut self) -> Outputs<'_, I, O> {
+        Outputs { pc: self, done: false }
+    }
+
+    /// A fault of the current instruction, with the machine context needed to diagnose it.
+    pub fn fault(&self, kind: ErrorKind, parameter: Option<usize>) -> InterpreterError {
+        let opcode = self.memory.read(self.ip);
+        let instruction = self.instructions.get(opcode);
+
+        let mut modes = vec![];
+        let mut digits = opcode / 100;
+        while digits > 0 || modes.len() < instruction.map_or(0, |x| x.number_parameters) {
+            modes.push((digits % 10) as u8);
+            digits /= 10;
+        }
+
+        let excerpt_start = self.ip.saturating_sub(EXCERPT_BEFORE);
+        let excerpt = (excerpt_start..usize::min(self.ip + EXCERPT_AFTER, self.memory.len()))
+            .map_while(|k| self.memory.get(k))
+            .collect();
+
+        InterpreterError {
+            kind,
+            ip: self.ip,
+            opcode,
+            parameter,
+            context: Box::new(FaultContext {
+                name: instruction.map(|x| x.name),
+                modes,
+                address: None,
+                excerpt_start,
+                excerpt,
+            }),
+        }
+    }
+
+    /// Moves the relative base by `offset`. Fails with `Overflow` if it leaves the range of `isize`.
+    fn adjust_relative_base(&mut self, offset: VALUE) -> Result<(), InterpreterError> {
+        self.relative_base = (self.relative_base as VALUE).checked_add(offset)
+            .and_then(|base| isize::try_from(base).ok())
+            .ok_or_else(|| self.fault(ErrorKind::Overflow, None))?;
+        Ok(())
+    }
+
+    /// The value at `address`. Fails with `OutOfMemory` if the address is not addressable.
+    pub fn read(&self, address: usize) -> Result<VALUE, InterpreterError> {
+        self.memory.get(address).ok_or_else(|| self.fault(ErrorKind::OutOfMemory, None).at(address as VALUE))
+    }
+
+    /// Overwrites the value at `address`, eg. to patch the program before running it.
+    pub fn write(&mut self, address: usize, val: VALUE) -> Result<(), InterpreterError> {
+        if !self.memory.contains(address) {
+            return Err(self.fault(ErrorKind::OutOfMemory, None).at(address as VALUE));
+        }
+        self.store(address, val);
+        Ok(())
+    }
+
+    /// Copies the values in `range` out of memory. Fails if any address is not addressable.
+    pub fn slice(&self, range: Range<usize>) -> Result<Vec<VALUE>, InterpreterError> {
+        range.map(|address| self.read(address)).collect()
+    }
+
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    pub fn ip(&self) -> usize {
+        self.ip
+    }
+
+    pub fn relative_base(&self) -> isize {
+        self.relative_base
+    }
+
+    /// The resolved address of parameter `k` of the current instruction, for use by handlers.
+    ///
+    /// Panics if the instruction has fewer parameters.
+    pub fn parameter(&self, k: usize) -> usize {
+        self.params()[k]
+    }
+
+    fn params(&self) -> &[usize] {
+        &self.param_indices[..self.number_parameters]
+    }
+
+    /// Moves the instruction pointer past the current instruction. Handlers call this unless
+    /// they jump, or wait for input.
+    pub fn advance(&mut self) {
+        self.ip += 1 + self.number_parameters;
+    }
+
+    /// Moves the instruction pointer to `target`, eg. to implement a jump in a handler.
+    pub fn set_ip(&mut self, target: usize) {
+        self.ip = target;
+    }
+
+    pub fn budget(&self) -> &Budget {
+        &self.budget
+    }
+
+    /// Replaces the limits of the machine, eg. to raise them and resume it.
+    pub fn set_budget(&mut self, budget: Budget) {
+        self.budget = budget;
+    }
+
+    pub fn usage(&self) -> &Usage {
+        &self.usage
+    }
+
+    /// Starts recording every executed instruction to `tracer`, or stops tracing if `None`.
+    pub fn set_tracer(&mut self, tracer: Option<Box<dyn TraceSink + Send>>) {
+        self.tracer = tracer;
+    }
+
+    pub fn instructions(&self) -> &InstructionSet<I, O> {
+        &self.instructions
+    }
+
+    /// Replaces the instructions understood by the machine, see `InstructionSet::register`.
+    pub fn set_instructions(&mut self, instructions: InstructionSet<I, O>) {
+        self.instructions = Arc::new(instructions);
+        if let Some(cache) = self.blocks.as_mut() {
+            cache.clear();
+        }
+    }
+
+    pub fn engine(&self) -> Engine {
+        if self.blocks.is_some() { Engine::Blocks } else { Engine::Step }
+    }
+
+    /// Selects the engine executing the program, at construction, eg.
+    /// `Interpreter::new(code, input).with_engine(Engine::Blocks)`.
+    pub fn with_engine(mut self, engine: Engine) -> Interpreter<I, O> {
+        self.blocks = match engine {
+            Engine::Step => None,
+            Engine::Blocks => Some(BlockCache::default()),
+        };
+        self
+    }
+
+    /// Creates an interpreter which reads from `input` and writes to `output`.
+    pub fn with_io(code: Vec<VALUE>, model: MemoryModel, input: I, output: O) -> Interpreter<I, O> {
         Interpreter {
-            code,
+            memory: Memory::new(code, model),
             ip: 0,
             relative_base: 0,
-            param_indices: vec![],
+            param_indices: [0; MAX_PARAMETERS],
+            number_parameters: 0,
             finish: false,
-            input_buffer,
+            input_buffer: input,
+            output,
             last_output: None,
+            tracer: None,
+            budget: Budget::default(),
+            usage: Usage::default(),
+            instructions: Arc::default(),
+            blocks: None,
         }
     }
 }
 
-// Warning: Quasi-jump table, Order of items important. 
-const OPCODES: [Instruction; 10] = [
-    Instruction { name: "halt", opcode: 99, func: op_halt, number_parameters: 0 },
-    Instruction { name: "add", opcode: 1, func: op_add, number_parameters: 3 },
-    Instruction { name: "multiply", opcode: 2, func: op_mul, number_parameters: 3 },
-    Instruction { name: "input", opcode: 3, func: op_in, number_parameters: 1 },
-    Instruction { name: "output", opcode: 4, func: op_out, number_parameters: 1 },
-    Instruction { name: "jump-if-true", opcode: 5, func: op_jit, number_parameters: 2 },
-    Instruction { name: "jump-if-false", opcode: 6, func: op_jif, number_parameters: 2 },
-    Instruction { name: "less than", opcode: 7, func: op_lt, number_parameters: 3 },
-    Instruction { name: "equals", opcode: 8, func: op_eq, number_parameters: 3 },
-    Instruction { name: "relative base offset", opcode: 9, func: op_relb, number_parameters: 1 },
+impl Interpreter {
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            memory: self.memory.clone(),
+            ip: self.ip,
+            relative_base: self.relative_base,
+            finish: self.finish,
+            input_buffer: self.input_buffer.clone(),
+            last_output: self.last_output,
+        }
+    }
+
+    pub fn from_snapshot(snapshot: Snapshot) -> Interpreter {
+        Interpreter {
+            memory: snapshot.memory,
+            ip: snapshot.ip,
+            relative_base: snapshot.relative_base,
+            param_indices: [0; MAX_PARAMETERS],
+            number_parameters: 0,
+            finish: snapshot.finish,
+            input_buffer: snapshot.input_buffer,
+            output: (),
+            last_output: snapshot.last_output,
+            tracer: None,
+            budget: Budget::default(),
+            usage: Usage::default(),
+            instructions: Arc::default(),
+            blocks: None,
+        }
+    }
+
+    /// Creates an interpreter with growable dense memory.
+    pub fn new(code: Vec<VALUE>, input_buffer: VecDeque<VALUE>) -> Interpreter {
+        Interpreter::with_memory(code, input_buffer, MemoryModel::Dense)
+    }
+
+    pub fn with_memory(code: Vec<VALUE>, input_buffer: VecDeque<VALUE>, model: MemoryModel) -> Interpreter {
+        Interpreter::with_io(code, model, input_buffer, ())
+    }
+}
+
+pub struct Outputs<'a, I, O> {
+    pc: &'a mut Interpreter<I, O>,
+    done: bool,
+}
+
+impl<I: InputSource, O: OutputSink> Iterator for Outputs<'_, I, O> {
+    type Item = Result<VALUE, InterpreterError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.pc.step_loop() {
+            Ok(RunResult::Output(val)) => Some(Ok(val)),
+            Ok(RunResult::Halted) => { self.done = true; None },
+            Ok(RunResult::NeedsInput) => { self.done = true; Some(Err(self.pc.fault(ErrorKind::NoInput, Some(0)))) },
+            Ok(RunResult::BudgetExhausted(limit)) => { self.done = true; Some(Err(self.pc.fault(ErrorKind::BudgetExhausted(limit), None))) },
+            Err(e) => { self.done = true; Some(Err(e)) },
+        }
+    }
+}
+
+/// The standard instruction set, the default of every `Interpreter`.
+pub const OPCODES: [Instruction; 10] = [
+    Instruction { name: "halt", opcode: 99, number_parameters: 0, writes: None },
+    Instruction { name: "add", opcode: 1, number_parameters: 3, writes: Some(2) },
+    Instruction { name: "multiply", opcode: 2, number_parameters: 3, writes: Some(2) },
+    Instruction { name: "input", opcode: 3, number_parameters: 1, writes: Some(0) },
+    Instruction { name: "output", opcode: 4, number_parameters: 1, writes: None },
+    Instruction { name: "jump-if-true", opcode: 5, number_parameters: 2, writes: None },
+    Instruction { name: "jump-if-false", opcode: 6, number_parameters: 2, writes: None },
+    Instruction { name: "less than", opcode: 7, number_parameters: 3, writes: Some(2) },
+    Instruction { name: "equals", opcode: 8, number_parameters: 3, writes: Some(2) },
+    Instruction { name: "relative base offset", opcode: 9, number_parameters: 1, writes: None },
 ];
 
+/// The most parameters an instruction may have, such that all mode digits fit into a `VALUE`.
+pub const MAX_PARAMETERS: usize = 16;
+
+/// Executes an instruction. Returns `Ok(None)` if the program simply continues.
+type HandlerFn<I, O> = dyn Fn(&mut Interpreter<I, O>) -> Result<Option<RunResult>, InterpreterError> + Send + Sync;
+
+enum Handler<I, O> {
+    /// One of the standard instructions, dispatched by opcode.
+    Builtin,
+    Custom(Arc<HandlerFn<I, O>>),
+}
+
+impl<I, O> Clone for Handler<I, O> {
+    fn clone(&self) -> Handler<I, O> {
+        match self {
+            Handler::Builtin => Handler::Builtin,
+            Handler::Custom(func) => Handler::Custom(Arc::clone(func)),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Instruction {
-    opcode: u8,
-    name: &'static str,
-    func: fn(&mut Interpreter) -> Option<InterpreterError>,
-    number_parameters: usize,
+    pub opcode: u8,
+    pub name: &'static str,
+    pub number_parameters: usize,
+    /// The parameter the instruction writes to, which must never be in immediate mode.
+    pub writes: Option<usize>,
+}
+
+impl Instruction {
+    /// The name as used by the assembler, eg. `less-than`.
+    pub fn mnemonic(&self) -> String {
+        self.name.replace(' ', "-")
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RegisterError {
+    /// Opcodes are between 1 and 99, 0 is always invalid.
+    InvalidOpcode(u8),
+    /// The opcode is already taken, remove it first to replace it.
+    Occupied(u8),
+    TooManyParameters(usize),
+    /// The written parameter is not one of the parameters of the instruction.
+    InvalidWrite(usize),
+}
+
+impl Display for RegisterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RegisterError::InvalidOpcode(opcode) => write!(f, "Invalid opcode {}, expected 1 to 99", opcode),
+            RegisterError::Occupied(opcode) => write!(f, "Opcode {} is already registered", opcode),
+            RegisterError::TooManyParameters(n) => write!(f, "{} parameters, at most {} are supported", n, MAX_PARAMETERS),
+            RegisterError::InvalidWrite(k) => write!(f, "Parameter {} written, but the instruction has fewer parameters", k),
+        }
+    }
+}
+
+impl Error for RegisterError {}
+
+/// The instructions understood by an `Interpreter`, indexed by opcode. Defaults to `OPCODES`.
+///
+/// Further instructions can be registered with a handler closure, eg. to implement an Intcode
+/// dialect or host calls:
+///
+/// ```ignore
+/// let mut instructions = InstructionSet::default();
+/// // 42 a: Prints the value of a
+/// instructions.register(42, "print", 1, None, |pc| {
+///     println!("{}", pc.read(pc.parameter(0))?);
+///     pc.advance();
+///     Ok(None)
+/// })?;
+/// pc.set_instructions(instructions);
+/// ```
+pub struct InstructionSet<I = VecDeque<VALUE>, O = ()> {
+    table: Vec<Option<(Instruction, Handler<I, O>)>>,
+}
+
+impl<I, O> Clone for InstructionSet<I, O> {
+    fn clone(&self) -> InstructionSet<I, O> {
+        InstructionSet { table: self.table.clone() }
+    }
+}
+
+impl<I, O> Default for InstructionSet<I, O> {
+    fn default() -> InstructionSet<I, O> {
+        let mut set = InstructionSet::empty();
+        for instruction in OPCODES {
+            set.table[instruction.opcode as usize] = Some((instruction, Handler::Builtin));
+        }
+        set
+    }
+}
+
+impl<I, O> InstructionSet<I, O> {
+    /// An instruction set without any instructions, not even `halt`.
+    pub fn empty() -> InstructionSet<I, O> {
+        InstructionSet { table: (0..100).map(|_| None).collect() }
+    }
+
+    /// The instruction for the raw instruction `word`, ignoring its parameter modes.
+    pub fn get(&self, word: VALUE) -> Option<&Instruction> {
+        self.entry(word).map(|(instruction, _)| instruction)
+    }
+
+    /// The instruction for `word`, if it is one of the standard instructions, executed by the
+    /// machine itself rather than a handler.
+    pub fn builtin(&self, word: VALUE) -> Option<&Instruction> {
+        match self.entry(word) {
+            Some((instruction, Handler::Builtin)) => Some(instruction),
+            _ => None,
+        }
+    }
+
+    fn entry(&self, word: VALUE) -> Option<&(Instruction, Handler<I, O>)> {
+        if word < 0 {
+            return None;
+        }
+        self.table[(word % 100) as usize].as_ref()
+    }
+
+    /// Adds an instruction with `number_parameters` parameters, executed by `handler`.
+    ///
+    /// The handler resolves its parameters with `Interpreter::parameter`, and has to move the
+    /// instruction pointer itself, usually with `Interpreter::advance`. The parameter `writes`,
+    /// if any, is checked to never be in immediate mode.
+    pub fn register<F>(&mut self, opcode: u8, name: &'static str, number_parameters: usize, writes: Option<usize>, handler: F) -> Result<(), RegisterError>
+    where
+        F: Fn(&mut Interpreter<I, O>) -> Result<Option<RunResult>, InterpreterError> + Send + Sync + 'static,
+    {
+        if opcode == 0 || opcode > 99 {
+            return Err(RegisterError::InvalidOpcode(opcode));
+        }
+        if self.table[opcode as usize].is_some() {
+            return Err(RegisterError::Occupied(opcode));
+        }
+        if number_parameters > MAX_PARAMETERS {
+            return Err(RegisterError::TooManyParameters(number_parameters));
+        }
+        if let Some(k) = writes.filter(|&k| k >= number_parameters) {
+            return Err(RegisterError::InvalidWrite(k));
+        }
+
+        let instruction = Instruction { opcode, name, number_parameters, writes };
+        self.table[opcode as usize] = Some((instruction, Handler::Custom(Arc::new(handler))));
+        Ok(())
+    }
+
+    /// Removes the instruction with `opcode`, eg. to replace a standard instruction.
+    pub fn remove(&mut self, opcode: u8) -> Option<Instruction> {
+        self.table.get_mut(opcode as usize)?.take().map(|(instruction, _)| instruction)
+    }
+
+    /// The registered instructions, ordered by opcode.
+    pub fn iter(&self) -> impl Iterator<Item = &Instruction> {
+        self.table.iter().flatten().map(|(instruction, _)| instruction)
+    }
 }
 
-fn op_halt(pc: &mut Interpreter) -> Option<InterpreterError> {
+fn op_halt<I: InputSource, O: OutputSink>(pc: &mut Interpreter<I, O>) -> Result<Option<RunResult>, InterpreterError> {
     pc.finish = true;
-    pc.ip += 0;
-    Some(InterpreterError::Terminated)
+    Ok(Some(RunResult::Halted))
 }
 
-fn op_add(pc: &mut Interpreter) -> Option<InterpreterError> {
-    if let Some(val) = pc.code[pc.param_indices[0] as usize].checked_add(pc.code[pc.param_indices[1] as usize]) {
-        pc.code[pc.param_indices[2] as usize] = val;
+fn op_add<I: InputSource, O: OutputSink>(pc: &mut Interpreter<I, O>) -> Result<Option<RunResult>, InterpreterError> {
+    if let Some(val) = pc.memory.read(pc.param_indices[0]).checked_add(pc.memory.read(pc.param_indices[1])) {
+        pc.store(pc.param_indices[2], val);
         pc.ip += 4;
-        None
+        Ok(None)
     } else {
-        Some(InterpreterError::Overflow)
+        Err(pc.fault(ErrorKind::Overflow, None))
     }
 }
 
-fn op_mul(pc: &mut Interpreter) -> Option<InterpreterError> {
-    if let Some(val) = pc.code[pc.param_indices[0] as usize].checked_mul(pc.code[pc.param_indices[1] as usize]) {
-        pc.code[pc.param_indices[2] as usize] = val;
+fn op_mul<I: InputSource, O: OutputSink>(pc: &mut Interpreter<I, O>) -> Result<Option<RunResult>, InterpreterError> {
+    if let Some(val) = pc.memory.read(pc.param_indices[0]).checked_mul(pc.memory.read(pc.param_indices[1])) {
+        pc.store(pc.param_indices[2], val);
         pc.ip += 4;
-        None
+        Ok(None)
     } else {
-        Some(InterpreterError::Overflow)
+        Err(pc.fault(ErrorKind::Overflow, None))
     }
 }
 
-fn op_in(pc: &mut Interpreter) -> Option<InterpreterError> {
-    if let Some(val) = pc.input_buffer.pop_front() {
-        pc.code[pc.param_indices[0]] = val;
+fn op_in<I: InputSource, O: OutputSink>(pc: &mut Interpreter<I, O>) -> Result<Option<RunResult>, InterpreterError> {
+    if let Some(val) = pc.input_buffer.next_input() {
+        pc.store(pc.param_indices[0], val);
         pc.ip += 2;
-        return None
+        return Ok(None)
     }
 
-    Some(InterpreterError::NoInputError)
+    Ok(Some(RunResult::NeedsInput))
 }
 
-fn op_out(pc: &mut Interpreter) -> Option<InterpreterError> {
-    let res = pc.code[pc.param_indices[0]];
+fn op_out<I: InputSource, O: OutputSink>(pc: &mut Interpreter<I, O>) -> Result<Option<RunResult>, InterpreterError> {
+    let res = pc.memory.read(pc.param_indices[0]);
 
-    pc.last_output = Some(res);
     pc.ip += 2;
-    None
+    Ok(Some(RunResult::Output(res)))
 }
 
-fn op_jit(pc: &mut Interpreter) -> Option<InterpreterError> {
-    if pc.code[pc.param_indices[0]] != 0 {
-        if let Some(&val) = pc.code.get(pc.param_indices[1]) {
-            pc.ip = val as usize;
-            None
-        } else {
-            Some(InterpreterError::JumpOutOfBounds)
-        }
+fn op_jit<I: InputSource, O: OutputSink>(pc: &mut Interpreter<I, O>) -> Result<Option<RunResult>, InterpreterError> {
+    if pc.memory.read(pc.param_indices[0]) != 0 {
+        jump(pc, pc.memory.read(pc.param_indices[1]))
     } else {
         pc.ip += 3;
-        None
+        Ok(None)
     }
 }
 
-fn op_jif(pc: &mut Interpreter) -> Option<InterpreterError> {
-    if pc.code[pc.param_indices[0]] == 0 {
-        if let Some(&val) = pc.code.get(pc.param_indices[1]) {
-            pc.ip = val as usize;
-            None
-        } else {
-            Some(InterpreterError::JumpOutOfBounds)
-        }
+fn op_jif<I: InputSource, O: OutputSink>(pc: &mut Interpreter<I, O>) -> Result<Option<RunResult>, InterpreterError> {
+    if pc.memory.read(pc.param_indices[0]) == 0 {
+        jump(pc, pc.memory.read(pc.param_indices[1]))
     } else {
         pc.ip += 3;
-        None
+        Ok(None)
+    }
+}
+
+fn jump<I: InputSource, O: OutputSink>(pc: &mut Interpreter<I, O>, target: VALUE) -> Result<Option<RunResult>, InterpreterError> {
+    if target < 0 {
+        return Err(pc.fault(ErrorKind::JumpOutOfBounds, Some(1)).at(target));
     }
+    pc.ip = target as usize;
+    Ok(None)
 }
 
-fn op_lt(pc: &mut Interpreter) -> Option<InterpreterError> {
-    pc.code[pc.param_indices[2]] = (pc.code[pc.param_indices[0]] < pc.code[pc.param_indices[1]]) as VALUE;
+fn op_lt<I: InputSource, O: OutputSink>(pc: &mut Interpreter<I, O>) -> Result<Option<RunResult>, InterpreterError> {
+    pc.store(pc.param_indices[2], (pc.memory.read(pc.param_indices[0]) < pc.memory.read(pc.param_indices[1])) as VALUE);
     pc.ip += 4;
-    None
+    Ok(None)
 }
 
-fn op_eq(pc: &mut Interpreter) -> Option<InterpreterError> {
-    pc.code[pc.param_indices[2]] = (pc.code[pc.param_indices[0]] == pc.code[pc.param_indices[1]]) as VALUE;
+fn op_eq<I: InputSource, O: OutputSink>(pc: &mut Interpreter<I, O>) -> Result<Option<RunResult>, InterpreterError> {
+    pc.store(pc.param_indices[2], (pc.memory.read(pc.param_indices[0]) == pc.memory.read(pc.param_indices[1])) as VALUE);
     pc.ip += 4;
-    None
+    Ok(None)
 }
 
-fn op_relb(pc: &mut Interpreter) -> Option<InterpreterError> {
-    pc.relative_base = (pc.relative_base as i64 + pc.code[pc.param_indices[0] as usize] as i64) as isize;
+fn op_relb<I: InputSource, O: OutputSink>(pc: &mut Interpreter<I, O>) -> Result<Option<RunResult>, InterpreterError> {
+    pc.adjust_relative_base(pc.memory.read(pc.param_indices[0]))?;
     pc.ip += 2;
-    None
+    Ok(None)
 }
-
@@ -0,0 +1,218 @@
+//! Machines on threads of their own, connected by `mpsc` channels as described by a `Graph`.
+//!
+//! Day 7's feedback loop, for one phase setting:
+//!
+//! ```ignore
+//! let mut graph = Graph::new();
+//! let machines: Vec<usize> = phases.iter().map(|&phase| graph.machine(&code, &[phase])).collect();
+//! for k in 0..machines.len() {
+//!     graph.connect(machines[k], machines[(k + 1) % machines.len()]);
+//! }
+//! graph.send(machines[0], 0);
+//! let signal = *graph.run()?[machines[4]].last().unwrap();
+//! ```
+
+use std::error::Error;
+use std::fmt::Display;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+use crate::io::{InputSource, OutputSink};
+use crate::memory::MemoryModel;
+use crate::src::{Interpreter, InterpreterError, RunResult, VALUE};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GraphError {
+    /// A machine faulted. The machines waiting for its output are stopped as deadlocked.
+    Fault { machine: usize, error: InterpreterError },
+    /// The machines which were waiting for input when every running machine was.
+    Deadlock { machines: Vec<usize> },
+}
+
+impl Display for GraphError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GraphError::Fault { machine, error } => write!(f, "Machine {} faulted: {}", machine, error),
+            GraphError::Deadlock { machines } => write!(f, "Deadlock, machines {:?} wait for input which never comes", machines),
+        }
+    }
+}
+
+impl Error for GraphError {}
+
+struct Node {
+    code: Vec<VALUE>,
+    input: Vec<VALUE>,
+    /// The machines receiving the output.
+    targets: Vec<usize>,
+}
+
+/// Machines and the connections between them, from the output of a machine to the input of
+/// others. Every output goes to all connected machines.
+#[derive(Default)]
+pub struct Graph {
+    nodes: Vec<Node>,
+}
+
+impl Graph {
+    pub fn new() -> Graph {
+        Graph::default()
+    }
+
+    /// Adds a machine running `code`, which first reads `prefix`. Returns its id, which is
+    /// the number of machines added before.
+    pub fn machine(&mut self, code: &[VALUE], prefix: &[VALUE]) -> usize {
+        self.nodes.push(Node { code: code.to_owned(), input: prefix.to_owned(), targets: vec![] });
+        self.nodes.len() - 1
+    }
+
+    /// Feeds every output of `from` into the input of `to`.
+    ///
+    /// Panics if either machine does not exist.
+    pub fn connect(&mut self, from: usize, to: usize) {
+        assert!(to < self.nodes.len(), "No machine {}", to);
+        self.nodes[from].targets.push(to);
+    }
+
+    /// Queues `val` as input of `machine`, after the values sent so far.
+    pub fn send(&mut self, machine: usize, val: VALUE) {
+        self.nodes[machine].input.push(val);
+    }
+
+    /// One machine per phase setting, connected in a ring, the first one receiving `signal`.
+    pub fn ring(code: &[VALUE], phases: &[VALUE], signal: VALUE) -> Graph {
+        let mut graph = Graph::new();
+        for &phase in phases {
+            graph.machine(code, &[phase]);
+        }
+        for k in 0..phases.len() {
+            graph.connect(k, (k + 1) % phases.len());
+        }
+        if !phases.is_empty() {
+            graph.send(0, signal);
+        }
+        graph
+    }
+
+    /// Runs every machine on a thread of its own until all of them have halted, and returns
+    /// the outputs of each machine.
+    ///
+    /// Once every running machine waits for input, the waiting machines are stopped and the
+    /// run fails with `GraphError::Deadlock`, unless a machine faulted before.
+    pub fn run(self) -> Result<Vec<Vec<VALUE>>, GraphError> {
+        let n = self.nodes.len();
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State { running: vec![true; n], waiting: vec![false; n], deadlock: false }),
+            changed: Condvar::new(),
+        });
+        let (senders, receivers): (Vec<Sender<VALUE>>, Vec<Receiver<VALUE>>) = (0..n).map(|_| mpsc::channel()).unzip();
+        for (node, tx) in self.nodes.iter().zip(&senders) {
+            for &val in &node.input {
+                tx.send(val).unwrap();
+            }
+        }
+
+        let results: Vec<_> = thread::scope(|scope| {
+            let threads: Vec<_> = self.nodes.into_iter().zip(receivers).enumerate().map(|(id, (node, rx))| {
+                let input = Input { id, rx, shared: Arc::clone(&shared) };
+                let targets = node.targets.iter().map(|&k| (k, senders[k].clone())).collect();
+                let output = Output { targets, shared: Arc::clone(&shared), values: vec![] };
+                scope.spawn(move || {
+                    let mut pc = Interpreter::with_io(node.code, MemoryModel::Dense, input, output);
+                    let res = pc.run();
+                    pc.input_buffer.halt();
+                    (res, pc.output.values)
+                })
+            }).collect();
+            threads.into_iter().map(|thread| thread.join().unwrap()).collect()
+        });
+
+        let mut outputs = vec![];
+        let mut deadlocked = vec![];
+        for (machine, (res, values)) in results.into_iter().enumerate() {
+            match res {
+                Ok(RunResult::NeedsInput) => deadlocked.push(machine),
+                Ok(_) => {},
+                Err(error) => return Err(GraphError::Fault { machine, error }),
+            }
+            outputs.push(values);
+        }
+        if !deadlocked.is_empty() {
+            return Err(GraphError::Deadlock { machines: deadlocked });
+        }
+        Ok(outputs)
+    }
+}
+
+struct State {
+    running: Vec<bool>,
+    /// Whether each machine waits for input.
+    waiting: Vec<bool>,
+    deadlock: bool,
+}
+
+/// The state of all machines, which is notified whenever a value is sent or a machine stops.
+struct Shared {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+/// Reads from the channel of a machine, detecting when every running machine waits.
+struct Input {
+    id: usize,
+    rx: Receiver<VALUE>,
+    shared: Arc<Shared>,
+}
+
+impl Input {
+    /// Marks the machine as stopped, once it has halted or faulted.
+    fn halt(&self) {
+        self.shared.state.lock().unwrap().running[self.id] = false;
+        self.shared.changed.notify_all();
+    }
+}
+
+impl InputSource for Input {
+    fn next_input(&mut self) -> Option<VALUE> {
+        let mut state = self.shared.state.lock().unwrap();
+        loop {
+            // Values are sent while holding the lock, so none can be missed
+            if let Ok(val) = self.rx.try_recv() {
+                state.waiting[self.id] = false;
+                return Some(val);
+            }
+            state.waiting[self.id] = true;
+            if !state.deadlock && state.running.iter().zip(&state.waiting).all(|(&running, &waiting)| !running || waiting) {
+                state.deadlock = true;
+                self.shared.changed.notify_all();
+            }
+            if state.deadlock {
+                return None;
+            }
+            state = self.shared.changed.wait(state).unwrap();
+        }
+    }
+}
+
+/// Sends every output to the channels of the connected machines, keeping a copy.
+struct Output {
+    targets: Vec<(usize, Sender<VALUE>)>,
+    shared: Arc<Shared>,
+    values: Vec<VALUE>,
+}
+
+impl OutputSink for Output {
+    fn emit(&mut self, val: VALUE) {
+        let mut state = self.shared.state.lock().unwrap();
+        for (k, tx) in &self.targets {
+            // A machine which has halted does not read anymore
+            if tx.send(val).is_ok() {
+                state.waiting[*k] = false;
+            }
+        }
+        drop(state);
+        self.shared.changed.notify_all();
+        self.values.push(val);
+    }
+}
@@ -0,0 +1,101 @@
+//! Records of every executed instruction, see `Interpreter::set_tracer`.
+
+use std::fmt::Display;
+use std::io::Write;
+use std::sync::mpsc::Sender;
+
+use crate::src::VALUE;
+
+/// A single executed instruction. Instructions which fail are not recorded.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceRecord {
+    pub ip: usize,
+    /// The raw instruction, including parameter modes.
+    pub opcode: VALUE,
+    pub name: &'static str,
+    /// The resolved addresses of the parameters.
+    pub param_indices: Vec<usize>,
+    /// The values of the parameters before the instruction executed.
+    pub reads: Vec<VALUE>,
+    /// The address and value written by the instruction, if any.
+    pub write: Option<(usize, VALUE)>,
+    /// The relative base before the instruction executed.
+    pub relative_base: isize,
+}
+
+impl TraceRecord {
+    /// The record as a single line JSON object.
+    pub fn to_json(&self) -> String {
+        let list = |values: Vec<String>| values.join(",");
+        format!(
+            "{{\"ip\":{},\"opcode\":{},\"name\":{},\"param_indices\":[{}],\"reads\":[{}],\"write\":{},\"relative_base\":{}}}",
+            self.ip,
+            self.opcode,
+            json_string(self.name),
+            list(self.param_indices.iter().map(|x| x.to_string()).collect()),
+            list(self.reads.iter().map(|x| x.to_string()).collect()),
+            match self.write {
+                Some((address, val)) => format!("{{\"address\":{},\"value\":{}}}", address, val),
+                None => "null".to_owned(),
+            },
+            self.relative_base,
+        )
+    }
+}
+
+/// `text` as a quoted JSON string.
+fn json_string(text: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in text.chars() {
+        match c {
+            '"' => { quoted.push_str("\\\""); },
+            '\\' => { quoted.push_str("\\\\"); },
+            '\n' => { quoted.push_str("\\n"); },
+            c if c.is_control() => { quoted.push_str(&format!("\\u{:04x}", c as u32)); },
+            c => { quoted.push(c); },
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+impl Display for TraceRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:>6}: {:>6} {:<20} indices {:?} reads {:?}", self.ip, self.opcode, self.name, self.param_indices, self.reads)?;
+        if let Some((address, val)) = self.write {
+            write!(f, " writes [{}] = {}", address, val)?;
+        }
+        write!(f, " rb {}", self.relative_base)
+    }
+}
+
+/// Receives a record of every instruction executed by a traced `Interpreter`.
+pub trait TraceSink {
+    fn record(&mut self, record: &TraceRecord);
+}
+
+/// Writes one human readable line per instruction.
+pub struct TextTracer<W: Write>(pub W);
+
+impl<W: Write> TraceSink for TextTracer<W> {
+    fn record(&mut self, record: &TraceRecord) {
+        // Tracing must never change the behaviour of the program, hence errors are ignored.
+        let _ = writeln!(self.0, "{}", record);
+    }
+}
+
+/// Writes one JSON object per line and instruction.
+pub struct JsonTracer<W: Write>(pub W);
+
+impl<W: Write> TraceSink for JsonTracer<W> {
+    fn record(&mut self, record: &TraceRecord) {
+        let _ = writeln!(self.0, "{}", record.to_json());
+    }
+}
+
+/// Sends the records to a channel, eg. to collect them in memory.
+impl TraceSink for Sender<TraceRecord> {
+    fn record(&mut self, record: &TraceRecord) {
+        let _ = self.send(record.clone());
+    }
+}
@@ -0,0 +1,42 @@
+//! Runs on a counting allocator of its own, which would otherwise count for every test.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use intcode::loader;
+use intcode::src::{Interpreter, RunResult};
+
+/// Counts the allocations of every thread, to keep the hot path of the interpreter free of them.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[test]
+fn step_allocations() {
+    let mut pc = Interpreter::new(loader::parse(include_str!("../data/day9.txt")).unwrap(), vec![2].into());
+    // The dense memory grows on the first writes beyond the code
+    for _ in 0..1000 {
+        pc.step().unwrap();
+    }
+
+    let before = ALLOCATIONS.with(Cell::get);
+    assert_eq!(pc.step_loop(), Ok(RunResult::Output(83239)));
+    assert_eq!(pc.step_loop(), Ok(RunResult::Halted));
+    assert_eq!(ALLOCATIONS.with(Cell::get), before);
+}